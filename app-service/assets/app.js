@@ -7,9 +7,17 @@ logoutLink.addEventListener("click", (e) => {
 
     let url = logoutLink.href;
 
+    // The auth service wants its CSRF token back, which it sets for the whole host
+    const csrfCookie = document.cookie
+        .split("; ")
+        .find(cookie => /^(__Host-|__Secure-)?csrf_token=/.test(cookie));
+
     fetch(url, {
         method: 'POST',
         credentials: 'include', // This will include cookies in the request
+        headers: {
+            'X-CSRF-Token': csrfCookie === undefined ? "" : csrfCookie.substring(csrfCookie.indexOf("=") + 1),
+        },
     }).then(response => {
         if (response.ok) {
             loginLink.style.display = "block";
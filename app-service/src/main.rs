@@ -47,7 +47,9 @@ async fn root() -> impl IntoResponse {
 }
 
 async fn protected(jar: CookieJar) -> impl IntoResponse {
-    let jwt_cookie = match jar.get("jwt") {
+    // Matches the auth service's cookie prefix setting, e.g. `__Host-jwt`
+    let cookie_name = env::var("AUTH_COOKIE_NAME").unwrap_or("jwt".to_owned());
+    let jwt_cookie = match jar.get(&cookie_name) {
         Some(cookie) => cookie,
         None => {
             return StatusCode::UNAUTHORIZED.into_response();
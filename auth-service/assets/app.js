@@ -4,7 +4,20 @@ const signupSection = document.getElementById("signup-section");
 
 const signupLink = document.getElementById("signup-link");
 const twoFALoginLink = document.getElementById("2fa-login-link");
+const twoFAResendLink = document.getElementById("2fa-resend-link");
 const signupLoginLink = document.getElementById("signup-login-link");
+const magicLinkLink = document.getElementById("magic-link-link");
+
+// Pages like the OAuth consent screen send users here with a `next` parameter
+// so they can be brought back once they've logged in.
+function redirectAfterLogin() {
+    const next = new URLSearchParams(window.location.search).get("next");
+    if (next !== null && next.startsWith("/") && !next.startsWith("//")) {
+        window.location.href = next;
+        return true;
+    }
+    return false;
+}
 
 signupLink.addEventListener("click", (e) => {
     e.preventDefault();
@@ -46,6 +59,7 @@ loginButton.addEventListener("click", (e) => {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
         },
         body: JSON.stringify({ email, password }),
     }).then(response => {
@@ -62,10 +76,13 @@ loginButton.addEventListener("click", (e) => {
             twoFASection.style.display = "block";
             signupSection.style.display = "none";
             loginErrAlter.style.display = "none";
-        } else if (response.status === 200) {
+        } else if (response.ok) {
             loginForm.email.value = "";
             loginForm.password.value = "";
             loginErrAlter.style.display = "none";
+            if (redirectAfterLogin()) {
+                return;
+            }
             alert("You have successfully logged in.");
         } else {
             response.json().then(data => {
@@ -81,6 +98,37 @@ loginButton.addEventListener("click", (e) => {
     });
 });
 
+// Passwordless login: the emailed link logs the user in when opened
+magicLinkLink.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const email = loginForm.email.value;
+
+    fetch('/login/magic-link', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
+        },
+        body: JSON.stringify({ email }),
+    }).then(response => {
+        if (response.ok) {
+            loginErrAlter.style.display = "none";
+            response.json().then(data => alert(data.message));
+        } else {
+            response.json().then(data => {
+                let error_msg = data.error;
+                if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+                    loginErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                    loginErrAlter.style.display = "block";
+                } else {
+                    loginErrAlter.style.display = "none";
+                }
+            });
+        }
+    });
+});
+
 const signupForm = document.getElementById("signup-form");
 const signupButton = document.getElementById("signup-form-submit");
 const signupErrAlter = document.getElementById("signup-err-alert");
@@ -89,13 +137,15 @@ signupButton.addEventListener("click", (e) => {
     e.preventDefault();
 
     const email = signupForm.email.value;
-    const password = signupForm.password.value;
+    // An empty password creates a passwordless account
+    const password = signupForm.password.value === "" ? null : signupForm.password.value;
     const requires2FA = signupForm.twoFA.checked;
 
     fetch('/signup', {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
         },
         body: JSON.stringify({ email, password, requires2FA }),
     }).then(response => {
@@ -104,7 +154,17 @@ signupButton.addEventListener("click", (e) => {
             signupForm.password.value = "";
             signupForm.twoFA.checked = false;
             signupErrAlter.style.display = "none";
-            alert("You have successfully created a user.");
+            response.json().then(data => {
+                // Recovery codes are only ever shown here, so the user has to save them now
+                if (data.recoveryCodes) {
+                    alert("You have successfully created a user.\n\n" +
+                        "Save these recovery codes somewhere safe. Each one can be used once " +
+                        "instead of a 2FA code if you lose access to your email:\n\n" +
+                        data.recoveryCodes.join("\n"));
+                } else {
+                    alert("You have successfully created a user.");
+                }
+            });
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
             signupSection.style.display = "none";
@@ -126,25 +186,63 @@ const TwoFAForm = document.getElementById("2fa-form");
 const TwoFAButton = document.getElementById("2fa-form-submit");
 const TwoFAErrAlter = document.getElementById("2fa-err-alert");
 
+// The login attempt ID stays the same, only the emailed code changes
+twoFAResendLink.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const email = TwoFAForm.email.value;
+    const loginAttemptId = TwoFAForm.login_attempt_id.value;
+
+    fetch('/resend-2fa', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
+        },
+        body: JSON.stringify({ email, loginAttemptId }),
+    }).then(response => {
+        if (response.ok) {
+            TwoFAErrAlter.style.display = "none";
+            alert("A new code has been sent to your email.");
+        } else {
+            response.json().then(data => {
+                let error_msg = data.error;
+                if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+                    TwoFAErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                    TwoFAErrAlter.style.display = "block";
+                } else {
+                    TwoFAErrAlter.style.display = "none";
+                }
+            });
+        }
+    });
+});
+
 TwoFAButton.addEventListener("click", (e) => {
     e.preventDefault();
 
     const email = TwoFAForm.email.value;
     const loginAttemptId = TwoFAForm.login_attempt_id.value;
     const TwoFACode = TwoFAForm.email_code.value;
+    const rememberDevice = TwoFAForm.remember_device.checked;
 
     fetch('/verify-2fa', {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
         },
-        body: JSON.stringify({ email, loginAttemptId, "2FACode": TwoFACode }),
+        body: JSON.stringify({ email, loginAttemptId, "2FACode": TwoFACode, rememberDevice }),
     }).then(response => {
         if (response.ok) {
             TwoFAForm.email.value = "";
             TwoFAForm.email_code.value = "";
             TwoFAForm.login_attempt_id.value = "";
+            TwoFAForm.remember_device.checked = false;
             TwoFAErrAlter.style.display = "none";
+            if (redirectAfterLogin()) {
+                return;
+            }
             alert("You have successfully logged in.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
@@ -0,0 +1,50 @@
+const params = new URLSearchParams(window.location.search);
+
+const consentClient = document.getElementById("consent-client");
+const consentScopes = document.getElementById("consent-scopes");
+const consentErrAlter = document.getElementById("consent-err-alert");
+
+consentClient.textContent = params.get("client_id");
+(params.get("scope") || "").split(" ").filter(scope => scope !== "").forEach(scope => {
+    const item = document.createElement("li");
+    item.textContent = scope;
+    consentScopes.appendChild(item);
+});
+
+function sendConsent(approve) {
+    const request = {};
+    for (const [key, value] of params.entries()) {
+        request[key] = value;
+    }
+    request.approve = approve;
+
+    fetch('/oauth/authorize', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
+        },
+        body: JSON.stringify(request),
+    }).then(response => {
+        response.json().then(data => {
+            if (response.ok) {
+                window.location.href = data.redirectTo;
+            } else if (response.status === 401) {
+                window.location.href = "/?next=" + encodeURIComponent("/oauth/authorize" + window.location.search);
+            } else {
+                consentErrAlter.innerHTML = `<span><strong>Error: </strong>${data.error_description}</span>`;
+                consentErrAlter.style.display = "block";
+            }
+        });
+    });
+}
+
+document.getElementById("consent-allow").addEventListener("click", (e) => {
+    e.preventDefault();
+    sendConsent(true);
+});
+
+document.getElementById("consent-deny").addEventListener("click", (e) => {
+    e.preventDefault();
+    sendConsent(false);
+});
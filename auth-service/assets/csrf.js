@@ -0,0 +1,9 @@
+// The auth service hands out a CSRF token in a cookie along with the auth cookie. Requests that
+// change something for the logged in user have to send it back in the X-CSRF-Token header, which
+// pages on other sites can't do since they can't read the cookie.
+function csrfToken() {
+    const cookie = document.cookie
+        .split("; ")
+        .find(cookie => /^(__Host-|__Secure-)?csrf_token=/.test(cookie));
+    return cookie === undefined ? "" : cookie.substring(cookie.indexOf("=") + 1);
+}
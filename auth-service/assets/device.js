@@ -0,0 +1,53 @@
+const params = new URLSearchParams(window.location.search);
+
+const deviceForm = document.getElementById("device-form");
+const deviceErrAlter = document.getElementById("device-err-alert");
+const deviceSuccessAlert = document.getElementById("device-success-alert");
+
+// The verification page needs a logged in user, including the 2FA step
+fetch('/profile').then(response => {
+    if (!response.ok) {
+        window.location.href = "/?next=" + encodeURIComponent("/device.html" + window.location.search);
+    }
+});
+
+if (params.get("user_code") !== null) {
+    deviceForm.user_code.value = params.get("user_code");
+}
+
+function sendVerification(approve) {
+    const userCode = deviceForm.user_code.value;
+
+    fetch('/oauth/device/verify', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
+        },
+        body: JSON.stringify({ userCode, approve }),
+    }).then(response => {
+        response.json().then(data => {
+            if (response.ok) {
+                deviceForm.style.display = "none";
+                deviceErrAlter.style.display = "none";
+                deviceSuccessAlert.textContent = approve
+                    ? "Your device is connected. You can return to it now."
+                    : "The device was denied access.";
+                deviceSuccessAlert.style.display = "block";
+            } else {
+                deviceErrAlter.innerHTML = `<span><strong>Error: </strong>${data.error}</span>`;
+                deviceErrAlter.style.display = "block";
+            }
+        });
+    });
+}
+
+document.getElementById("device-approve").addEventListener("click", (e) => {
+    e.preventDefault();
+    sendVerification(true);
+});
+
+document.getElementById("device-deny").addEventListener("click", (e) => {
+    e.preventDefault();
+    sendVerification(false);
+});
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS oauth_clients(
+   client_id TEXT NOT NULL PRIMARY KEY,
+   secret_hash TEXT,
+   redirect_uris TEXT NOT NULL DEFAULT '[]',
+   allowed_scopes TEXT NOT NULL DEFAULT ''
+);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS audit_events(
+   id INTEGER PRIMARY KEY AUTOINCREMENT,
+   kind TEXT NOT NULL,
+   email TEXT NOT NULL,
+   actor TEXT,
+   ip TEXT,
+   details TEXT,
+   -- Microseconds since the Unix epoch
+   at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_email_at ON audit_events(email, at);
+CREATE INDEX IF NOT EXISTS audit_events_at ON audit_events(at);
+
+-- The audit log is append-only
+CREATE TRIGGER IF NOT EXISTS audit_events_no_update BEFORE UPDATE ON audit_events
+BEGIN
+   SELECT RAISE(ABORT, 'audit events cannot be changed');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_events_no_delete BEFORE DELETE ON audit_events
+BEGIN
+   SELECT RAISE(ABORT, 'audit events cannot be deleted');
+END;
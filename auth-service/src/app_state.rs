@@ -1,13 +1,65 @@
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::{HashmapTwoFACodeStore, HashmapUserStore, HashsetBannedTokenStore, MockEmailClient};
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::{
+	HashmapAuthorizationCodeStore, HashmapClientStore, HashmapDeviceCodeStore, HashmapPasskeyStore,
+	HashmapRecoveryCodeStore, HashmapSessionStore, HashmapTrustedDeviceStore, HashmapTwoFACodeStore, HashmapUserStore,
+	HashsetBannedTokenStore, MockEmailClient, MockSmsClient, VecAuditSink, WebhookClient,
+};
+use crate::config::Config;
+use crate::utils::metrics::Metrics;
+use crate::utils::oidc::IdTokenKey;
+use crate::utils::rate_limit::RateLimiter;
+use crate::domain::{
+	AuditSink, AuthorizationCodeStore, BannedTokenStore, ClientStore, DeviceCodeStore, EmailClient, PasskeyStore,
+	RecoveryCodeStore, SessionStore, SmsClient, TrustedDeviceStore, TwoFACodeStore, UserStore,
+};
 
 pub type UserStoreType = Arc<RwLock<Box<dyn UserStore + Send + Sync>>>;
 pub type BannedTokenStoreType = Arc<RwLock<Box<dyn BannedTokenStore + Send + Sync>>>;
 pub type TwoFACodeStoreType = Arc<RwLock<Box<dyn TwoFACodeStore + Send + Sync>>>;
 pub type EmailClientType = Arc<RwLock<Box<dyn EmailClient + Send + Sync>>>;
+pub type SmsClientType = Arc<RwLock<Box<dyn SmsClient + Send + Sync>>>;
+pub type AuthorizationCodeStoreType = Arc<RwLock<Box<dyn AuthorizationCodeStore + Send + Sync>>>;
+pub type ClientStoreType = Arc<RwLock<Box<dyn ClientStore + Send + Sync>>>;
+pub type DeviceCodeStoreType = Arc<RwLock<Box<dyn DeviceCodeStore + Send + Sync>>>;
+pub type SessionStoreType = Arc<RwLock<Box<dyn SessionStore + Send + Sync>>>;
+pub type RecoveryCodeStoreType = Arc<RwLock<Box<dyn RecoveryCodeStore + Send + Sync>>>;
+pub type TrustedDeviceStoreType = Arc<RwLock<Box<dyn TrustedDeviceStore + Send + Sync>>>;
+pub type PasskeyStoreType = Arc<RwLock<Box<dyn PasskeyStore + Send + Sync>>>;
+pub type AuditSinkType = Arc<RwLock<Box<dyn AuditSink + Send + Sync>>>;
+
+// Limits on how often the code of a pending 2FA login can be resent
+#[derive(Clone, Debug)]
+pub struct Resend2FAPolicy {
+	pub cooldown: chrono::Duration,
+	pub max_resends: u32,
+}
+
+impl Default for Resend2FAPolicy {
+	fn default() -> Self {
+		Self {
+			cooldown: chrono::Duration::seconds(30),
+			max_resends: 3,
+		}
+	}
+}
+
+// How many wrong passwords in a row lock an account, and for how long
+#[derive(Clone, Debug)]
+pub struct LoginLockoutPolicy {
+	pub max_failed_attempts: u32,
+	pub lockout: chrono::Duration,
+}
+
+impl Default for LoginLockoutPolicy {
+	fn default() -> Self {
+		Self {
+			max_failed_attempts: 5,
+			lockout: chrono::Duration::minutes(15),
+		}
+	}
+}
 
 #[derive(Clone)]
 pub struct AppState {
@@ -15,6 +67,25 @@ pub struct AppState {
 	pub banned_token_store: BannedTokenStoreType,
 	pub two_fa_code_store: TwoFACodeStoreType,
 	pub email_client: EmailClientType,
+	pub authorization_code_store: AuthorizationCodeStoreType,
+	pub client_store: ClientStoreType,
+	pub device_code_store: DeviceCodeStoreType,
+	pub session_store: SessionStoreType,
+	pub recovery_code_store: RecoveryCodeStoreType,
+	pub trusted_device_store: TrustedDeviceStoreType,
+	pub passkey_store: PasskeyStoreType,
+	pub audit_sink: AuditSinkType,
+	pub resend_2fa_policy: Resend2FAPolicy,
+	pub login_lockout_policy: LoginLockoutPolicy,
+	// Limits the device authorizations each IP address starts, as starting one needs no login
+	pub device_authorization_limiter: RateLimiter,
+	pub sms_client: SmsClientType,
+	// Shared HTTP client, e.g. for the SMS gateway
+	pub http_client: reqwest::Client,
+	pub webhook_client: WebhookClient,
+	pub metrics: Metrics,
+	pub config: Arc<Config>,
+	pub id_token_key: Arc<IdTokenKey>,
 }
 
 impl AppState {
@@ -29,8 +100,100 @@ impl AppState {
 			banned_token_store,
 			two_fa_code_store,
 			email_client,
+			authorization_code_store: Arc::new(RwLock::new(Box::new(HashmapAuthorizationCodeStore::default()))),
+			client_store: Arc::new(RwLock::new(Box::new(HashmapClientStore::default()))),
+			device_code_store: Arc::new(RwLock::new(Box::new(HashmapDeviceCodeStore::default()))),
+			session_store: Arc::new(RwLock::new(Box::new(HashmapSessionStore::default()))),
+			recovery_code_store: Arc::new(RwLock::new(Box::new(HashmapRecoveryCodeStore::default()))),
+			trusted_device_store: Arc::new(RwLock::new(Box::new(HashmapTrustedDeviceStore::default()))),
+			passkey_store: Arc::new(RwLock::new(Box::new(HashmapPasskeyStore::default()))),
+			audit_sink: Arc::new(RwLock::new(Box::new(VecAuditSink::default()))),
+			resend_2fa_policy: Resend2FAPolicy::default(),
+			login_lockout_policy: LoginLockoutPolicy::default(),
+			device_authorization_limiter: RateLimiter::new(10, std::time::Duration::from_secs(60)),
+			sms_client: Arc::new(RwLock::new(Box::new(MockSmsClient::default()))),
+			http_client: reqwest::Client::new(),
+			webhook_client: WebhookClient::default(),
+			metrics: Metrics::new(),
+			config: Arc::new(Config::default()),
+			id_token_key: Arc::new(IdTokenKey::generate()),
 		}
 	}
+
+	pub fn with_config(mut self, config: Config) -> Self {
+		self.config = Arc::new(config);
+		self
+	}
+
+	pub fn with_id_token_key(mut self, id_token_key: IdTokenKey) -> Self {
+		self.id_token_key = Arc::new(id_token_key);
+		self
+	}
+
+	pub fn with_webhook_client(mut self, webhook_client: WebhookClient) -> Self {
+		self.webhook_client = webhook_client;
+		self
+	}
+
+	pub fn with_authorization_code_store(mut self, authorization_code_store: AuthorizationCodeStoreType) -> Self {
+		self.authorization_code_store = authorization_code_store;
+		self
+	}
+
+	pub fn with_client_store(mut self, client_store: ClientStoreType) -> Self {
+		self.client_store = client_store;
+		self
+	}
+
+	pub fn with_device_code_store(mut self, device_code_store: DeviceCodeStoreType) -> Self {
+		self.device_code_store = device_code_store;
+		self
+	}
+
+	pub fn with_session_store(mut self, session_store: SessionStoreType) -> Self {
+		self.session_store = session_store;
+		self
+	}
+
+	pub fn with_recovery_code_store(mut self, recovery_code_store: RecoveryCodeStoreType) -> Self {
+		self.recovery_code_store = recovery_code_store;
+		self
+	}
+
+	pub fn with_trusted_device_store(mut self, trusted_device_store: TrustedDeviceStoreType) -> Self {
+		self.trusted_device_store = trusted_device_store;
+		self
+	}
+
+	pub fn with_passkey_store(mut self, passkey_store: PasskeyStoreType) -> Self {
+		self.passkey_store = passkey_store;
+		self
+	}
+
+	pub fn with_audit_sink(mut self, audit_sink: AuditSinkType) -> Self {
+		self.audit_sink = audit_sink;
+		self
+	}
+
+	pub fn with_sms_client(mut self, sms_client: SmsClientType) -> Self {
+		self.sms_client = sms_client;
+		self
+	}
+
+	pub fn with_resend_2fa_policy(mut self, resend_2fa_policy: Resend2FAPolicy) -> Self {
+		self.resend_2fa_policy = resend_2fa_policy;
+		self
+	}
+
+	pub fn with_login_lockout_policy(mut self, login_lockout_policy: LoginLockoutPolicy) -> Self {
+		self.login_lockout_policy = login_lockout_policy;
+		self
+	}
+
+	pub fn with_device_authorization_limiter(mut self, device_authorization_limiter: RateLimiter) -> Self {
+		self.device_authorization_limiter = device_authorization_limiter;
+		self
+	}
 }
 
 impl Default for AppState {
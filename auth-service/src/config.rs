@@ -0,0 +1,629 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum_extra::extract::cookie::SameSite;
+use serde::Deserialize;
+
+use crate::domain::Email;
+use crate::utils::constants::{env, CSRF_COOKIE_NAME, JWT_COOKIE_NAME, TRUSTED_DEVICE_COOKIE_NAME};
+use crate::utils::crypto::generate_secret;
+
+// Where the config file is looked for when `CONFIG_PATH` isn't set. It's fine for it not to exist.
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+// Everything the service can be configured with. `Config::load` layers the TOML file and
+// environment variables over the defaults and validates the result.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	#[serde(default)]
+	pub server: ServerConfig,
+	#[serde(default)]
+	pub auth: AuthConfig,
+	#[serde(default)]
+	pub cookies: CookieConfig,
+	#[serde(default)]
+	pub database: DatabaseConfig,
+	// Without a gateway, SMS codes are only printed like the mock email client does
+	#[serde(default)]
+	pub sms_gateway: Option<SmsGatewayConfig>,
+	#[serde(default)]
+	pub audit: AuditConfig,
+}
+
+// A config built in code, e.g. for tests, signs with a random secret. Loaded configs have to set one,
+// so tokens stay valid across restarts and instances.
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			server: ServerConfig::default(),
+			auth: AuthConfig {
+				jwt_secret: generate_secret(),
+				..AuthConfig::default()
+			},
+			cookies: CookieConfig::default(),
+			database: DatabaseConfig::default(),
+			sms_gateway: None,
+			audit: AuditConfig::default(),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+	pub address: String,
+	// Origins allowed to make credentialed cross-origin requests
+	pub allowed_origins: Vec<String>,
+	// How long requests in flight and background tasks get to finish once shutting down
+	pub drain_timeout_seconds: u64,
+	// Serve HTTPS instead of plain HTTP
+	pub tls: Option<TlsConfig>,
+}
+
+impl ServerConfig {
+	pub fn drain_timeout(&self) -> Duration {
+		Duration::from_secs(self.drain_timeout_seconds)
+	}
+}
+
+impl Default for ServerConfig {
+	fn default() -> Self {
+		Self {
+			address: "0.0.0.0:3000".to_string(),
+			allowed_origins: vec!["http://localhost:3000".to_string(), "http://127.0.0.1:3000".to_string()],
+			drain_timeout_seconds: 30,
+			tls: None,
+		}
+	}
+}
+
+// PEM files of the certificate chain and its private key. They're checked for changes every
+// `reload_interval_seconds` and swapped in without a restart, e.g. after a renewal.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+	pub cert_path: PathBuf,
+	pub key_path: PathBuf,
+	#[serde(default = "default_tls_reload_interval")]
+	pub reload_interval_seconds: u64,
+}
+
+impl TlsConfig {
+	pub fn reload_interval(&self) -> Duration {
+		Duration::from_secs(self.reload_interval_seconds)
+	}
+}
+
+fn default_tls_reload_interval() -> u64 {
+	60
+}
+
+// Attributes of the auth cookie
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+	pub prefix: CookiePrefix,
+	// Shares the cookie with subdomains, e.g. `example.com` for single sign-on across `*.example.com`.
+	// Without it only the service's own host gets the cookie.
+	pub domain: Option<String>,
+	pub same_site: CookieSameSite,
+	// Whether cookies are marked `Secure`, so browsers only send them over HTTPS
+	pub secure: SecureCookies,
+}
+
+// Name prefixes browsers enforce the cookie's attributes for, so they can't be overwritten
+// by a less trusted subdomain or over plain HTTP
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CookiePrefix {
+	#[default]
+	None,
+	// `__Secure-`: only set over HTTPS with `Secure`
+	Secure,
+	// `__Host-`: like `__Secure-`, and only for the exact host with path `/`
+	Host,
+}
+
+impl CookiePrefix {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			CookiePrefix::None => "",
+			CookiePrefix::Secure => "__Secure-",
+			CookiePrefix::Host => "__Host-",
+		}
+	}
+}
+
+impl FromStr for CookiePrefix {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"none" => Ok(CookiePrefix::None),
+			"secure" => Ok(CookiePrefix::Secure),
+			"host" => Ok(CookiePrefix::Host),
+			_ => Err(()),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieSameSite {
+	// Not even sent on top-level navigations from other sites, so following a link arrives logged out
+	Strict,
+	// Sent on top-level navigations from other sites, but not with their requests
+	#[default]
+	Lax,
+	// Sent with requests from other sites too, which browsers only allow with `Secure`
+	None,
+}
+
+impl From<CookieSameSite> for SameSite {
+	fn from(same_site: CookieSameSite) -> Self {
+		match same_site {
+			CookieSameSite::Strict => SameSite::Strict,
+			CookieSameSite::Lax => SameSite::Lax,
+			CookieSameSite::None => SameSite::None,
+		}
+	}
+}
+
+impl FromStr for CookieSameSite {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"strict" => Ok(CookieSameSite::Strict),
+			"lax" => Ok(CookieSameSite::Lax),
+			"none" => Ok(CookieSameSite::None),
+			_ => Err(()),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecureCookies {
+	// Secure when serving HTTPS
+	#[default]
+	Auto,
+	// E.g. behind a proxy that terminates TLS
+	Always,
+	Never,
+}
+
+impl FromStr for SecureCookies {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"auto" => Ok(SecureCookies::Auto),
+			"always" => Ok(SecureCookies::Always),
+			"never" => Ok(SecureCookies::Never),
+			_ => Err(()),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+	// Signs every token the service hands out
+	pub jwt_secret: String,
+	// Public URL of the service, used as the token issuer and passkey origin
+	pub issuer: String,
+	// How long auth tokens are valid for
+	pub token_ttl_seconds: i64,
+	// Sessions not used for this long are ended, invalidating their tokens
+	pub session_idle_ttl_seconds: i64,
+	pub trusted_device_ttl_days: i64,
+	// Accounts with one of these emails get the admin role once they verified the email, so there's a first admin to hand out roles
+	pub admin_emails: Vec<String>,
+	// PEM file of the P-256 key signing ID tokens. Without one a key is generated on every start.
+	pub id_token_key_path: Option<PathBuf>,
+}
+
+impl Default for AuthConfig {
+	fn default() -> Self {
+		Self {
+			jwt_secret: String::new(),
+			issuer: "http://localhost:3000".to_string(),
+			token_ttl_seconds: 600, // 10 minutes
+			session_idle_ttl_seconds: 3600, // 1 hour
+			trusted_device_ttl_days: 30,
+			admin_emails: Vec::new(),
+			id_token_key_path: None,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+	pub url: String,
+}
+
+// HTTP SMS gateway, which gets `POST {url}/messages` with the API key as a bearer token
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmsGatewayConfig {
+	pub url: String,
+	pub api_key: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+	// The audit log goes to the database unless a file was configured for log tooling to pick up
+	pub log_path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+	ReadFile { path: PathBuf, error: std::io::Error },
+	ParseFile { path: PathBuf, error: toml::de::Error },
+	InvalidEnvVar { name: &'static str, value: String },
+	MissingEnvVar { name: &'static str },
+	Invalid { field: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ConfigError::ReadFile { path, error } => write!(f, "failed to read {}: {error}", path.display()),
+			ConfigError::ParseFile { path, error } => write!(f, "failed to parse {}: {error}", path.display()),
+			ConfigError::InvalidEnvVar { name, value } => write!(f, "{name} has an invalid value: {value:?}"),
+			ConfigError::MissingEnvVar { name } => write!(f, "{name} must be set"),
+			ConfigError::Invalid { field, reason } => write!(f, "{field} {reason}"),
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+	pub fn secure_cookies(&self) -> bool {
+		match self.cookies.secure {
+			SecureCookies::Auto => self.server.tls.is_some(),
+			SecureCookies::Always => true,
+			SecureCookies::Never => false,
+		}
+	}
+
+	// `jwt` with the configured prefix
+	pub fn auth_cookie_name(&self) -> String {
+		format!("{}{JWT_COOKIE_NAME}", self.cookies.prefix.as_str())
+	}
+
+	pub fn csrf_cookie_name(&self) -> String {
+		format!("{}{CSRF_COOKIE_NAME}", self.cookies.prefix.as_str())
+	}
+
+	pub fn trusted_device_cookie_name(&self) -> String {
+		format!("{}{TRUSTED_DEVICE_COOKIE_NAME}", self.cookies.prefix.as_str())
+	}
+
+	// Defaults, overridden by the TOML file at `CONFIG_PATH` (or `config.toml`), overridden by environment variables
+	pub fn load() -> Result<Self, ConfigError> {
+		dotenvy::dotenv().ok();
+
+		let (path, required) = match std::env::var(env::CONFIG_PATH_ENV_VAR) {
+			Ok(path) => (PathBuf::from(path), true),
+			Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+		};
+		let contents = match std::fs::read_to_string(&path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => String::new(),
+			Err(error) => return Err(ConfigError::ReadFile { path, error }),
+		};
+
+		Self::from_sources(&path, &contents, |name| std::env::var(name).ok())
+	}
+
+	// The config in `contents` with the environment variables `vars` knows about applied on top
+	pub fn from_sources(
+		path: &Path,
+		contents: &str,
+		vars: impl Fn(&str) -> Option<String>,
+	) -> Result<Self, ConfigError> {
+		let mut config: Config = toml::from_str(contents)
+			.map_err(|error| ConfigError::ParseFile { path: path.to_owned(), error })?;
+		config.apply_env(vars)?;
+		config.validate()?;
+
+		Ok(config)
+	}
+
+	fn apply_env(&mut self, vars: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+		if let Some(address) = vars(env::APP_ADDRESS_ENV_VAR) {
+			self.server.address = address;
+		}
+		if let Some(origins) = vars(env::ALLOWED_ORIGINS_ENV_VAR) {
+			self.server.allowed_origins = split_list(&origins);
+		}
+		if let Some(seconds) = vars(env::DRAIN_TIMEOUT_SECONDS_ENV_VAR) {
+			self.server.drain_timeout_seconds = parse_env(env::DRAIN_TIMEOUT_SECONDS_ENV_VAR, seconds)?;
+		}
+		if let Some(cert_path) = vars(env::TLS_CERT_PATH_ENV_VAR) {
+			let key_path = vars(env::TLS_KEY_PATH_ENV_VAR)
+				.map(PathBuf::from)
+				.or_else(|| self.server.tls.as_ref().map(|tls| tls.key_path.clone()))
+				.ok_or(ConfigError::MissingEnvVar { name: env::TLS_KEY_PATH_ENV_VAR })?;
+			let reload_interval_seconds = self.server.tls.as_ref()
+				.map_or_else(default_tls_reload_interval, |tls| tls.reload_interval_seconds);
+			self.server.tls = Some(TlsConfig { cert_path: PathBuf::from(cert_path), key_path, reload_interval_seconds });
+		}
+		if let Some(secret) = vars(env::JWT_SECRET_ENV_VAR) {
+			self.auth.jwt_secret = secret;
+		}
+		if let Some(issuer) = vars(env::OIDC_ISSUER_ENV_VAR) {
+			self.auth.issuer = issuer;
+		}
+		if let Some(seconds) = vars(env::TOKEN_TTL_SECONDS_ENV_VAR) {
+			self.auth.token_ttl_seconds = parse_env(env::TOKEN_TTL_SECONDS_ENV_VAR, seconds)?;
+		}
+		if let Some(seconds) = vars(env::SESSION_IDLE_TTL_SECONDS_ENV_VAR) {
+			self.auth.session_idle_ttl_seconds = parse_env(env::SESSION_IDLE_TTL_SECONDS_ENV_VAR, seconds)?;
+		}
+		if let Some(days) = vars(env::TRUSTED_DEVICE_TTL_DAYS_ENV_VAR) {
+			self.auth.trusted_device_ttl_days = parse_env(env::TRUSTED_DEVICE_TTL_DAYS_ENV_VAR, days)?;
+		}
+		if let Some(emails) = vars(env::ADMIN_EMAILS_ENV_VAR) {
+			self.auth.admin_emails = split_list(&emails);
+		}
+		if let Some(path) = vars(env::ID_TOKEN_KEY_PATH_ENV_VAR) {
+			self.auth.id_token_key_path = Some(PathBuf::from(path));
+		}
+		if let Some(prefix) = vars(env::COOKIE_PREFIX_ENV_VAR) {
+			self.cookies.prefix = parse_env(env::COOKIE_PREFIX_ENV_VAR, prefix)?;
+		}
+		if let Some(domain) = vars(env::COOKIE_DOMAIN_ENV_VAR) {
+			self.cookies.domain = Some(domain).filter(|domain| !domain.is_empty());
+		}
+		if let Some(same_site) = vars(env::COOKIE_SAME_SITE_ENV_VAR) {
+			self.cookies.same_site = parse_env(env::COOKIE_SAME_SITE_ENV_VAR, same_site)?;
+		}
+		if let Some(secure) = vars(env::SECURE_COOKIES_ENV_VAR) {
+			self.cookies.secure = parse_env(env::SECURE_COOKIES_ENV_VAR, secure)?;
+		}
+		if let Some(url) = vars(env::DATABASE_URL_ENV_VAR) {
+			self.database.url = url;
+		}
+		if let Some(url) = vars(env::SMS_GATEWAY_URL_ENV_VAR) {
+			let api_key = vars(env::SMS_GATEWAY_API_KEY_ENV_VAR)
+				.or_else(|| self.sms_gateway.as_ref().map(|gateway| gateway.api_key.clone()))
+				.ok_or(ConfigError::MissingEnvVar { name: env::SMS_GATEWAY_API_KEY_ENV_VAR })?;
+			self.sms_gateway = Some(SmsGatewayConfig { url, api_key });
+		}
+		if let Some(path) = vars(env::AUDIT_LOG_PATH_ENV_VAR) {
+			self.audit.log_path = Some(PathBuf::from(path));
+		}
+
+		Ok(())
+	}
+
+	fn validate(&mut self) -> Result<(), ConfigError> {
+		if self.server.address.parse::<SocketAddr>().is_err() {
+			return Err(invalid("server.address", "must be an IP address and port, e.g. 0.0.0.0:3000"));
+		}
+		if let Some(origin) = self.server.allowed_origins.iter().find(|origin| !is_http_url(origin)) {
+			return Err(invalid("server.allowed_origins", &format!("contains {origin:?}, which isn't an http(s) origin")));
+		}
+		if self.server.tls.as_ref().is_some_and(|tls| tls.reload_interval_seconds == 0) {
+			return Err(invalid("server.tls.reload_interval_seconds", "must be a positive number of seconds"));
+		}
+
+		if self.auth.jwt_secret.is_empty() {
+			return Err(invalid("auth.jwt_secret", "must be set, e.g. with JWT_SECRET"));
+		}
+		self.auth.issuer = self.auth.issuer.trim_end_matches('/').to_string();
+		if !is_http_url(&self.auth.issuer) {
+			return Err(invalid("auth.issuer", "must be an http(s) URL"));
+		}
+		if self.auth.token_ttl_seconds <= 0 {
+			return Err(invalid("auth.token_ttl_seconds", "must be a positive number of seconds"));
+		}
+		if self.auth.session_idle_ttl_seconds <= 0 {
+			return Err(invalid("auth.session_idle_ttl_seconds", "must be a positive number of seconds"));
+		}
+		if self.auth.trusted_device_ttl_days <= 0 {
+			return Err(invalid("auth.trusted_device_ttl_days", "must be a positive number of days"));
+		}
+		if let Some(email) = self.auth.admin_emails.iter().find(|email| Email::from_str(email).is_err()) {
+			return Err(invalid("auth.admin_emails", &format!("contains {email:?}, which isn't an email address")));
+		}
+
+		// Browsers silently drop cookies that break these rules, which would look like logins not sticking
+		if let Some(domain) = &mut self.cookies.domain {
+			*domain = domain.trim_start_matches('.').to_string();
+			if domain.is_empty() || domain.contains(['/', ':']) {
+				return Err(invalid("cookies.domain", "must be a host name, e.g. example.com"));
+			}
+		}
+		if self.cookies.prefix != CookiePrefix::None && !self.secure_cookies() {
+			return Err(invalid("cookies.prefix", "requires secure cookies"));
+		}
+		if self.cookies.prefix == CookiePrefix::Host && self.cookies.domain.is_some() {
+			return Err(invalid("cookies.prefix", "can't be host with a cookie domain"));
+		}
+		if self.cookies.same_site == CookieSameSite::None && !self.secure_cookies() {
+			return Err(invalid("cookies.same_site", "can only be none with secure cookies"));
+		}
+
+		if self.database.url.is_empty() {
+			return Err(invalid("database.url", "must be set, e.g. with DATABASE_URL"));
+		}
+		if let Some(gateway) = &self.sms_gateway {
+			if !is_http_url(&gateway.url) {
+				return Err(invalid("sms_gateway.url", "must be an http(s) URL"));
+			}
+			if gateway.api_key.is_empty() {
+				return Err(invalid("sms_gateway.api_key", "must not be empty"));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+fn invalid(field: &'static str, reason: &str) -> ConfigError {
+	ConfigError::Invalid { field, reason: reason.to_string() }
+}
+
+fn parse_env<T: FromStr>(name: &'static str, value: String) -> Result<T, ConfigError> {
+	value.parse().map_err(|_| ConfigError::InvalidEnvVar { name, value })
+}
+
+// Comma-separated list, as lists are given in environment variables
+fn split_list(list: &str) -> Vec<String> {
+	list.split(',')
+		.map(str::trim)
+		.filter(|item| !item.is_empty())
+		.map(str::to_owned)
+		.collect()
+}
+
+fn is_http_url(value: &str) -> bool {
+	url::Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::*;
+
+	fn load(contents: &str, vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+		let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+		Config::from_sources(Path::new("config.toml"), contents, |name| vars.get(name).cloned())
+	}
+
+	const MINIMAL: &str = r#"
+		[auth]
+		jwt_secret = "secret"
+
+		[database]
+		url = "sqlite::memory:"
+	"#;
+
+	#[test]
+	fn test_defaults_fill_in_the_rest() {
+		let config = load(MINIMAL, &[]).unwrap();
+		assert_eq!(config.server.address, "0.0.0.0:3000");
+		assert_eq!(config.auth.token_ttl_seconds, 600);
+		assert_eq!(config.auth.issuer, "http://localhost:3000");
+		assert!(config.sms_gateway.is_none());
+		assert!(config.server.tls.is_none());
+		assert!(!config.secure_cookies());
+		assert_eq!(config.auth_cookie_name(), "jwt");
+	}
+
+	#[test]
+	fn test_cookies_are_secure_with_tls() {
+		let config = load(MINIMAL, &[("TLS_CERT_PATH", "cert.pem"), ("TLS_KEY_PATH", "key.pem")]).unwrap();
+		let tls = config.server.tls.as_ref().unwrap();
+		assert_eq!(tls.cert_path, PathBuf::from("cert.pem"));
+		assert_eq!(tls.reload_interval(), Duration::from_secs(60));
+		assert!(config.secure_cookies());
+
+		let config = load(MINIMAL, &[("SECURE_COOKIES", "always")]).unwrap();
+		assert!(config.secure_cookies());
+
+		let config = load(MINIMAL, &[
+			("TLS_CERT_PATH", "cert.pem"),
+			("TLS_KEY_PATH", "key.pem"),
+			("SECURE_COOKIES", "never"),
+		]).unwrap();
+		assert!(!config.secure_cookies());
+	}
+
+	#[test]
+	fn test_cookie_policy() {
+		let contents = format!("{MINIMAL}
+			[cookies]
+			prefix = \"secure\"
+			domain = \".example.com\"
+			same_site = \"strict\"
+			secure = \"always\"
+		");
+		let config = load(&contents, &[]).unwrap();
+		assert_eq!(config.auth_cookie_name(), "__Secure-jwt");
+		assert_eq!(config.cookies.domain.as_deref(), Some("example.com"));
+		assert_eq!(config.cookies.same_site, CookieSameSite::Strict);
+
+		let config = load(MINIMAL, &[("COOKIE_PREFIX", "host"), ("SECURE_COOKIES", "always")]).unwrap();
+		assert_eq!(config.auth_cookie_name(), "__Host-jwt");
+	}
+
+	#[test]
+	fn test_env_overrides_file() {
+		let contents = r#"
+			[server]
+			address = "127.0.0.1:8080"
+			allowed_origins = ["https://example.com"]
+
+			[auth]
+			jwt_secret = "from-file"
+			issuer = "https://auth.example.com/"
+			token_ttl_seconds = 300
+
+			[database]
+			url = "sqlite::memory:"
+		"#;
+		let config = load(contents, &[
+			("JWT_SECRET", "from-env"),
+			("ADMIN_EMAILS", "admin@example.com, other@example.com"),
+			("SMS_GATEWAY_URL", "https://sms.example.com"),
+			("SMS_GATEWAY_API_KEY", "key"),
+		]).unwrap();
+
+		assert_eq!(config.server.address, "127.0.0.1:8080");
+		assert_eq!(config.server.allowed_origins, vec!["https://example.com"]);
+		assert_eq!(config.auth.jwt_secret, "from-env");
+		assert_eq!(config.auth.issuer, "https://auth.example.com");
+		assert_eq!(config.auth.token_ttl_seconds, 300);
+		assert_eq!(config.auth.admin_emails, vec!["admin@example.com", "other@example.com"]);
+		assert_eq!(config.sms_gateway.unwrap().api_key, "key");
+	}
+
+	#[test]
+	fn test_secret_is_required() {
+		let error = load("[database]\nurl = \"sqlite::memory:\"", &[]).unwrap_err();
+		assert_eq!(error.to_string(), "auth.jwt_secret must be set, e.g. with JWT_SECRET");
+	}
+
+	#[test]
+	fn test_rejects_invalid_values() {
+		for (vars, message) in [
+			(vec![("TOKEN_TTL_SECONDS", "ten")], "TOKEN_TTL_SECONDS has an invalid value: \"ten\""),
+			(vec![("TOKEN_TTL_SECONDS", "0")], "auth.token_ttl_seconds must be a positive number of seconds"),
+			(vec![("SESSION_IDLE_TTL_SECONDS", "-1")], "auth.session_idle_ttl_seconds must be a positive number of seconds"),
+			(vec![("APP_ADDRESS", "localhost")], "server.address must be an IP address and port, e.g. 0.0.0.0:3000"),
+			(vec![("OIDC_ISSUER", "auth.example.com")], "auth.issuer must be an http(s) URL"),
+			(vec![("SMS_GATEWAY_URL", "https://sms.example.com")], "SMS_GATEWAY_API_KEY must be set"),
+			(vec![("TLS_CERT_PATH", "cert.pem")], "TLS_KEY_PATH must be set"),
+			(vec![("SECURE_COOKIES", "yes")], "SECURE_COOKIES has an invalid value: \"yes\""),
+			(vec![("COOKIE_PREFIX", "host")], "cookies.prefix requires secure cookies"),
+			(
+				vec![("COOKIE_PREFIX", "host"), ("COOKIE_DOMAIN", "example.com"), ("SECURE_COOKIES", "always")],
+				"cookies.prefix can't be host with a cookie domain",
+			),
+			(vec![("COOKIE_SAME_SITE", "none")], "cookies.same_site can only be none with secure cookies"),
+			(vec![("COOKIE_DOMAIN", "https://example.com")], "cookies.domain must be a host name, e.g. example.com"),
+		] {
+			let error = load(MINIMAL, &vars).unwrap_err();
+			assert_eq!(error.to_string(), message);
+		}
+	}
+
+	#[test]
+	fn test_rejects_unknown_keys() {
+		let error = load(&format!("{MINIMAL}\n[server]\nport = 3000"), &[]).unwrap_err();
+		assert!(matches!(error, ConfigError::ParseFile { .. }));
+	}
+}
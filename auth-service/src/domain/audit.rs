@@ -0,0 +1,188 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, SubsecRound, Utc};
+
+use super::Email;
+
+// Something security-relevant that happened to an account
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+	pub kind: AuditEventKind,
+	// The account the event is about
+	pub email: Email,
+	// Who acted on the account when it wasn't its owner, e.g. an administrator
+	pub actor: Option<Email>,
+	pub ip: Option<String>,
+	// Free-form context, e.g. how a user logged in or why a login failed
+	pub details: Option<String>,
+	pub at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+	pub fn new(kind: AuditEventKind, email: Email, ip: Option<String>) -> Self {
+		Self {
+			kind,
+			email,
+			actor: None,
+			ip,
+			details: None,
+			// Microseconds are as precise as the sinks store timestamps
+			at: Utc::now().trunc_subsecs(6),
+		}
+	}
+
+	pub fn by(mut self, actor: Email) -> Self {
+		self.actor = Some(actor);
+		self
+	}
+
+	pub fn with_details(mut self, details: impl Into<String>) -> Self {
+		self.details = Some(details.into());
+		self
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuditEventKind {
+	SignedUp,
+	LoginSucceeded,
+	LoginFailed,
+	// Too many wrong passwords in a row
+	AccountLocked,
+	LoggedOut,
+	// A wrong 2FA code, recovery code or passkey was entered for a pending 2FA login
+	TwoFactorFailed,
+	RecoveryCodeUsed,
+	RecoveryCodesRegenerated,
+	TwoFactorChannelChanged,
+	PasswordChanged,
+	PasskeyRegistered,
+	PasskeyRemoved,
+	SessionRevoked,
+	TrustedDeviceRevoked,
+	// An OAuth client or device was allowed to act for the user
+	OAuthAccessGranted,
+	RolesChanged,
+	AccountStatusChanged,
+	PasswordResetForced,
+	TwoFactorReset,
+	AccountUnlocked,
+	// An administrator was issued a token to act as the user
+	ImpersonationStarted,
+}
+
+impl AuditEventKind {
+	pub const ALL: [AuditEventKind; 21] = [
+		AuditEventKind::SignedUp,
+		AuditEventKind::LoginSucceeded,
+		AuditEventKind::LoginFailed,
+		AuditEventKind::AccountLocked,
+		AuditEventKind::LoggedOut,
+		AuditEventKind::TwoFactorFailed,
+		AuditEventKind::RecoveryCodeUsed,
+		AuditEventKind::RecoveryCodesRegenerated,
+		AuditEventKind::TwoFactorChannelChanged,
+		AuditEventKind::PasswordChanged,
+		AuditEventKind::PasskeyRegistered,
+		AuditEventKind::PasskeyRemoved,
+		AuditEventKind::SessionRevoked,
+		AuditEventKind::TrustedDeviceRevoked,
+		AuditEventKind::OAuthAccessGranted,
+		AuditEventKind::RolesChanged,
+		AuditEventKind::AccountStatusChanged,
+		AuditEventKind::PasswordResetForced,
+		AuditEventKind::TwoFactorReset,
+		AuditEventKind::AccountUnlocked,
+		AuditEventKind::ImpersonationStarted,
+	];
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			AuditEventKind::SignedUp => "signed_up",
+			AuditEventKind::LoginSucceeded => "login_succeeded",
+			AuditEventKind::LoginFailed => "login_failed",
+			AuditEventKind::AccountLocked => "account_locked",
+			AuditEventKind::LoggedOut => "logged_out",
+			AuditEventKind::TwoFactorFailed => "two_factor_failed",
+			AuditEventKind::RecoveryCodeUsed => "recovery_code_used",
+			AuditEventKind::RecoveryCodesRegenerated => "recovery_codes_regenerated",
+			AuditEventKind::TwoFactorChannelChanged => "two_factor_channel_changed",
+			AuditEventKind::PasswordChanged => "password_changed",
+			AuditEventKind::PasskeyRegistered => "passkey_registered",
+			AuditEventKind::PasskeyRemoved => "passkey_removed",
+			AuditEventKind::SessionRevoked => "session_revoked",
+			AuditEventKind::TrustedDeviceRevoked => "trusted_device_revoked",
+			AuditEventKind::OAuthAccessGranted => "oauth_access_granted",
+			AuditEventKind::RolesChanged => "roles_changed",
+			AuditEventKind::AccountStatusChanged => "account_status_changed",
+			AuditEventKind::PasswordResetForced => "password_reset_forced",
+			AuditEventKind::TwoFactorReset => "two_factor_reset",
+			AuditEventKind::AccountUnlocked => "account_unlocked",
+			AuditEventKind::ImpersonationStarted => "impersonation_started",
+		}
+	}
+}
+
+impl FromStr for AuditEventKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|kind| kind.as_str() == s)
+			.ok_or_else(|| format!("Unknown audit event type: {s}"))
+	}
+}
+
+impl fmt::Display for AuditEventKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+// Which events to fetch from the audit log. Unset fields match every event.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+	pub email: Option<Email>,
+	pub kind: Option<AuditEventKind>,
+	// Inclusive
+	pub since: Option<DateTime<Utc>>,
+	// Exclusive
+	pub until: Option<DateTime<Utc>>,
+	pub limit: usize,
+}
+
+impl AuditQuery {
+	pub fn matches(&self, event: &AuditEvent) -> bool {
+		self.email.as_ref().is_none_or(|email| email == &event.email)
+			&& self.kind.is_none_or(|kind| kind == event.kind)
+			&& self.since.is_none_or(|since| event.at >= since)
+			&& self.until.is_none_or(|until| event.at < until)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_kind_round_trip() {
+		for kind in AuditEventKind::ALL {
+			assert_eq!(AuditEventKind::from_str(kind.as_str()), Ok(kind));
+		}
+		assert!(AuditEventKind::from_str("unknown").is_err());
+	}
+
+	#[test]
+	fn test_query_matches() {
+		let email = Email::from_str("test@example.com").unwrap();
+		let event = AuditEvent::new(AuditEventKind::LoginFailed, email.clone(), None);
+
+		assert!(AuditQuery::default().matches(&event));
+		assert!(AuditQuery { email: Some(email), kind: Some(AuditEventKind::LoginFailed), ..Default::default() }.matches(&event));
+		assert!(!AuditQuery { kind: Some(AuditEventKind::LoggedOut), ..Default::default() }.matches(&event));
+		assert!(!AuditQuery { since: Some(event.at + chrono::Duration::seconds(1)), ..Default::default() }.matches(&event));
+		assert!(!AuditQuery { until: Some(event.at), ..Default::default() }.matches(&event));
+	}
+}
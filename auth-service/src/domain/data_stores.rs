@@ -1,8 +1,14 @@
+use std::fmt;
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use rand::{distr::Alphanumeric, Rng};
 
-use crate::domain::{Email, Password};
+use crate::domain::{
+	AuditEvent, AuditQuery, AuthorizationCode, AuthorizationGrant, DeviceAuthorization, DeviceCode, Email, OAuthClient,
+	Passkey, PasskeyChallenge, PasskeyId, Password, RecoveryCode, Session, SessionId, TrustedDevice, TrustedDeviceId,
+	UserCode,
+};
 
 use super::User;
 
@@ -10,7 +16,11 @@ use super::User;
 pub trait UserStore {
 	async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
 	async fn get_user(&self, email: Email) -> Result<User, UserStoreError>;
+	// Replace the stored user that has the same email
+	async fn update_user(&mut self, user: User) -> Result<(), UserStoreError>;
 	async fn validate_user(&self, email: Email, password: Password) -> Result<(), UserStoreError>;
+	// One page of the users, sorted by email. `search` matches part of the email or display name, ignoring case.
+	async fn list_users(&self, search: Option<&str>, offset: usize, limit: usize) -> Result<UserPage, UserStoreError>;
 
 	async fn get_user_str(&self, email: &str) -> Result<User, UserStoreError> {
 		let email = Email::from_str(email).map_err(|_| UserStoreError::UserNotFound)?;
@@ -22,6 +32,18 @@ pub trait UserStore {
 		let password = Password::from_str(password).map_err(|_| UserStoreError::InvalidCredentials)?;
 		self.validate_user(email, password).await
 	}
+
+	// Whether the store can serve requests, for the readiness check. In-memory stores always can.
+	async fn health_check(&self) -> Result<(), UserStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub struct UserPage {
+	pub users: Vec<User>,
+	// Number of users matching the search across all pages
+	pub total: usize,
 }
 
 #[derive(Debug, PartialEq)]
@@ -36,6 +58,13 @@ pub enum UserStoreError {
 pub trait BannedTokenStore {
 	async fn add(&mut self, token: String) -> Result<(), BannedTokenStoreError>;
 	async fn check(&self, token: &str) -> Result<(), BannedTokenStoreError>;
+	async fn count(&self) -> Result<usize, BannedTokenStoreError>;
+	// Drop the tokens that have expired, as those fail validation anyway. Returns how many were dropped.
+	async fn remove_expired(&mut self) -> Result<usize, BannedTokenStoreError>;
+
+	async fn health_check(&self) -> Result<(), BannedTokenStoreError> {
+		Ok(())
+	}
 }
 
 #[derive(Debug, PartialEq)]
@@ -47,17 +76,34 @@ pub enum BannedTokenStoreError {
 
 #[async_trait::async_trait]
 pub trait TwoFACodeStore {
+	// Start a login attempt, replacing the user's pending one so logging in again always gets a fresh code
 	async fn add_code(
 		&mut self,
 		email: Email,
 		login_attempt_id: LoginAttemptId,
 		code: TwoFACode,
 	) -> Result<(), TwoFACodeStoreError>;
+	// Swap the code of the pending login attempt for a new one, keeping its login attempt ID
+	async fn replace_code(&mut self, email: &Email, code: TwoFACode) -> Result<(), TwoFACodeStoreError>;
 	async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
 	async fn get_code(
 		&self,
 		email: &Email,
 	) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+	async fn get_code_delivery(&self, email: &Email) -> Result<TwoFACodeDelivery, TwoFACodeStoreError>;
+	// Number of logins waiting for their code
+	async fn count(&self) -> Result<usize, TwoFACodeStoreError>;
+
+	async fn health_check(&self) -> Result<(), TwoFACodeStoreError> {
+		Ok(())
+	}
+}
+
+// When the pending 2FA code was last sent, and how many times it has been resent
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoFACodeDelivery {
+	pub sent_at: DateTime<Utc>,
+	pub resend_count: u32,
 }
 
 #[derive(Debug, PartialEq)]
@@ -66,6 +112,192 @@ pub enum TwoFACodeStoreError {
 	UnexpectedError,
 }
 
+#[async_trait::async_trait]
+pub trait AuthorizationCodeStore {
+	async fn add_code(
+		&mut self,
+		code: AuthorizationCode,
+		grant: AuthorizationGrant,
+	) -> Result<(), AuthorizationCodeStoreError>;
+	// Codes are single-use, so fetching one also removes it from the store
+	async fn take_code(
+		&mut self,
+		code: &AuthorizationCode,
+	) -> Result<AuthorizationGrant, AuthorizationCodeStoreError>;
+
+	async fn health_check(&self) -> Result<(), AuthorizationCodeStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AuthorizationCodeStoreError {
+	CodeAlreadyExists,
+	CodeNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait ClientStore {
+	async fn add_client(&mut self, client: OAuthClient) -> Result<(), ClientStoreError>;
+	async fn get_client(&self, client_id: &str) -> Result<OAuthClient, ClientStoreError>;
+
+	async fn health_check(&self) -> Result<(), ClientStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClientStoreError {
+	ClientAlreadyExists,
+	ClientNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait DeviceCodeStore {
+	async fn add_authorization(
+		&mut self,
+		device_code: DeviceCode,
+		authorization: DeviceAuthorization,
+	) -> Result<(), DeviceCodeStoreError>;
+	async fn get_authorization(&self, device_code: &DeviceCode) -> Result<DeviceAuthorization, DeviceCodeStoreError>;
+	async fn get_authorization_by_user_code(
+		&self,
+		user_code: &UserCode,
+	) -> Result<(DeviceCode, DeviceAuthorization), DeviceCodeStoreError>;
+	async fn update_authorization(
+		&mut self,
+		device_code: &DeviceCode,
+		authorization: DeviceAuthorization,
+	) -> Result<(), DeviceCodeStoreError>;
+	async fn remove_authorization(&mut self, device_code: &DeviceCode) -> Result<(), DeviceCodeStoreError>;
+	// Drop the authorizations that have expired, whatever their status. Returns how many were dropped.
+	async fn remove_expired(&mut self) -> Result<usize, DeviceCodeStoreError>;
+
+	async fn health_check(&self) -> Result<(), DeviceCodeStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeviceCodeStoreError {
+	CodeAlreadyExists,
+	CodeNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait SessionStore {
+	async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError>;
+	async fn get_session(&self, id: &SessionId) -> Result<Session, SessionStoreError>;
+	async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError>;
+	// Record that the session was just used
+	async fn touch_session(&mut self, id: &SessionId) -> Result<(), SessionStoreError>;
+	async fn remove_session(&mut self, id: &SessionId) -> Result<(), SessionStoreError>;
+	async fn remove_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError>;
+	// Drop the sessions unused for longer than `idle_ttl_seconds`. Returns how many were dropped.
+	async fn remove_expired(&mut self, idle_ttl_seconds: i64) -> Result<usize, SessionStoreError>;
+
+	async fn health_check(&self) -> Result<(), SessionStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SessionStoreError {
+	SessionAlreadyExists,
+	SessionNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait RecoveryCodeStore {
+	// Replace all of the user's recovery codes with a new set
+	async fn set_codes(&mut self, email: Email, codes: Vec<RecoveryCode>) -> Result<(), RecoveryCodeStoreError>;
+	// Codes are single-use, so a matching code is removed. Returns how many codes are left.
+	async fn use_code(&mut self, email: &Email, code: &RecoveryCode) -> Result<usize, RecoveryCodeStoreError>;
+	async fn remaining_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError>;
+
+	async fn health_check(&self) -> Result<(), RecoveryCodeStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecoveryCodeStoreError {
+	CodeNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait TrustedDeviceStore {
+	async fn add_device(&mut self, device: TrustedDevice) -> Result<(), TrustedDeviceStoreError>;
+	async fn get_device(&self, id: &TrustedDeviceId) -> Result<TrustedDevice, TrustedDeviceStoreError>;
+	// Only devices that haven't expired yet are returned
+	async fn get_devices(&self, email: &Email) -> Result<Vec<TrustedDevice>, TrustedDeviceStoreError>;
+	async fn remove_device(&mut self, id: &TrustedDeviceId) -> Result<(), TrustedDeviceStoreError>;
+	async fn remove_devices(&mut self, email: &Email) -> Result<(), TrustedDeviceStoreError>;
+	// Drop the devices whose trust has run out. Returns how many were dropped.
+	async fn remove_expired(&mut self) -> Result<usize, TrustedDeviceStoreError>;
+
+	async fn health_check(&self) -> Result<(), TrustedDeviceStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TrustedDeviceStoreError {
+	DeviceAlreadyExists,
+	DeviceNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait PasskeyStore {
+	async fn add_passkey(&mut self, passkey: Passkey) -> Result<(), PasskeyStoreError>;
+	async fn get_passkey(&self, id: &PasskeyId) -> Result<Passkey, PasskeyStoreError>;
+	// Sorted by creation time, oldest first
+	async fn get_passkeys(&self, email: &Email) -> Result<Vec<Passkey>, PasskeyStoreError>;
+	// Record a successful authentication with the counter the authenticator reported
+	async fn update_sign_count(&mut self, id: &PasskeyId, sign_count: u32) -> Result<(), PasskeyStoreError>;
+	async fn remove_passkey(&mut self, id: &PasskeyId) -> Result<(), PasskeyStoreError>;
+	async fn add_challenge(&mut self, challenge: PasskeyChallenge) -> Result<(), PasskeyStoreError>;
+	// Challenges are single-use, so fetching one also removes it from the store
+	async fn take_challenge(&mut self, value: &str) -> Result<PasskeyChallenge, PasskeyStoreError>;
+	// Drop the challenges nobody came back for in time. Returns how many were dropped.
+	async fn remove_expired_challenges(&mut self) -> Result<usize, PasskeyStoreError>;
+
+	async fn health_check(&self) -> Result<(), PasskeyStoreError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PasskeyStoreError {
+	PasskeyAlreadyExists,
+	PasskeyNotFound,
+	ChallengeNotFound,
+	UnexpectedError,
+}
+
+#[async_trait::async_trait]
+pub trait AuditSink {
+	// The log is append-only, so events can't be changed or removed once recorded
+	async fn record(&mut self, event: AuditEvent) -> Result<(), AuditSinkError>;
+	// Up to `query.limit` matching events, newest first
+	async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>, AuditSinkError>;
+
+	async fn health_check(&self) -> Result<(), AuditSinkError> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AuditSinkError {
+	UnexpectedError,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct LoginAttemptId(String);
 
@@ -89,9 +321,16 @@ impl AsRef<str> for LoginAttemptId {
 	}
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct TwoFACode(String);
 
+// Kept out of logs
+impl fmt::Debug for TwoFACode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("TwoFACode(<redacted>)")
+	}
+}
+
 impl TwoFACode {
 	pub fn parse(code: String) -> Result<Self, String> {
 		// Ensure `code` is a valid 6-digit code
@@ -9,4 +9,9 @@ pub trait EmailClient {
 		subject: &str,
 		content: &str,
 	) -> Result<(), String>;
+
+	// Whether emails can be sent, for the readiness check
+	async fn health_check(&self) -> Result<(), String> {
+		Ok(())
+	}
 }
@@ -1,3 +1,4 @@
+#[derive(Debug)]
 pub enum AuthAPIError {
 	UserAlreadyExists,
 	InvalidCredentials,
@@ -7,4 +8,89 @@ pub enum AuthAPIError {
 	MissingToken,
 	InvalidToken,
 	Invalid2FACredentials,
+	InvalidProfile,
+	InsufficientScope,
+	InvalidUserCode,
+	SessionNotFound,
+	TwoFactorNotEnabled,
+	TrustedDeviceNotFound,
+	Resend2FACooldown,
+	Resend2FALimitReached,
+	TwoFADeliveryFailed,
+	InvalidTwoFAChannel,
+	InvalidPasskey,
+	PasskeyNotFound,
+	MissingPermission,
+	UserNotFound,
+	InvalidRole,
+	AccountDisabled,
+	AccountPendingVerification,
+	AccountLocked,
+	PasswordResetRequired,
+	PasswordlessAccount,
+	CannotDeactivateSelf,
+	InvalidAccountStatus,
+	ImpersonationNotAllowed,
+	CannotImpersonateSelf,
+	InvalidAuditQuery,
+	CrossSiteRequest,
+}
+
+// Errors returned by the OAuth 2.0 endpoints, serialized as described in RFC 6749 section 5.2
+#[derive(Debug)]
+pub enum OAuthError {
+	InvalidRequest(String),
+	InvalidClient,
+	InvalidGrant,
+	UnauthorizedClient,
+	UnsupportedGrantType,
+	UnsupportedResponseType,
+	InvalidScope,
+	AccessDenied,
+	LoginRequired,
+	AuthorizationPending,
+	SlowDown,
+	ExpiredToken,
+	TemporarilyUnavailable,
+	ServerError,
+}
+
+impl OAuthError {
+	pub fn code(&self) -> &'static str {
+		match self {
+			OAuthError::InvalidRequest(_) => "invalid_request",
+			OAuthError::InvalidClient => "invalid_client",
+			OAuthError::InvalidGrant => "invalid_grant",
+			OAuthError::UnauthorizedClient => "unauthorized_client",
+			OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+			OAuthError::UnsupportedResponseType => "unsupported_response_type",
+			OAuthError::InvalidScope => "invalid_scope",
+			OAuthError::AccessDenied => "access_denied",
+			OAuthError::LoginRequired => "login_required",
+			OAuthError::AuthorizationPending => "authorization_pending",
+			OAuthError::SlowDown => "slow_down",
+			OAuthError::ExpiredToken => "expired_token",
+			OAuthError::TemporarilyUnavailable => "temporarily_unavailable",
+			OAuthError::ServerError => "server_error",
+		}
+	}
+
+	pub fn description(&self) -> String {
+		match self {
+			OAuthError::InvalidRequest(reason) => reason.clone(),
+			OAuthError::InvalidClient => "Client authentication failed".to_string(),
+			OAuthError::InvalidGrant => "The authorization grant is invalid, expired or revoked".to_string(),
+			OAuthError::UnauthorizedClient => "The client is not allowed to use this grant type".to_string(),
+			OAuthError::UnsupportedGrantType => "Unsupported grant type".to_string(),
+			OAuthError::UnsupportedResponseType => "Unsupported response type".to_string(),
+			OAuthError::InvalidScope => "The requested scope is invalid".to_string(),
+			OAuthError::AccessDenied => "The resource owner denied the request".to_string(),
+			OAuthError::LoginRequired => "The user is not logged in".to_string(),
+			OAuthError::AuthorizationPending => "The user has not yet completed the authorization".to_string(),
+			OAuthError::SlowDown => "Polling too frequently, increase the interval by 5 seconds".to_string(),
+			OAuthError::ExpiredToken => "The device code has expired".to_string(),
+			OAuthError::TemporarilyUnavailable => "Too many requests, try again later".to_string(),
+			OAuthError::ServerError => "Unexpected error".to_string(),
+		}
+	}
 }
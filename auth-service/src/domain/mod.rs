@@ -1,9 +1,27 @@
+mod audit;
 mod data_stores;
 mod error;
 mod email_client;
+mod oauth;
+mod passkey;
+mod recovery_code;
+mod role;
+mod session;
+mod sms_client;
+mod trusted_device;
+mod two_fa_channel;
 mod user;
 
+pub use audit::*;
 pub use data_stores::*;
 pub use email_client::*;
-pub use error::AuthAPIError;
+pub use error::{AuthAPIError, OAuthError};
+pub use oauth::*;
+pub use passkey::*;
+pub use recovery_code::*;
+pub use role::*;
+pub use session::*;
+pub use sms_client::*;
+pub use trusted_device::*;
+pub use two_fa_channel::*;
 pub use user::*;
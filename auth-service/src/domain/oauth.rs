@@ -0,0 +1,205 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use rand::{distr::Alphanumeric, seq::IndexedRandom, Rng};
+
+use crate::utils::crypto::verify_secret;
+
+use super::Email;
+
+// An application allowed to request tokens from us. Clients without a secret are
+// public (e.g. single page apps) and can only use the authorization code flow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthClient {
+	pub client_id: String,
+	pub secret_hash: Option<String>,
+	pub redirect_uris: Vec<String>,
+	pub allowed_scopes: Vec<String>,
+}
+
+impl OAuthClient {
+	pub fn is_confidential(&self) -> bool {
+		self.secret_hash.is_some()
+	}
+
+	pub fn verify_secret(&self, secret: &str) -> bool {
+		self.secret_hash.as_deref().is_some_and(|hash| verify_secret(secret, hash))
+	}
+
+	pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+		self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+	}
+
+	// Every scope in the space-delimited `scope` must have been granted to the client
+	pub fn allows_scope(&self, scope: &str) -> bool {
+		scope.split_whitespace().all(|s| self.allowed_scopes.iter().any(|allowed| allowed == s))
+	}
+}
+
+// Single-use code handed to the client through the redirect URI
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct AuthorizationCode(String);
+
+// Kept out of logs
+impl fmt::Debug for AuthorizationCode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("AuthorizationCode(<redacted>)")
+	}
+}
+
+impl AuthorizationCode {
+	pub fn parse(code: String) -> Result<Self, String> {
+		if code.len() != 32 || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+			return Err("Invalid authorization code".to_string());
+		}
+
+		Ok(Self(code))
+	}
+}
+
+impl Default for AuthorizationCode {
+	fn default() -> Self {
+		let mut rng = rand::rng();
+		let code: String = (&mut rng).sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+		Self(code)
+	}
+}
+
+impl AsRef<str> for AuthorizationCode {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+// Everything the token endpoint needs to redeem an authorization code
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationGrant {
+	pub email: Email,
+	pub client_id: String,
+	pub redirect_uri: String,
+	pub scope: String,
+	pub nonce: Option<String>,
+	pub code_challenge: String,
+	pub expires_at: DateTime<Utc>,
+}
+
+impl AuthorizationGrant {
+	pub fn is_expired(&self) -> bool {
+		self.expires_at <= Utc::now()
+	}
+}
+
+// Secret the device polls the token endpoint with (RFC 8628)
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DeviceCode(String);
+
+// Kept out of logs
+impl fmt::Debug for DeviceCode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("DeviceCode(<redacted>)")
+	}
+}
+
+impl DeviceCode {
+	pub fn parse(code: String) -> Result<Self, String> {
+		if code.len() != 40 || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+			return Err("Invalid device code".to_string());
+		}
+
+		Ok(Self(code))
+	}
+}
+
+impl Default for DeviceCode {
+	fn default() -> Self {
+		let mut rng = rand::rng();
+		let code: String = (&mut rng).sample_iter(&Alphanumeric).take(40).map(char::from).collect();
+		Self(code)
+	}
+}
+
+impl AsRef<str> for DeviceCode {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+// Consonants only, so codes are easy to type and never spell words (RFC 8628 section 6.1)
+const USER_CODE_CHARSET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
+// Short code the user types in on the verification page, shown as `XXXX-XXXX`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UserCode(String);
+
+impl UserCode {
+	// Users may type the code in lowercase, with or without the dash
+	pub fn parse(code: &str) -> Result<Self, String> {
+		let normalized: String = code
+			.chars()
+			.filter(|c| !c.is_whitespace() && *c != '-')
+			.map(|c| c.to_ascii_uppercase())
+			.collect();
+
+		if normalized.len() != 8 || !normalized.bytes().all(|c| USER_CODE_CHARSET.contains(&c)) {
+			return Err("Invalid user code".to_string());
+		}
+
+		Ok(Self(format!("{}-{}", &normalized[..4], &normalized[4..])))
+	}
+}
+
+impl Default for UserCode {
+	fn default() -> Self {
+		let mut rng = rand::rng();
+		let chars: String = (0..8)
+			.map(|_| *USER_CODE_CHARSET.choose(&mut rng).expect("charset is not empty") as char)
+			.collect();
+		Self(format!("{}-{}", &chars[..4], &chars[4..]))
+	}
+}
+
+impl AsRef<str> for UserCode {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceAuthorizationStatus {
+	Pending,
+	Approved(Email),
+	Denied,
+}
+
+// A device authorization request waiting for the user to enter its user code
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceAuthorization {
+	pub client_id: String,
+	pub scope: String,
+	pub user_code: UserCode,
+	pub status: DeviceAuthorizationStatus,
+	pub expires_at: DateTime<Utc>,
+	// Minimum number of seconds between polls, raised every time the device polls too fast
+	pub interval: i64,
+	pub last_polled_at: Option<DateTime<Utc>>,
+}
+
+impl DeviceAuthorization {
+	pub fn is_expired(&self) -> bool {
+		self.expires_at <= Utc::now()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_user_code_parse() {
+		let code = UserCode::default();
+		assert_eq!(UserCode::parse(code.as_ref()).unwrap(), code);
+		assert_eq!(UserCode::parse("bcdf ghjk").unwrap().as_ref(), "BCDF-GHJK");
+		assert!(UserCode::parse("BCDF-GHJ").is_err());
+		assert!(UserCode::parse("ABCD-EFGH").is_err());
+	}
+}
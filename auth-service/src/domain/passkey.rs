@@ -0,0 +1,76 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+
+use super::Email;
+
+// How long the browser has to complete a registration or authentication ceremony
+pub const PASSKEY_CHALLENGE_TTL_SECONDS: i64 = 300;
+
+// Credential ID chosen by the authenticator, base64url-encoded the same way browsers report it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PasskeyId(String);
+
+impl PasskeyId {
+	pub fn parse(id: &str) -> Result<Self, String> {
+		let bytes = URL_SAFE_NO_PAD.decode(id).map_err(|_| "Invalid passkey ID".to_string())?;
+		Ok(Self::from_bytes(&bytes))
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Self {
+		Self(URL_SAFE_NO_PAD.encode(bytes))
+	}
+}
+
+impl AsRef<str> for PasskeyId {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+// A WebAuthn credential registered by a user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passkey {
+	pub id: PasskeyId,
+	pub email: Email,
+	pub name: Option<String>,
+	// Uncompressed SEC1 encoding of the P-256 public key
+	pub public_key: Vec<u8>,
+	// Last signature counter reported by the authenticator, used to detect cloned authenticators
+	pub sign_count: u32,
+	pub created_at: DateTime<Utc>,
+	pub last_used_at: Option<DateTime<Utc>>,
+}
+
+// What a challenge was handed out for, so it can't be replayed in another ceremony
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasskeyCeremony {
+	Registration(Email),
+	// The email is known when the passkey is used as a second factor or the user typed it in
+	Authentication(Option<Email>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasskeyChallenge {
+	// Random bytes, base64url-encoded as they come back in the client data
+	pub value: String,
+	pub ceremony: PasskeyCeremony,
+	pub expires_at: DateTime<Utc>,
+}
+
+impl PasskeyChallenge {
+	pub fn new(ceremony: PasskeyCeremony) -> Self {
+		let mut bytes = [0u8; 32];
+		rand::rng().fill_bytes(&mut bytes);
+		Self {
+			value: URL_SAFE_NO_PAD.encode(bytes),
+			ceremony,
+			expires_at: Utc::now() + chrono::Duration::seconds(PASSKEY_CHALLENGE_TTL_SECONDS),
+		}
+	}
+
+	pub fn is_expired(&self) -> bool {
+		Utc::now() >= self.expires_at
+	}
+}
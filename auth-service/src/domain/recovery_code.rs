@@ -0,0 +1,92 @@
+use std::fmt;
+
+use rand::seq::IndexedRandom;
+
+use crate::utils::crypto::hash_secret;
+
+// How many recovery codes a user gets at a time
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+// Lowercase letters and digits, without the easily confused `0`, `1`, `i`, `l` and `o`
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_GROUP_LENGTH: usize = 5;
+
+// One-time code a 2FA user can enter instead of the emailed code, formatted as `xxxxx-xxxxx`
+#[derive(Clone, PartialEq)]
+pub struct RecoveryCode(String);
+
+// Kept out of logs
+impl fmt::Debug for RecoveryCode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("RecoveryCode(<redacted>)")
+	}
+}
+
+impl RecoveryCode {
+	// Users write these codes down, so case, spaces and the dash are forgiven
+	pub fn parse(code: &str) -> Result<Self, String> {
+		let normalized: String = code
+			.chars()
+			.filter(|c| !c.is_whitespace() && *c != '-')
+			.map(|c| c.to_ascii_lowercase())
+			.collect();
+
+		if normalized.len() != RECOVERY_CODE_GROUP_LENGTH * 2
+			|| !normalized.bytes().all(|c| RECOVERY_CODE_ALPHABET.contains(&c))
+		{
+			return Err("Invalid recovery code".to_string());
+		}
+
+		let (first, second) = normalized.split_at(RECOVERY_CODE_GROUP_LENGTH);
+		Ok(Self(format!("{first}-{second}")))
+	}
+
+	pub fn generate_set() -> Vec<Self> {
+		(0..RECOVERY_CODE_COUNT).map(|_| Self::default()).collect()
+	}
+
+	// Recovery codes are only ever stored hashed
+	pub fn hash(&self) -> String {
+		hash_secret(&self.0)
+	}
+}
+
+impl Default for RecoveryCode {
+	fn default() -> Self {
+		let mut rng = rand::rng();
+		let mut group = || -> String {
+			(0..RECOVERY_CODE_GROUP_LENGTH)
+				.map(|_| *RECOVERY_CODE_ALPHABET.choose(&mut rng).unwrap() as char)
+				.collect()
+		};
+		let first = group();
+		let second = group();
+		Self(format!("{first}-{second}"))
+	}
+}
+
+impl AsRef<str> for RecoveryCode {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_normalizes_code() {
+		let code = RecoveryCode::default();
+		assert_eq!(RecoveryCode::parse(code.as_ref()).unwrap(), code);
+		assert_eq!(RecoveryCode::parse(&code.as_ref().to_uppercase().replace('-', " ")).unwrap(), code);
+		assert!(RecoveryCode::parse("123456").is_err());
+		assert!(RecoveryCode::parse("abcde-fghi0").is_err());
+	}
+
+	#[test]
+	fn test_debug_is_redacted() {
+		let code = RecoveryCode::default();
+		assert_eq!(format!("{code:?}"), "RecoveryCode(<redacted>)");
+	}
+}
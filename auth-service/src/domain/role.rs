@@ -0,0 +1,114 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+// Something a token can allow beyond managing the user's own account.
+// Permissions travel in tokens as scope strings, e.g. `users:read`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Permission {
+	ReadUsers,
+	ManageUsers,
+	ImpersonateUsers,
+	ReadAuditLog,
+}
+
+impl Permission {
+	pub const ALL: [Permission; 4] = [
+		Permission::ReadUsers,
+		Permission::ManageUsers,
+		Permission::ImpersonateUsers,
+		Permission::ReadAuditLog,
+	];
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Permission::ReadUsers => "users:read",
+			Permission::ManageUsers => "users:write",
+			Permission::ImpersonateUsers => "users:impersonate",
+			Permission::ReadAuditLog => "audit:read",
+		}
+	}
+}
+
+impl FromStr for Permission {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|permission| permission.as_str() == s)
+			.ok_or_else(|| format!("Unknown permission: {s}"))
+	}
+}
+
+impl fmt::Display for Permission {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+// Named set of permissions an operator can hand to a user. Users without roles can only
+// manage their own account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+	Admin,
+	Support,
+}
+
+impl Role {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Role::Admin => "admin",
+			Role::Support => "support",
+		}
+	}
+
+	pub fn permissions(&self) -> &'static [Permission] {
+		match self {
+			Role::Admin => &Permission::ALL,
+			Role::Support => &[Permission::ReadUsers, Permission::ImpersonateUsers, Permission::ReadAuditLog],
+		}
+	}
+}
+
+impl FromStr for Role {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"admin" => Ok(Role::Admin),
+			"support" => Ok(Role::Support),
+			_ => Err(format!("Unknown role: {s}")),
+		}
+	}
+}
+
+// The permissions granted by any of the roles, without duplicates
+pub fn permissions_of(roles: &[Role]) -> Vec<Permission> {
+	let mut permissions: Vec<Permission> = roles.iter().flat_map(|role| role.permissions()).copied().collect();
+	permissions.sort();
+	permissions.dedup();
+	permissions
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_permission_round_trip() {
+		for permission in Permission::ALL {
+			assert_eq!(Permission::from_str(permission.as_str()), Ok(permission));
+		}
+		assert!(Permission::from_str("users:delete").is_err());
+	}
+
+	#[test]
+	fn test_permissions_of() {
+		assert!(permissions_of(&[]).is_empty());
+		assert_eq!(permissions_of(&[Role::Support, Role::Admin]), Permission::ALL.to_vec());
+		assert!(!permissions_of(&[Role::Support]).contains(&Permission::ManageUsers));
+	}
+}
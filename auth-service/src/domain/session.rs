@@ -0,0 +1,55 @@
+use chrono::{DateTime, Duration, Utc};
+
+use super::Email;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionId(String);
+
+impl SessionId {
+	pub fn parse(id: String) -> Result<Self, String> {
+		uuid::Uuid::parse_str(&id).map_err(|_| "Invalid session ID".to_string())?;
+		Ok(Self(id))
+	}
+}
+
+impl Default for SessionId {
+	fn default() -> Self {
+		Self(uuid::Uuid::new_v4().to_string())
+	}
+}
+
+impl AsRef<str> for SessionId {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+// A login recorded server-side, so the tokens issued for it can be revoked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+	pub id: SessionId,
+	pub email: Email,
+	pub created_at: DateTime<Utc>,
+	pub last_seen_at: DateTime<Utc>,
+	pub user_agent: Option<String>,
+	pub ip: Option<String>,
+}
+
+impl Session {
+	pub fn new(email: Email, user_agent: Option<String>, ip: Option<String>) -> Self {
+		let now = Utc::now();
+		Self {
+			id: SessionId::default(),
+			email,
+			created_at: now,
+			last_seen_at: now,
+			user_agent,
+			ip,
+		}
+	}
+
+	// Whether the session went unused for longer than `idle_ttl_seconds`
+	pub fn is_expired(&self, idle_ttl_seconds: i64) -> bool {
+		Utc::now() - self.last_seen_at > Duration::seconds(idle_ttl_seconds)
+	}
+}
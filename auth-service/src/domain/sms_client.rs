@@ -0,0 +1,12 @@
+use super::PhoneNumber;
+
+// This trait represents the interface all concrete SMS clients should implement
+#[async_trait::async_trait]
+pub trait SmsClient {
+	async fn send_sms(&self, recipient: &PhoneNumber, content: &str) -> Result<(), String>;
+
+	// Whether text messages can be sent, for the readiness check
+	async fn health_check(&self) -> Result<(), String> {
+		Ok(())
+	}
+}
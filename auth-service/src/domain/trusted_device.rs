@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+
+use super::Email;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrustedDeviceId(String);
+
+impl TrustedDeviceId {
+	pub fn parse(id: String) -> Result<Self, String> {
+		uuid::Uuid::parse_str(&id).map_err(|_| "Invalid trusted device ID".to_string())?;
+		Ok(Self(id))
+	}
+}
+
+impl Default for TrustedDeviceId {
+	fn default() -> Self {
+		Self(uuid::Uuid::new_v4().to_string())
+	}
+}
+
+impl AsRef<str> for TrustedDeviceId {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+// A browser the user chose to remember after 2FA, so later logins from it skip the 2FA step
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrustedDevice {
+	pub id: TrustedDeviceId,
+	pub email: Email,
+	pub created_at: DateTime<Utc>,
+	pub expires_at: DateTime<Utc>,
+	pub user_agent: Option<String>,
+}
+
+impl TrustedDevice {
+	pub fn new(email: Email, user_agent: Option<String>, ttl_days: i64) -> Self {
+		let now = Utc::now();
+		Self {
+			id: TrustedDeviceId::default(),
+			email,
+			created_at: now,
+			expires_at: now + chrono::Duration::days(ttl_days),
+			user_agent,
+		}
+	}
+
+	pub fn is_expired(&self) -> bool {
+		Utc::now() >= self.expires_at
+	}
+}
@@ -0,0 +1,89 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+// Where a user's 2FA codes get delivered
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub enum TwoFAChannel {
+	#[default]
+	Email,
+	Sms(PhoneNumber),
+	Webhook(WebhookUrl),
+}
+
+impl TwoFAChannel {
+	pub fn name(&self) -> &'static str {
+		match self {
+			TwoFAChannel::Email => "email",
+			TwoFAChannel::Sms(_) => "sms",
+			TwoFAChannel::Webhook(_) => "webhook",
+		}
+	}
+}
+
+// Phone number in E.164 format, e.g. `+306912345678`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhoneNumber(String);
+
+impl AsRef<str> for PhoneNumber {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+impl FromStr for PhoneNumber {
+	type Err = String;
+
+	// Spaces, dashes and parentheses people like to format numbers with are dropped
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let number: String = s.chars().filter(|c| !matches!(c, ' ' | '-' | '(' | ')')).collect();
+		let digits = number.strip_prefix('+').ok_or("Phone number must start with a country code".to_string())?;
+
+		if !(8..=15).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+			return Err("Invalid phone number".to_string());
+		}
+
+		Ok(PhoneNumber(number))
+	}
+}
+
+// Endpoint of the user's own service that receives their 2FA codes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebhookUrl(String);
+
+impl AsRef<str> for WebhookUrl {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+impl FromStr for WebhookUrl {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match url::Url::parse(s) {
+			Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => Ok(WebhookUrl(s.to_string())),
+			_ => Err("Webhook URL must be an http(s) URL".to_string()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_phone_number() {
+		assert_eq!(PhoneNumber::from_str("+30 691 234-5678").unwrap().as_ref(), "+306912345678");
+		assert!(PhoneNumber::from_str("6912345678").is_err());
+		assert!(PhoneNumber::from_str("+30abc").is_err());
+		assert!(PhoneNumber::from_str("+123").is_err());
+	}
+
+	#[test]
+	fn test_parse_webhook_url() {
+		assert!(WebhookUrl::from_str("https://example.com/2fa").is_ok());
+		assert!(WebhookUrl::from_str("ftp://example.com").is_err());
+		assert!(WebhookUrl::from_str("not a url").is_err());
+	}
+}
@@ -1,36 +1,145 @@
+use std::fmt;
 use std::str::FromStr;
 
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+use super::{permissions_of, Permission, Role, TwoFAChannel};
+
+#[derive(Debug, Clone)]
 pub struct User {
 	email: Email,
-	password: Password,
+	// Passwordless accounts only log in through magic links
+	password: Option<Password>,
 	pub requires_2fa: bool,
+	pub two_fa_channel: TwoFAChannel,
+	pub email_verified: bool,
+	pub profile: UserProfile,
+	pub roles: Vec<Role>,
+	pub status: AccountStatus,
+	// Set by an administrator when the password may have leaked. Password logins are refused until it's changed.
+	pub password_reset_required: bool,
+	// Wrong passwords entered since the last successful login
+	pub failed_login_attempts: u32,
+	pub locked_until: Option<DateTime<Utc>>,
 }
 
 impl User {
 	pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
 		Self {
 			email,
-			password,
+			password: Some(password),
 			requires_2fa,
+			two_fa_channel: TwoFAChannel::default(),
+			email_verified: false,
+			profile: UserProfile::default(),
+			roles: Vec::new(),
+			status: AccountStatus::Active,
+			password_reset_required: false,
+			failed_login_attempts: 0,
+			locked_until: None,
+		}
+	}
+
+	pub fn new_passwordless(email: Email) -> Self {
+		Self {
+			password: None,
+			..Self::new(email, Password(String::new()), false)
 		}
 	}
 
 	pub fn from_str(email: &str, password: &str, requires_2fa: bool) -> Result<Self, String> {
-		Ok(Self {
-			email: Email::from_str(email)?,
-			password: Password::from_str(password)?,
-			requires_2fa,
-		})
+		Ok(Self::new(Email::from_str(email)?, Password::from_str(password)?, requires_2fa))
 	}
 
 	pub fn email(&self) -> Email { self.email.clone() }
 	pub fn email_str(&self) -> &str { self.email.as_ref() }
-	pub fn password(&self) -> Password { self.password.clone() }
-	pub fn password_str(&self) -> &str { self.password.as_ref() }
+	pub fn password(&self) -> Option<&Password> { self.password.as_ref() }
+	pub fn password_str(&self) -> Option<&str> { self.password.as_ref().map(AsRef::as_ref) }
 	pub fn requires_2fa(&self) -> bool { self.requires_2fa }
+	pub fn permissions(&self) -> Vec<Permission> { permissions_of(&self.roles) }
+
+	// Too many wrong passwords lock password logins for a while
+	pub fn is_locked(&self) -> bool {
+		self.locked_until.is_some_and(|locked_until| locked_until > Utc::now())
+	}
+
+	pub fn set_password(&mut self, password: Password) {
+		self.password = Some(password);
+		self.password_reset_required = false;
+	}
+}
+
+// Only active accounts can log in or use their tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccountStatus {
+	#[default]
+	Active,
+	// Suspended by an administrator
+	Disabled,
+	// Waiting for the owner of the email address to be confirmed
+	PendingVerification,
+}
+
+impl AccountStatus {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			AccountStatus::Active => "active",
+			AccountStatus::Disabled => "disabled",
+			AccountStatus::PendingVerification => "pendingVerification",
+		}
+	}
+}
+
+impl FromStr for AccountStatus {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"active" => Ok(AccountStatus::Active),
+			"disabled" => Ok(AccountStatus::Disabled),
+			"pendingVerification" => Ok(AccountStatus::PendingVerification),
+			_ => Err(format!("Unknown account status: {s}")),
+		}
+	}
+}
+
+// Optional details users can fill in about themselves, exposed as OIDC standard claims
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UserProfile {
+	pub display_name: Option<String>,
+	pub locale: Option<String>,
+	pub avatar_url: Option<String>,
+}
+
+impl UserProfile {
+	pub fn validate(&self) -> Result<(), String> {
+		if let Some(display_name) = &self.display_name {
+			if display_name.trim().is_empty() || display_name.chars().count() > 100 {
+				return Err("Display name must be between 1 and 100 characters".to_string());
+			}
+		}
+
+		// BCP 47 language tags, e.g. `en` or `el-GR`
+		if let Some(locale) = &self.locale {
+			let valid = !locale.is_empty()
+				&& locale.len() <= 35
+				&& locale.split('-').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()));
+			if !valid {
+				return Err("Invalid locale".to_string());
+			}
+		}
+
+		if let Some(avatar_url) = &self.avatar_url {
+			match url::Url::parse(avatar_url) {
+				Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+				_ => return Err("Avatar URL must be an http(s) URL".to_string()),
+			}
+		}
+
+		Ok(())
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
@@ -54,9 +163,16 @@ impl FromStr for Email {
 	}
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+// Neither `Debug` nor `Serialize` show the password, so it can't end up in logs or responses
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Password(String);
 
+impl fmt::Debug for Password {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Password(<redacted>)")
+	}
+}
+
 impl AsRef<str> for Password {
 	fn as_ref(&self) -> &str {
 		&self.0
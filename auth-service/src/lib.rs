@@ -1,77 +1,249 @@
 use std::error::Error;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
 
 use axum::{Json, Router};
 use axum::response::{IntoResponse, Response};
-use axum::routing::post;
-use axum::serve::Serve;
-use axum::http::{Method, StatusCode};
+use axum::routing::{delete, get, post, put};
+use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
+use axum::middleware;
+use axum_server::tls_rustls::RustlsConfig;
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::SqlitePool;
+use tokio::net::TcpListener;
 use tower_http::cors::CorsLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::ServeDir;
+use tokio_util::task::TaskTracker;
+use tower_http::trace::TraceLayer;
 
 mod app_state;
+mod config;
 mod domain;
 mod routes;
 mod services;
 mod utils;
 
 pub use app_state::*;
+pub use config::*;
 pub use routes::signup::SignupResponse;
 pub use services::hashmap_user_store::HashmapUserStore;
 pub use services::hashset_banned_token_store::HashsetBannedTokenStore;
 pub use services::hashmap_two_fa_code_store::HashmapTwoFACodeStore;
-pub use services::mock_email_client::MockEmailClient;
+pub use services::hashmap_authorization_code_store::HashmapAuthorizationCodeStore;
+pub use services::hashmap_client_store::HashmapClientStore;
+pub use services::hashmap_device_code_store::HashmapDeviceCodeStore;
+pub use services::hashmap_session_store::HashmapSessionStore;
+pub use services::hashmap_recovery_code_store::HashmapRecoveryCodeStore;
+pub use services::hashmap_trusted_device_store::HashmapTrustedDeviceStore;
+pub use services::hashmap_passkey_store::HashmapPasskeyStore;
+pub use services::sqlite_client_store::SqliteClientStore;
+pub use services::mock_email_client::{MockEmailClient, SentEmail};
+pub use services::http_sms_client::HttpSmsClient;
+pub use services::mock_sms_client::{MockSmsClient, SentSms};
+pub use services::webhook_client::WebhookClient;
+pub use services::vec_audit_sink::VecAuditSink;
+pub use services::sqlite_audit_sink::SqliteAuditSink;
+pub use services::json_lines_audit_sink::JsonLinesAuditSink;
+pub use utils::client_registration::ClientRegistration;
 pub use utils::constants::*;
+pub use utils::oidc::IdTokenKey;
+pub use utils::rate_limit::RateLimiter;
+pub use utils::shutdown::{shutdown_signal, ShutdownHandle};
+pub use utils::telemetry::init_tracing;
+pub use routes::admin_audit_events::{AuditEventListResponse, AuditEventResponse};
+pub use routes::admin_users::{AdminUserResponse, ImpersonationResponse, UserListResponse, UserRolesResponse};
+pub use routes::health::HealthResponse;
 pub use routes::login::TwoFactorAuthResponse;
-pub use domain::Email;
+pub use routes::magic_link::MagicLinkResponse;
+pub use routes::oauth_authorize::ConsentResponse;
+pub use routes::oauth_device_authorization::DeviceAuthorizationResponse;
+pub use routes::oauth_token::TokenResponse;
+pub use routes::openid_configuration::OpenIdConfiguration;
+pub use routes::passkeys::{PasskeyAuthenticationOptions, PasskeyRegistrationOptions, PasskeyResponse};
+pub use routes::profile::ProfileResponse;
+pub use routes::recovery_codes::{RecoveryCodesResponse, RecoveryCodesStatusResponse};
+pub use routes::sessions::SessionResponse;
+pub use routes::trusted_devices::TrustedDeviceResponse;
+pub use routes::two_fa_channel::TwoFAChannelBody;
+pub use routes::userinfo::UserInfoResponse;
+pub use domain::{
+	AccountStatus, AuditEvent, AuditEventKind, AuditQuery, AuditSink, AuditSinkError, Email, OAuthClient, Permission, Role,
+};
+pub use utils::crypto::{generate_secret, hash_secret};
 
-use crate::domain::AuthAPIError;
+use crate::domain::{AuthAPIError, OAuthError};
 
 // This struct encapsulates our application-related logic.
 pub struct Application {
-	server: Serve<Router, Router>,
+	listener: TcpListener,
+	service: IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+	// Set when serving HTTPS
+	tls: Option<(RustlsConfig, TlsConfig)>,
+	// Handed to the background tasks started by `run`
+	state: AppState,
+	shutdown: ShutdownHandle,
+	// How long requests in flight and background tasks get to finish once shutting down
+	drain_timeout: Duration,
 	// address is exposed as a public field
 	// so we have access to it in tests.
 	pub address: String,
 }
 
 impl Application {
-	pub async fn build(app_state: AppState, address: &str) -> Result<Self, Box<dyn Error>> {
-		let allowed_origins = [
-			"http://localhost:3000".parse()?,
-			"http://127.0.0.1:3000".parse()?,
-		];
+	pub async fn build(app_state: AppState, config: &ServerConfig) -> Result<Self, Box<dyn Error>> {
+		let allowed_origins = config.allowed_origins
+			.iter()
+			.map(|origin| origin.parse::<HeaderValue>())
+			.collect::<Result<Vec<_>, _>>()?;
 
 		let cors = CorsLayer::new()
-			.allow_methods([Method::GET, Method::POST])
+			.allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
 			.allow_origin(allowed_origins)
+			// Pages on allowed origins send the CSRF token along with cookie-authenticated requests
+			.allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, HeaderName::from_static(CSRF_HEADER_NAME)])
 			.allow_credentials(true);
 
 		let router = Router::new()
 			.nest_service("/", ServeDir::new("assets"))
 			.route("/signup", post(routes::signup))
 			.route("/login", post(routes::login))
+			.route("/login/magic-link", post(routes::request_magic_link))
+			.route("/login/magic-link/callback", get(routes::magic_link_callback))
 			.route("/verify-2fa", post(routes::verify_2fa))
+			.route("/resend-2fa", post(routes::resend_2fa))
+			.route("/2fa/channel", get(routes::get_two_fa_channel).put(routes::update_two_fa_channel))
 			.route("/logout", post(routes::logout))
+			.route("/password", post(routes::change_password))
 			.route("/verify-token", post(routes::verify_token))
-			.with_state(app_state)
-			.layer(cors);
+			.route("/oauth/authorize", get(routes::oauth_authorize).post(routes::oauth_authorize_consent))
+			.route("/oauth/token", post(routes::oauth_token))
+			.route("/oauth/device_authorization", post(routes::oauth_device_authorization))
+			.route("/oauth/device/verify", post(routes::oauth_device_verify))
+			.route("/.well-known/openid-configuration", get(routes::openid_configuration))
+			.route("/.well-known/jwks.json", get(routes::jwks))
+			.route("/metrics", get(routes::metrics))
+			.route("/health/live", get(routes::health_live))
+			.route("/health/ready", get(routes::health_ready))
+			.route("/userinfo", get(routes::userinfo).post(routes::userinfo))
+			.route("/profile", get(routes::get_profile).patch(routes::update_profile))
+			.route("/recovery-codes", get(routes::get_recovery_codes).post(routes::regenerate_recovery_codes))
+			.route("/sessions", get(routes::list_sessions).delete(routes::revoke_all_sessions))
+			.route("/sessions/:id", delete(routes::revoke_session))
+			.route("/trusted-devices", get(routes::list_trusted_devices).delete(routes::revoke_all_trusted_devices))
+			.route("/trusted-devices/:id", delete(routes::revoke_trusted_device))
+			.route("/admin/audit-events", get(routes::list_audit_events))
+			.route("/admin/users", get(routes::list_users))
+			.route("/admin/users/:email", get(routes::get_user))
+			.route("/admin/users/:email/roles", get(routes::get_user_roles).put(routes::update_user_roles))
+			.route("/admin/users/:email/status", put(routes::update_user_status))
+			.route("/admin/users/:email/disable", post(routes::disable_user))
+			.route("/admin/users/:email/enable", post(routes::enable_user))
+			.route("/admin/users/:email/force-password-reset", post(routes::force_password_reset))
+			.route("/admin/users/:email/reset-2fa", post(routes::reset_two_fa))
+			.route("/admin/users/:email/unlock", post(routes::unlock_user))
+			.route("/admin/users/:email/impersonate", post(routes::impersonate_user))
+			.route("/passkeys", get(routes::list_passkeys))
+			.route("/passkeys/:id", delete(routes::remove_passkey))
+			.route("/passkeys/register/start", post(routes::start_passkey_registration))
+			.route("/passkeys/register/finish", post(routes::finish_passkey_registration))
+			.route("/passkeys/login/start", post(routes::start_passkey_login))
+			.route("/passkeys/login/finish", post(routes::finish_passkey_login))
+			.route_layer(middleware::from_fn_with_state(app_state.clone(), utils::csrf::protect))
+			.route_layer(middleware::from_fn_with_state(app_state.clone(), utils::metrics::track_request_duration))
+			.with_state(app_state.clone())
+			.layer(cors)
+			// Layers run outside in, so the request ID is set before the request span is opened
+			.layer(PropagateRequestIdLayer::x_request_id())
+			.layer(TraceLayer::new_for_http().make_span_with(utils::telemetry::request_span))
+			.layer(SetRequestIdLayer::x_request_id(MakeRequestUuid));
 
-		let listener = tokio::net::TcpListener::bind(address).await?;
+		// Fail here rather than on the first connection if the certificate can't be used
+		let tls = match &config.tls {
+			Some(tls) => Some((utils::tls::load_certificate(tls).await?, tls.clone())),
+			None => None,
+		};
+
+		let listener = TcpListener::bind(&config.address).await?;
 		let address = listener.local_addr()?.to_string();
-		let server = axum::serve(listener, router);
 
 		Ok(Self {
-			server,
+			listener,
+			// Connection info lets sessions record the client's IP address
+			service: router.into_make_service_with_connect_info::<SocketAddr>(),
+			tls,
+			state: app_state,
+			shutdown: ShutdownHandle::default(),
+			drain_timeout: config.drain_timeout(),
 			address,
 		})
 	}
 
+	// Lets the caller stop the app once it's running, e.g. on a signal or at the end of a test
+	pub fn shutdown_handle(&self) -> ShutdownHandle {
+		self.shutdown.clone()
+	}
+
+	// Serve until shut down through the handle, then give requests in flight and background tasks
+	// up to the drain timeout to finish before dropping them
 	pub async fn run(self) -> Result<(), std::io::Error> {
-		println!("listening on {}", &self.address);
-		self.server.await
+		tracing::info!(address = %self.address, tls = self.tls.is_some(), "listening");
+
+		let background_tasks = TaskTracker::new();
+		let signal = self.shutdown.clone();
+		let mut server: Pin<Box<dyn Future<Output = Result<(), std::io::Error>> + Send>> = match self.tls {
+			None => Box::pin(
+				axum::serve(self.listener, self.service)
+					.with_graceful_shutdown(async move { signal.wait().await })
+					.into_future(),
+			),
+			Some((rustls_config, tls)) => {
+				background_tasks.spawn(utils::tls::reload_certificate(rustls_config.clone(), tls, self.shutdown.clone()));
+
+				let handle = axum_server::Handle::new();
+				let server_handle = handle.clone();
+				background_tasks.spawn(async move {
+					signal.wait().await;
+					server_handle.graceful_shutdown(None);
+				});
+
+				Box::pin(
+					axum_server::from_tcp_rustls(self.listener.into_std()?, rustls_config)
+						.handle(handle)
+						.serve(self.service),
+				)
+			}
+		};
+		background_tasks.spawn(utils::background::prune_expired(self.state, self.shutdown.clone()));
+		background_tasks.close();
+
+		// Without a shutdown the server only returns if it fails
+		tokio::select! {
+			result = &mut server => {
+				self.shutdown.shutdown();
+				return result;
+			}
+			_ = self.shutdown.wait() => {}
+		}
+		tracing::info!(drain_timeout = ?self.drain_timeout, "shutting down, draining connections");
+
+		let drained = tokio::time::timeout(self.drain_timeout, async {
+			let result = server.await;
+			background_tasks.wait().await;
+			result
+		}).await;
+
+		match drained {
+			Ok(result) => result?,
+			Err(_) => tracing::warn!("drain timeout reached, dropping the remaining connections"),
+		}
+		tracing::info!("shut down");
+
+		Ok(())
 	}
 }
 
@@ -91,6 +263,32 @@ impl IntoResponse for AuthAPIError {
 			AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Missing authentication token"),
 			AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid authentication token"),
 			AuthAPIError::Invalid2FACredentials => (StatusCode::UNAUTHORIZED, "Invalid 2FA code or login attempt ID"),
+			AuthAPIError::InvalidProfile => (StatusCode::BAD_REQUEST, "Invalid profile data"),
+			AuthAPIError::InsufficientScope => (StatusCode::FORBIDDEN, "The token does not grant access to this resource"),
+			AuthAPIError::InvalidUserCode => (StatusCode::BAD_REQUEST, "Invalid or expired user code"),
+			AuthAPIError::SessionNotFound => (StatusCode::NOT_FOUND, "Session not found"),
+			AuthAPIError::TwoFactorNotEnabled => (StatusCode::BAD_REQUEST, "2FA is not enabled for this account"),
+			AuthAPIError::TrustedDeviceNotFound => (StatusCode::NOT_FOUND, "Trusted device not found"),
+			AuthAPIError::Resend2FACooldown => (StatusCode::TOO_MANY_REQUESTS, "Please wait before requesting another 2FA code"),
+			AuthAPIError::Resend2FALimitReached => (StatusCode::TOO_MANY_REQUESTS, "Too many 2FA codes requested, please log in again"),
+			AuthAPIError::TwoFADeliveryFailed => (StatusCode::BAD_GATEWAY, "Failed to deliver the 2FA code"),
+			AuthAPIError::InvalidTwoFAChannel => (StatusCode::BAD_REQUEST, "Invalid 2FA channel"),
+			AuthAPIError::InvalidPasskey => (StatusCode::UNAUTHORIZED, "Passkey verification failed"),
+			AuthAPIError::PasskeyNotFound => (StatusCode::NOT_FOUND, "Passkey not found"),
+			AuthAPIError::MissingPermission => (StatusCode::FORBIDDEN, "You don't have permission to do this"),
+			AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+			AuthAPIError::InvalidRole => (StatusCode::BAD_REQUEST, "Invalid role"),
+			AuthAPIError::AccountDisabled => (StatusCode::FORBIDDEN, "This account has been disabled"),
+			AuthAPIError::AccountPendingVerification => (StatusCode::FORBIDDEN, "This account has not been verified yet"),
+			AuthAPIError::AccountLocked => (StatusCode::LOCKED, "Too many failed login attempts, please try again later"),
+			AuthAPIError::PasswordResetRequired => (StatusCode::FORBIDDEN, "The password has to be changed before logging in"),
+			AuthAPIError::PasswordlessAccount => (StatusCode::BAD_REQUEST, "The account has no password"),
+			AuthAPIError::CannotDeactivateSelf => (StatusCode::BAD_REQUEST, "You can't deactivate your own account"),
+			AuthAPIError::InvalidAccountStatus => (StatusCode::BAD_REQUEST, "Invalid account status"),
+			AuthAPIError::ImpersonationNotAllowed => (StatusCode::FORBIDDEN, "This can't be done while impersonating a user"),
+			AuthAPIError::CannotImpersonateSelf => (StatusCode::BAD_REQUEST, "You can't impersonate yourself"),
+			AuthAPIError::InvalidAuditQuery => (StatusCode::BAD_REQUEST, "Invalid audit log query"),
+			AuthAPIError::CrossSiteRequest => (StatusCode::FORBIDDEN, "Cross-site request rejected, the CSRF token or origin is invalid"),
 		};
 		let body = Json(ErrorResponse {
 			error: error_message.to_string(),
@@ -99,6 +297,28 @@ impl IntoResponse for AuthAPIError {
 	}
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct OAuthErrorResponse {
+	pub error: String,
+	pub error_description: String,
+}
+
+impl IntoResponse for OAuthError {
+	fn into_response(self) -> Response {
+		let status = match self {
+			OAuthError::InvalidClient | OAuthError::LoginRequired => StatusCode::UNAUTHORIZED,
+			OAuthError::TemporarilyUnavailable => StatusCode::TOO_MANY_REQUESTS,
+			OAuthError::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
+			_ => StatusCode::BAD_REQUEST,
+		};
+		let body = Json(OAuthErrorResponse {
+			error: self.code().to_string(),
+			error_description: self.description(),
+		});
+		(status, body).into_response()
+	}
+}
+
 pub type DatabasePool = SqlitePool;
 
 pub async fn get_sql_pool(url: &str) -> DatabasePool {
@@ -1,22 +1,122 @@
-use auth_service::{prod, AppState, Application};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use auth_service::{
+	init_tracing, shutdown_signal, AppState, Application, ClientRegistration, Config, HttpSmsClient, IdTokenKey,
+	JsonLinesAuditSink, SqliteAuditSink, SqliteClientStore,
+};
+use tokio::sync::RwLock;
 
 #[tokio::main]
 async fn main() {
-	let db_pool = configure_db_pool().await;
+	let config = Config::load().unwrap_or_else(|e| {
+		eprintln!("Invalid configuration: {e}");
+		std::process::exit(1);
+	});
+
+	// Run as `auth-service healthcheck` by the container health check, since the image has no curl
+	if std::env::args().nth(1).as_deref() == Some("healthcheck") {
+		std::process::exit(health_check(&config).await);
+	}
+	// Run as `auth-service register-client ...` by the operator to add an OAuth client to the database
+	if std::env::args().nth(1).as_deref() == Some("register-client") {
+		std::process::exit(register_client(&config).await);
+	}
+
+	init_tracing();
+
+	let db_pool = configure_db_pool(&config.database.url).await;
+
+	let client_store = Arc::new(RwLock::new(Box::new(SqliteClientStore::new(db_pool.clone())) as _));
+	let mut app_state = AppState::default().with_client_store(client_store);
+
+	let audit_sink = match &config.audit.log_path {
+		Some(path) => Arc::new(RwLock::new(Box::new(JsonLinesAuditSink::new(path)) as _)),
+		None => Arc::new(RwLock::new(Box::new(SqliteAuditSink::new(db_pool)) as _)),
+	};
+	app_state = app_state.with_audit_sink(audit_sink);
+
+	if let Some(gateway) = &config.sms_gateway {
+		let sms_client = HttpSmsClient::new(app_state.http_client.clone(), gateway.url.clone(), gateway.api_key.clone());
+		app_state = app_state.with_sms_client(Arc::new(RwLock::new(Box::new(sms_client))));
+	}
 
-	let app_state = AppState::default();
+	match &config.auth.id_token_key_path {
+		Some(path) => {
+			let key = std::fs::read_to_string(path)
+				.map_err(|e| e.to_string())
+				.and_then(|pem| IdTokenKey::from_pem(&pem))
+				.unwrap_or_else(|e| {
+					eprintln!("Invalid ID token key {}: {e}", path.display());
+					std::process::exit(1);
+				});
+			app_state = app_state.with_id_token_key(key);
+		}
+		None => tracing::warn!("No ID token key configured, ID tokens can't be verified after a restart"),
+	}
 
-	let app = Application::build(app_state, prod::APP_ADDRESS)
+	let server_config = config.server.clone();
+	let app = Application::build(app_state.with_config(config), &server_config)
 		.await
 		.expect("Failed to build app");
 
+	let shutdown = app.shutdown_handle();
+	tokio::spawn(async move {
+		shutdown_signal().await;
+		shutdown.shutdown();
+	});
+
 	app.run().await.expect("Failed to run app");
 }
 
+// Exit code for the health check: 0 if the running server is ready, 1 otherwise
+async fn health_check(config: &Config) -> i32 {
+	let port = config.server.address.parse::<SocketAddr>().map_or(3000, |address| address.port());
+	// With TLS the port only speaks HTTPS. The certificate is for the public name, not the loopback
+	// address, and it's our own server on the other end, so it isn't checked.
+	let scheme = if config.server.tls.is_some() { "https" } else { "http" };
+	let Ok(http_client) = reqwest::Client::builder().danger_accept_invalid_certs(config.server.tls.is_some()).build() else {
+		return 1;
+	};
+	let response = http_client.get(format!("{scheme}://127.0.0.1:{port}/health/ready")).send().await;
+
+	match response {
+		Ok(response) if response.status().is_success() => 0,
+		_ => 1,
+	}
+}
+
+// Exit code for the client registration: 0 if the client was added, 1 otherwise
+async fn register_client(config: &Config) -> i32 {
+	let registration = match ClientRegistration::parse(std::env::args().skip(2)) {
+		Ok(registration) => registration,
+		Err(e) => {
+			eprintln!("{e}");
+			return 1;
+		}
+	};
+	let client_id = registration.client_id.clone();
+
+	let db_pool = configure_db_pool(&config.database.url).await;
+	match registration.register(&mut SqliteClientStore::new(db_pool)).await {
+		Ok(Some(secret)) => {
+			println!("Registered confidential client {client_id}");
+			println!("Client secret, which can't be shown again: {secret}");
+			0
+		}
+		Ok(None) => {
+			println!("Registered public client {client_id}");
+			0
+		}
+		Err(e) => {
+			eprintln!("Failed to register client {client_id}: {e}");
+			1
+		}
+	}
+}
 
-async fn configure_db_pool() -> auth_service::DatabasePool {
-	let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-	let db = auth_service::get_sql_pool(&url).await;
+async fn configure_db_pool(url: &str) -> auth_service::DatabasePool {
+	let db = auth_service::get_sql_pool(url).await;
 	sqlx::migrate!().run(&db).await.expect("Failed to run migrations");
 
 	db
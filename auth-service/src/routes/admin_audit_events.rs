@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuditEvent, AuditEventKind, AuditQuery, AuthAPIError, Email};
+use crate::utils::auth::{require, Authorized};
+use crate::AppState;
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+// Search the audit log, newest events first
+pub async fn list_audit_events(
+	State(state): State<AppState>,
+	_: Authorized<require::ReadAuditLog>,
+	Query(query): Query<ListAuditEventsQuery>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let query = query.to_audit_query().map_err(|_| AuthAPIError::InvalidAuditQuery)?;
+
+	let events = state.audit_sink
+		.read().await
+		.query(&query).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(Json(AuditEventListResponse {
+		events: events.into_iter().map(AuditEventResponse::from).collect(),
+	}))
+}
+
+#[derive(Deserialize)]
+pub struct ListAuditEventsQuery {
+	pub email: Option<String>,
+	// One of the event types, e.g. `login_failed`
+	#[serde(rename = "type")]
+	pub kind: Option<String>,
+	// RFC 3339 timestamps. `since` is inclusive, `until` exclusive.
+	pub since: Option<String>,
+	pub until: Option<String>,
+	pub limit: Option<usize>,
+}
+
+impl ListAuditEventsQuery {
+	fn to_audit_query(&self) -> Result<AuditQuery, String> {
+		let timestamp = |value: &Option<String>| {
+			value
+				.as_deref()
+				.map(|value| DateTime::parse_from_rfc3339(value).map(|at| at.with_timezone(&Utc)))
+				.transpose()
+				.map_err(|e| e.to_string())
+		};
+
+		Ok(AuditQuery {
+			email: self.email.as_deref().map(Email::from_str).transpose()?,
+			kind: self.kind.as_deref().map(AuditEventKind::from_str).transpose()?,
+			since: timestamp(&self.since)?,
+			until: timestamp(&self.until)?,
+			limit: self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+		})
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEventListResponse {
+	pub events: Vec<AuditEventResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEventResponse {
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub email: String,
+	// The administrator who acted on the account, if it wasn't its owner
+	pub actor: Option<String>,
+	pub ip: Option<String>,
+	pub details: Option<String>,
+	pub at: DateTime<Utc>,
+}
+
+impl From<AuditEvent> for AuditEventResponse {
+	fn from(event: AuditEvent) -> Self {
+		Self {
+			kind: event.kind.to_string(),
+			email: event.email.as_ref().to_string(),
+			actor: event.actor.map(|actor| actor.as_ref().to_string()),
+			ip: event.ip,
+			details: event.details,
+			at: event.at,
+		}
+	}
+}
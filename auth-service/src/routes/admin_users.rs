@@ -0,0 +1,375 @@
+use std::str::FromStr;
+
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+	permissions_of, AccountStatus, AuditEvent, AuditEventKind, AuthAPIError, Email, Permission, Role, TwoFAChannel, User,
+};
+use crate::utils::audit::record_event;
+use crate::utils::auth::{
+	ensure_active, generate_impersonation_token, require, start_session, Authorized, Claims, ClientInfo,
+	IMPERSONATION_TOKEN_TTL_SECONDS,
+};
+use crate::AppState;
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+pub async fn list_users(
+	State(state): State<AppState>,
+	_: Authorized<require::ReadUsers>,
+	Query(query): Query<ListUsersQuery>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let page = query.page.unwrap_or(1).max(1);
+	let per_page = query.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+	let search = query.search.as_deref().map(str::trim).filter(|search| !search.is_empty());
+
+	let users = state.user_store
+		.read().await
+		.list_users(search, (page - 1).saturating_mul(per_page), per_page).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(Json(UserListResponse {
+		users: users.users.iter().map(AdminUserResponse::from).collect(),
+		total: users.total,
+		page,
+		per_page,
+	}))
+}
+
+pub async fn get_user(
+	State(state): State<AppState>,
+	_: Authorized<require::ReadUsers>,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let user = find_user(&state, &email).await?;
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+pub async fn update_user_status(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+	Json(request): Json<UpdateStatusRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let status = AccountStatus::from_str(&request.status).map_err(|_| AuthAPIError::InvalidAccountStatus)?;
+	let user = set_status(&state, &admin.claims, client, &email, status).await?;
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+// Shorthand for setting the status to disabled
+pub async fn disable_user(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let user = set_status(&state, &admin.claims, client, &email, AccountStatus::Disabled).await?;
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+// Shorthand for setting the status to active
+pub async fn enable_user(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let user = set_status(&state, &admin.claims, client, &email, AccountStatus::Active).await?;
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+// Accounts that aren't active can't log in with any method, so their sessions are revoked as well
+async fn set_status(
+	state: &AppState,
+	admin: &Claims,
+	client: ClientInfo,
+	email: &str,
+	status: AccountStatus,
+) -> Result<User, AuthAPIError> {
+	if admin.sub == email && status != AccountStatus::Active {
+		return Err(AuthAPIError::CannotDeactivateSelf);
+	}
+
+	let mut user = find_user(state, email).await?;
+	user.status = status;
+	save_user(state, &user).await?;
+	if status != AccountStatus::Active {
+		revoke_sessions(state, &user).await?;
+	}
+	let event = admin_event(AuditEventKind::AccountStatusChanged, &user, admin, client).with_details(status.as_str());
+	record_event(state, event).await;
+
+	Ok(user)
+}
+
+// For when the password may have leaked: the user is logged out everywhere, and has to
+// change the password through `/password` before logging in with it again
+pub async fn force_password_reset(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let mut user = find_user(&state, &email).await?;
+	if user.password().is_none() {
+		return Err(AuthAPIError::PasswordlessAccount);
+	}
+	user.password_reset_required = true;
+	save_user(&state, &user).await?;
+	revoke_sessions(&state, &user).await?;
+	record_event(&state, admin_event(AuditEventKind::PasswordResetForced, &user, &admin.claims, client)).await;
+
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+// For users who lost access to their 2FA channel. 2FA stays on, but codes go back to the
+// user's email address, and recovery codes, trusted devices and any pending code are dropped.
+pub async fn reset_two_fa(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let mut user = find_user(&state, &email).await?;
+	if !user.requires_2fa {
+		return Err(AuthAPIError::TwoFactorNotEnabled);
+	}
+	user.two_fa_channel = TwoFAChannel::Email;
+	save_user(&state, &user).await?;
+
+	state.recovery_code_store
+		.write().await
+		.set_codes(user.email(), Vec::new()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	state.trusted_device_store
+		.write().await
+		.remove_devices(&user.email()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	// There may be no login waiting for a code
+	let _ = state.two_fa_code_store.write().await.remove_code(&user.email()).await;
+	record_event(&state, admin_event(AuditEventKind::TwoFactorReset, &user, &admin.claims, client)).await;
+
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+// Lift a lockout caused by too many wrong passwords
+pub async fn unlock_user(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let mut user = find_user(&state, &email).await?;
+	user.failed_login_attempts = 0;
+	user.locked_until = None;
+	save_user(&state, &user).await?;
+	record_event(&state, admin_event(AuditEventKind::AccountUnlocked, &user, &admin.claims, client)).await;
+
+	Ok(Json(AdminUserResponse::from(&user)))
+}
+
+pub async fn get_user_roles(
+	State(state): State<AppState>,
+	_: Authorized<require::ReadUsers>,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let user = find_user(&state, &email).await?;
+	Ok(Json(UserRolesResponse::from(&user)))
+}
+
+// Replace the user's roles. Tokens issued before the change keep the old permissions until they expire.
+pub async fn update_user_roles(
+	State(state): State<AppState>,
+	admin: Authorized<require::ManageUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+	Json(request): Json<UpdateRolesRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let mut roles = request.roles
+		.iter()
+		.map(|role| Role::from_str(role))
+		.collect::<Result<Vec<Role>, _>>()
+		.map_err(|_| AuthAPIError::InvalidRole)?;
+	roles.sort();
+	roles.dedup();
+
+	// Operators can't lock themselves out of role management by accident
+	if admin.claims.sub == email && !permissions_of(&roles).contains(&Permission::ManageUsers) {
+		return Err(AuthAPIError::InvalidRole);
+	}
+
+	let mut user = find_user(&state, &email).await?;
+	user.roles = roles;
+	save_user(&state, &user).await?;
+
+	let roles = user.roles.iter().map(Role::as_str).collect::<Vec<_>>().join(" ");
+	record_event(&state, admin_event(AuditEventKind::RolesChanged, &user, &admin.claims, client).with_details(roles)).await;
+
+	Ok(Json(UserRolesResponse::from(&user)))
+}
+
+// Let support staff see what the user sees. The token gets its own session, so it shows up in
+// the user's session list and can be revoked there, and every use of this route is audited.
+pub async fn impersonate_user(
+	State(state): State<AppState>,
+	admin: Authorized<require::ImpersonateUsers>,
+	client: ClientInfo,
+	Path(email): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	if admin.claims.sub == email {
+		return Err(AuthAPIError::CannotImpersonateSelf);
+	}
+	let actor = Email::from_str(&admin.claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+	let user = find_user(&state, &email).await?;
+	ensure_active(&user)?;
+
+	// Recorded before the session exists, so there's never an impersonation the log doesn't know about
+	state.audit_sink
+		.write().await
+		.record(AuditEvent::new(AuditEventKind::ImpersonationStarted, user.email(), client.ip.clone()).by(actor.clone())).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	let session_id = start_session(&state, &user.email(), client).await?;
+	let token = generate_impersonation_token(&state.config, &user.email(), &session_id, &actor)
+		.map_err(|_| AuthAPIError::TokenCreationError)?;
+
+	Ok(Json(ImpersonationResponse {
+		token,
+		expires_in: IMPERSONATION_TOKEN_TTL_SECONDS,
+	}))
+}
+
+async fn find_user(state: &AppState, email: &str) -> Result<User, AuthAPIError> {
+	let email = Email::from_str(email).map_err(|_| AuthAPIError::UserNotFound)?;
+	state.user_store
+		.read().await
+		.get_user(email).await
+		.map_err(|_| AuthAPIError::UserNotFound)
+}
+
+// An administrator's change to the user's account
+fn admin_event(kind: AuditEventKind, user: &User, admin: &Claims, client: ClientInfo) -> AuditEvent {
+	let mut event = AuditEvent::new(kind, user.email(), client.ip);
+	event.actor = admin.sub.parse().ok();
+	event
+}
+
+async fn save_user(state: &AppState, user: &User) -> Result<(), AuthAPIError> {
+	state.user_store
+		.write().await
+		.update_user(user.clone()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+async fn revoke_sessions(state: &AppState, user: &User) -> Result<(), AuthAPIError> {
+	state.session_store
+		.write().await
+		.remove_sessions(&user.email()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImpersonationResponse {
+	pub token: String,
+	// Seconds until the token expires
+	#[serde(rename = "expiresIn")]
+	pub expires_in: i64,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateStatusRequest {
+	// `active`, `disabled` or `pendingVerification`
+	pub status: String,
+}
+
+#[derive(Deserialize)]
+pub struct ListUsersQuery {
+	// Part of the email address or display name
+	pub search: Option<String>,
+	// Starts at 1
+	pub page: Option<usize>,
+	#[serde(rename = "perPage")]
+	pub per_page: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserListResponse {
+	pub users: Vec<AdminUserResponse>,
+	// Number of users matching the search across all pages
+	pub total: usize,
+	pub page: usize,
+	#[serde(rename = "perPage")]
+	pub per_page: usize,
+}
+
+// What operators get to see of an account. Secrets like the password never leave the store.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserResponse {
+	pub email: String,
+	#[serde(rename = "displayName")]
+	pub display_name: Option<String>,
+	#[serde(rename = "emailVerified")]
+	pub email_verified: bool,
+	#[serde(rename = "hasPassword")]
+	pub has_password: bool,
+	#[serde(rename = "requires2FA")]
+	pub requires_2fa: bool,
+	#[serde(rename = "twoFAChannel")]
+	pub two_fa_channel: String,
+	pub roles: Vec<Role>,
+	pub status: AccountStatus,
+	#[serde(rename = "passwordResetRequired")]
+	pub password_reset_required: bool,
+	#[serde(rename = "failedLoginAttempts")]
+	pub failed_login_attempts: u32,
+	// Only set while the account is locked
+	#[serde(rename = "lockedUntil")]
+	pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl From<&User> for AdminUserResponse {
+	fn from(user: &User) -> Self {
+		Self {
+			email: user.email_str().to_string(),
+			display_name: user.profile.display_name.clone(),
+			email_verified: user.email_verified,
+			has_password: user.password().is_some(),
+			requires_2fa: user.requires_2fa,
+			two_fa_channel: user.two_fa_channel.name().to_string(),
+			roles: user.roles.clone(),
+			status: user.status,
+			password_reset_required: user.password_reset_required,
+			failed_login_attempts: user.failed_login_attempts,
+			locked_until: user.locked_until.filter(|_| user.is_locked()),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+pub struct UpdateRolesRequest {
+	pub roles: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserRolesResponse {
+	pub email: String,
+	pub roles: Vec<Role>,
+	// What the roles add up to, as the scope strings tokens carry
+	pub permissions: Vec<String>,
+}
+
+impl From<&User> for UserRolesResponse {
+	fn from(user: &User) -> Self {
+		Self {
+			email: user.email_str().to_string(),
+			roles: user.roles.clone(),
+			permissions: user.permissions().iter().map(|permission| permission.as_str().to_string()).collect(),
+		}
+	}
+}
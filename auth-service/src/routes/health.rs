@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+
+// The process is up and serving requests
+pub async fn health_live() -> Json<HealthResponse> {
+	Json(HealthResponse {
+		status: "ok".to_string(),
+		checks: BTreeMap::new(),
+	})
+}
+
+// Every store and client the app depends on can serve requests. Answers 503 naming the ones that can't,
+// so the orchestrator holds traffic back until they recover.
+pub async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+	let checks = [
+		("user_store", state.user_store.read().await.health_check().await.is_ok()),
+		("banned_token_store", state.banned_token_store.read().await.health_check().await.is_ok()),
+		("two_fa_code_store", state.two_fa_code_store.read().await.health_check().await.is_ok()),
+		("authorization_code_store", state.authorization_code_store.read().await.health_check().await.is_ok()),
+		("client_store", state.client_store.read().await.health_check().await.is_ok()),
+		("device_code_store", state.device_code_store.read().await.health_check().await.is_ok()),
+		("session_store", state.session_store.read().await.health_check().await.is_ok()),
+		("recovery_code_store", state.recovery_code_store.read().await.health_check().await.is_ok()),
+		("trusted_device_store", state.trusted_device_store.read().await.health_check().await.is_ok()),
+		("passkey_store", state.passkey_store.read().await.health_check().await.is_ok()),
+		("audit_sink", state.audit_sink.read().await.health_check().await.is_ok()),
+		("email_client", state.email_client.read().await.health_check().await.is_ok()),
+		("sms_client", state.sms_client.read().await.health_check().await.is_ok()),
+	];
+
+	let ready = checks.iter().all(|(_, healthy)| *healthy);
+	for (name, _) in checks.iter().filter(|(_, healthy)| !healthy) {
+		tracing::warn!(dependency = name, "Health check failed");
+	}
+
+	let response = HealthResponse {
+		status: if ready { "ok" } else { "unavailable" }.to_string(),
+		checks: checks
+			.into_iter()
+			.map(|(name, healthy)| (name.to_string(), if healthy { "ok" } else { "unavailable" }.to_string()))
+			.collect(),
+	};
+	let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+	(status, Json(response))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+	// `ok` or `unavailable`
+	pub status: String,
+	// The status of each dependency, only filled in by the readiness check
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub checks: BTreeMap<String, String>,
+}
@@ -7,15 +7,37 @@ use axum::http::StatusCode;
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{AuthAPIError, Email, LoginAttemptId, Password, TwoFACode};
+use crate::domain::{AuditEvent, AuditEventKind, AuthAPIError, Email, LoginAttemptId, Password, TwoFACode, User};
+use crate::utils::audit::{ensure_active_login, record_event, record_login};
+use crate::utils::auth::{start_session, verify_password, ClientInfo};
+use crate::utils::trusted_device::trusted_device_id;
+use crate::utils::two_fa::send_2fa_code;
 use crate::AppState;
 
 pub async fn login(
 	State(state): State<AppState>,
+	client: ClientInfo,
 	jar: CookieJar,
 	Json(request): Json<LoginRequest>
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
-	let user_store = state.user_store.read().await;
+	let result = attempt_login(&state, client, jar, request).await;
+
+	let outcome = match &result {
+		Ok((_, response)) if response.status() == StatusCode::PARTIAL_CONTENT => "2fa_required",
+		Ok(_) => "success",
+		Err(_) => "failure",
+	};
+	state.metrics.logins.with_label_values(&[outcome]).inc();
+
+	result
+}
+
+async fn attempt_login(
+	state: &AppState,
+	client: ClientInfo,
+	jar: CookieJar,
+	request: LoginRequest,
+) -> Result<(CookieJar, Response), AuthAPIError> {
 	let Ok(user_email) = Email::from_str(&request.email) else {
 		return Err(AuthAPIError::InvalidCredentials);
 	};
@@ -23,50 +45,62 @@ pub async fn login(
 		return Err(AuthAPIError::InvalidCredentials);
 	};
 
-	if user_store.validate_user(user_email.clone(), user_password).await.is_err() {
-		return Err(AuthAPIError::IncorrectPassword);
+	let user = verify_password(state, &user_email, user_password, client.ip.clone()).await?;
+	ensure_active_login(state, &user, client.ip.clone()).await?;
+	if user.password_reset_required {
+		let event = AuditEvent::new(AuditEventKind::LoginFailed, user_email, client.ip).with_details("password reset required");
+		record_event(state, event).await;
+		return Err(AuthAPIError::PasswordResetRequired);
 	}
 
-	let user = user_store.get_user(user_email.clone()).await.map_err(|_| AuthAPIError::InvalidCredentials)?;
-
-	if user.requires_2fa {
-		handle_2fa(user_email, &state, jar).await
+	// Browsers the user told us to remember after a previous 2FA login skip the 2FA step
+	if !user.requires_2fa {
+		handle_no_2fa(&user, state, client, jar, "password").await
+	} else if trusted_device_id(state, &jar, &user_email).await.is_some() {
+		handle_no_2fa(&user, state, client, jar, "password and trusted device").await
 	} else {
-		handle_no_2fa(&user_email, jar).await
+		handle_2fa(&user, state, jar).await
 	}
 }
 
-async fn handle_2fa(email: Email, state: &AppState, jar: CookieJar) -> Result<(CookieJar, Response), AuthAPIError> {
+async fn handle_2fa(user: &User, state: &AppState, jar: CookieJar) -> Result<(CookieJar, Response), AuthAPIError> {
 	let login_attempt_id = LoginAttemptId::default();
 	let two_fa_code = TwoFACode::default();
 
 	state.two_fa_code_store
 		.write().await
-		.add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone()).await
+		.add_code(user.email(), login_attempt_id.clone(), two_fa_code.clone()).await
 		.map_err(|_| AuthAPIError::UnexpectedError)?;
 
-	state.email_client
-		.write().await
-		.send_email(
-			&email,
-			"Let's Get Rusty Bootcamp: 2FA Login",
-			format!("Your 2FA code is: {}", two_fa_code.as_ref()).as_str()
-		).await
-		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	// Drop the attempt if the code never reached the user, so they can simply log in again
+	if let Err(error) = send_2fa_code(state, user, &two_fa_code).await {
+		let _ = state.two_fa_code_store.write().await.remove_code(&user.email()).await;
+		return Err(error);
+	}
 
 	let twofa_response = LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
 		message: "2FA required".to_string(),
 		login_attempt_id: login_attempt_id.as_ref().to_string(),
+		channel: user.two_fa_channel.name().to_string(),
 	});
 
 	Ok((jar, (StatusCode::PARTIAL_CONTENT, Json(twofa_response)).into_response()))
 }
 
-async fn handle_no_2fa(email: &Email, jar: CookieJar) -> Result<(CookieJar, Response), AuthAPIError> {
-	let auth_cookie = crate::utils::auth::generate_auth_cookie(email)
+async fn handle_no_2fa(
+	user: &User,
+	state: &AppState,
+	client: ClientInfo,
+	jar: CookieJar,
+	method: &str,
+) -> Result<(CookieJar, Response), AuthAPIError> {
+	let ip = client.ip.clone();
+	let session_id = start_session(state, &user.email(), client).await?;
+	let auth_cookie = crate::utils::auth::generate_auth_cookie(&state.config, &user.email(), &session_id, &user.permissions())
 		.map_err(|_| AuthAPIError::TokenCreationError)?;
 
 	let updated_jar = jar.add(auth_cookie);
+	record_login(state, &user.email(), ip, method).await;
 
 	Ok((updated_jar, StatusCode::NO_CONTENT.into_response()))
 }
@@ -92,4 +126,6 @@ pub struct TwoFactorAuthResponse {
     pub message: String,
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
+    // Where the code was sent: "email", "sms" or "webhook"
+    pub channel: String,
 }
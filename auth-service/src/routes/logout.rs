@@ -3,23 +3,38 @@ use axum::response::IntoResponse;
 use axum::http::StatusCode;
 use axum_extra::extract::CookieJar;
 
-use crate::domain::AuthAPIError;
-use crate::utils::auth::validate_token;
-use crate::{AppState, JWT_COOKIE_NAME};
+use crate::domain::{AuditEventKind, AuthAPIError, SessionId};
+use crate::utils::audit::{record_event, token_event};
+use crate::utils::auth::{auth_cookie_removal, AuthenticatedUser, ClientInfo, TokenSource};
+use crate::AppState;
 
 pub async fn logout(
 	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
 	jar: CookieJar
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
-	let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
-
-	let token = cookie.value();
-	validate_token(token, &state.banned_token_store).await.map_err(|_| AuthAPIError::InvalidToken)?;
+	let AuthenticatedUser { claims, token, source } = auth;
+	let event = token_event(AuditEventKind::LoggedOut, &claims, client.ip)?;
 	state.banned_token_store.write()
 		.await
-		.add(token.to_string())
+		.add(token)
 		.await
 		.map_err(|_| AuthAPIError::InvalidToken)?;
+	state.metrics.tokens_banned.inc();
+
+	let session_id = SessionId::parse(claims.sid).map_err(|_| AuthAPIError::InvalidToken)?;
+	state.session_store
+		.write().await
+		.remove_session(&session_id).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	record_event(&state, event).await;
+
+	// Clients logging out with a bearer token keep whatever cookie they have
+	let jar = match source {
+		TokenSource::Cookie => jar.remove(auth_cookie_removal(&state.config)),
+		TokenSource::Bearer => jar,
+	};
 
-	Ok((jar.remove(JWT_COOKIE_NAME), StatusCode::OK))
+	Ok((jar, StatusCode::OK))
 }
@@ -0,0 +1,101 @@
+use std::str::FromStr;
+
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Redirect};
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+
+use crate::domain::{AuthAPIError, Email};
+use crate::utils::audit::{ensure_active_login, record_login};
+use crate::utils::auth::{generate_auth_cookie, start_session, verify_email, ClientInfo};
+use crate::utils::magic_link::{decode_magic_link_token, generate_magic_link_token, MAGIC_LINK_TTL_SECONDS};
+use crate::AppState;
+
+// Emails a single-use login link. The response is the same whether or not the
+// account exists, so the route can't be used to find out who has an account.
+pub async fn request_magic_link(
+	State(state): State<AppState>,
+	Json(request): Json<MagicLinkRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = Email::from_str(&request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+	let user = state.user_store.read().await.get_user(email.clone()).await;
+	// The link only proves access to the mailbox, which isn't enough for 2FA accounts
+	if user.is_ok_and(|user| !user.requires_2fa) {
+		let token = generate_magic_link_token(&state.config, &email).map_err(|_| AuthAPIError::TokenCreationError)?;
+		let token: String = form_urlencoded::byte_serialize(token.as_bytes()).collect();
+		let link = format!("{}/login/magic-link/callback?token={token}", state.config.auth.issuer);
+
+		state.email_client
+			.write().await
+			.send_email(
+				&email,
+				"Let's Get Rusty Bootcamp: Login link",
+				format!(
+					"Click this link to log in: {link}\nIt can be used once and expires in {} minutes.",
+					MAGIC_LINK_TTL_SECONDS / 60
+				).as_str()
+			).await
+			.map_err(|_| AuthAPIError::UnexpectedError)?;
+	}
+
+	Ok(Json(MagicLinkResponse {
+		message: "If an account exists for this email, a login link has been sent".to_string(),
+	}))
+}
+
+// Target of the emailed link: logs the user in and sends them to the home page
+pub async fn magic_link_callback(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	jar: CookieJar,
+	Query(query): Query<MagicLinkCallbackQuery>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let claims = decode_magic_link_token(&state.config, &query.token).map_err(|_| AuthAPIError::InvalidToken)?;
+	let email = Email::from_str(&claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+
+	// Links are single-use, so a used token goes on the banned list
+	let mut banned_token_store = state.banned_token_store.write().await;
+	banned_token_store.check(&query.token).await.map_err(|_| AuthAPIError::InvalidToken)?;
+	banned_token_store.add(query.token).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(banned_token_store);
+	state.metrics.tokens_banned.inc();
+
+	// 2FA could have been turned on after the link was sent
+	let mut user_store = state.user_store.write().await;
+	let mut user = user_store.get_user(email.clone()).await.map_err(|_| AuthAPIError::InvalidToken)?;
+	if user.requires_2fa {
+		return Err(AuthAPIError::InvalidToken);
+	}
+	ensure_active_login(&state, &user, client.ip.clone()).await?;
+	// The link was emailed, so following it proves the user owns the mailbox
+	if !user.email_verified {
+		verify_email(&state.config, &mut user);
+		user_store.update_user(user.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	}
+	drop(user_store);
+
+	let ip = client.ip.clone();
+	let session_id = start_session(&state, &email, client).await?;
+	let auth_cookie = generate_auth_cookie(&state.config, &email, &session_id, &user.permissions()).map_err(|_| AuthAPIError::TokenCreationError)?;
+	record_login(&state, &email, ip, "magic link").await;
+
+	Ok((jar.add(auth_cookie), Redirect::to("/")))
+}
+
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+	pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkResponse {
+	pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct MagicLinkCallbackQuery {
+	pub token: String,
+}
@@ -0,0 +1,31 @@
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+
+use crate::domain::AuthAPIError;
+use crate::AppState;
+
+// Prometheus scrape target. Store sizes are read at scrape time rather than tracked on every change.
+pub async fn metrics(State(state): State<AppState>) -> Result<impl IntoResponse, AuthAPIError> {
+	let users = state.user_store
+		.read().await
+		.list_users(None, 0, 0).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?
+		.total;
+	let banned_tokens = state.banned_token_store
+		.read().await
+		.count().await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	let pending_two_fa_codes = state.two_fa_code_store
+		.read().await
+		.count().await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	let metrics = &state.metrics;
+	metrics.users.set(users as i64);
+	metrics.banned_tokens.set(banned_tokens as i64);
+	metrics.pending_two_fa_codes.set(pending_two_fa_codes as i64);
+
+	let body = metrics.render().map_err(|_| AuthAPIError::UnexpectedError)?;
+	Ok(([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body))
+}
@@ -1,11 +1,47 @@
+pub mod admin_audit_events;
+pub mod admin_users;
+pub mod health;
 pub mod login;
 pub mod logout;
+pub mod magic_link;
+pub mod metrics;
+pub mod oauth_authorize;
+pub mod oauth_device_authorization;
+pub mod oauth_token;
+pub mod openid_configuration;
+pub mod passkeys;
+pub mod password;
+pub mod profile;
+pub mod recovery_codes;
+pub mod resend_2fa;
+pub mod sessions;
 pub mod signup;
+pub mod trusted_devices;
+pub mod two_fa_channel;
+pub mod userinfo;
 pub mod verify_2fa;
 pub mod verify_token;
 
+pub use admin_audit_events::*;
+pub use admin_users::*;
+pub use health::*;
 pub use login::*;
 pub use logout::*;
+pub use magic_link::*;
+pub use metrics::*;
+pub use oauth_authorize::*;
+pub use oauth_device_authorization::*;
+pub use oauth_token::*;
+pub use openid_configuration::*;
+pub use passkeys::*;
+pub use password::*;
+pub use profile::*;
+pub use recovery_codes::*;
+pub use resend_2fa::*;
+pub use sessions::*;
 pub use signup::*;
+pub use trusted_devices::*;
+pub use two_fa_channel::*;
+pub use userinfo::*;
 pub use verify_2fa::*;
 pub use verify_token::*;
@@ -0,0 +1,183 @@
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use url::{form_urlencoded, Url};
+
+use crate::domain::{AuditEvent, AuditEventKind, AuthorizationCode, AuthorizationGrant, Email, OAuthClient, OAuthError};
+use crate::utils::audit::record_event;
+use crate::utils::auth::{AuthenticatedUser, ClientInfo};
+use crate::utils::oidc::{has_scope, AUTHORIZATION_CODE_TTL_SECONDS};
+use crate::AppState;
+
+// Entry point of the authorization code flow. Logged in users are sent to the consent
+// screen, everyone else to the login page, which brings them back here afterwards.
+pub async fn oauth_authorize(
+	State(state): State<AppState>,
+	auth: Option<AuthenticatedUser>,
+	Query(request): Query<AuthorizeRequest>,
+) -> Result<Response, OAuthError> {
+	let (client, redirect_uri) = request.resolve_client(&state).await?;
+	if let Err(error) = request.validate(&client) {
+		return Ok(Redirect::to(request.error_redirect(redirect_uri, &error).as_str()).into_response());
+	}
+
+	let query = request.to_query_string();
+	if logged_in_email(auth).is_some() {
+		return Ok(Redirect::to(&format!("/consent.html?{query}")).into_response());
+	}
+
+	let next: String = form_urlencoded::byte_serialize(format!("/oauth/authorize?{query}").as_bytes()).collect();
+	Ok(Redirect::to(&format!("/?next={next}")).into_response())
+}
+
+// Called by the consent screen once the user allowed or denied the client
+pub async fn oauth_authorize_consent(
+	State(state): State<AppState>,
+	client_info: ClientInfo,
+	auth: Option<AuthenticatedUser>,
+	Json(consent): Json<ConsentRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+	let request = consent.request;
+	let (client, redirect_uri) = request.resolve_client(&state).await?;
+	request.validate(&client)?;
+
+	let email = logged_in_email(auth).ok_or(OAuthError::LoginRequired)?;
+
+	if !consent.approve {
+		let redirect_to = request.error_redirect(redirect_uri, &OAuthError::AccessDenied).to_string();
+		return Ok(Json(ConsentResponse { redirect_to }));
+	}
+
+	let code = AuthorizationCode::default();
+	let grant = AuthorizationGrant {
+		email: email.clone(),
+		client_id: request.client_id.clone(),
+		redirect_uri: request.redirect_uri.clone(),
+		scope: request.scope.clone(),
+		nonce: request.nonce.clone(),
+		code_challenge: request.code_challenge.clone().unwrap_or_default(),
+		expires_at: Utc::now() + chrono::Duration::seconds(AUTHORIZATION_CODE_TTL_SECONDS),
+	};
+
+	state.authorization_code_store
+		.write().await
+		.add_code(code.clone(), grant).await
+		.map_err(|_| OAuthError::ServerError)?;
+
+	let details = format!("client {} ({})", request.client_id, request.scope);
+	record_event(&state, AuditEvent::new(AuditEventKind::OAuthAccessGranted, email, client_info.ip).with_details(details)).await;
+
+	let mut redirect_to = redirect_uri;
+	redirect_to.query_pairs_mut().append_pair("code", code.as_ref());
+	if let Some(request_state) = &request.state {
+		redirect_to.query_pairs_mut().append_pair("state", request_state);
+	}
+
+	Ok(Json(ConsentResponse { redirect_to: redirect_to.to_string() }))
+}
+
+// Impersonation tokens count as logged out, so they can't be used to hand clients access to the user's account
+fn logged_in_email(auth: Option<AuthenticatedUser>) -> Option<Email> {
+	let auth = auth?;
+	if auth.claims.act.is_some() {
+		return None;
+	}
+	auth.email().ok()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthorizeRequest {
+	pub response_type: String,
+	pub client_id: String,
+	pub redirect_uri: String,
+	pub scope: String,
+	pub state: Option<String>,
+	pub code_challenge: Option<String>,
+	pub code_challenge_method: Option<String>,
+	pub nonce: Option<String>,
+}
+
+impl AuthorizeRequest {
+	// Errors found before the redirect URI is known to belong to the client can't be sent back to it
+	async fn resolve_client(&self, state: &AppState) -> Result<(OAuthClient, Url), OAuthError> {
+		let client = state.client_store
+			.read().await
+			.get_client(&self.client_id).await
+			.map_err(|_| OAuthError::InvalidRequest("Unknown client_id".to_string()))?;
+
+		if !client.allows_redirect_uri(&self.redirect_uri) {
+			return Err(OAuthError::InvalidRequest("Invalid redirect_uri".to_string()));
+		}
+
+		match Url::parse(&self.redirect_uri) {
+			Ok(url) if matches!(url.scheme(), "http" | "https") && url.fragment().is_none() => Ok((client, url)),
+			_ => Err(OAuthError::InvalidRequest("Invalid redirect_uri".to_string())),
+		}
+	}
+
+	fn validate(&self, client: &OAuthClient) -> Result<(), OAuthError> {
+		if self.response_type != "code" {
+			return Err(OAuthError::UnsupportedResponseType);
+		}
+		if !has_scope(&self.scope, "openid") || !client.allows_scope(&self.scope) {
+			return Err(OAuthError::InvalidScope);
+		}
+		// PKCE is mandatory and only the S256 method is accepted
+		if self.code_challenge.as_deref().unwrap_or_default().is_empty() {
+			return Err(OAuthError::InvalidRequest("code_challenge is required".to_string()));
+		}
+		if self.code_challenge_method.as_deref() != Some("S256") {
+			return Err(OAuthError::InvalidRequest("code_challenge_method must be S256".to_string()));
+		}
+
+		Ok(())
+	}
+
+	fn error_redirect(&self, mut redirect_uri: Url, error: &OAuthError) -> Url {
+		redirect_uri.query_pairs_mut()
+			.append_pair("error", error.code())
+			.append_pair("error_description", &error.description());
+		if let Some(state) = &self.state {
+			redirect_uri.query_pairs_mut().append_pair("state", state);
+		}
+		redirect_uri
+	}
+
+	fn to_query_string(&self) -> String {
+		let mut serializer = form_urlencoded::Serializer::new(String::new());
+		serializer
+			.append_pair("response_type", &self.response_type)
+			.append_pair("client_id", &self.client_id)
+			.append_pair("redirect_uri", &self.redirect_uri)
+			.append_pair("scope", &self.scope);
+
+		let optional = [
+			("state", &self.state),
+			("code_challenge", &self.code_challenge),
+			("code_challenge_method", &self.code_challenge_method),
+			("nonce", &self.nonce),
+		];
+		for (key, value) in optional {
+			if let Some(value) = value {
+				serializer.append_pair(key, value);
+			}
+		}
+
+		serializer.finish()
+	}
+}
+
+#[derive(Deserialize)]
+pub struct ConsentRequest {
+	#[serde(flatten)]
+	pub request: AuthorizeRequest,
+	pub approve: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsentResponse {
+	#[serde(rename = "redirectTo")]
+	pub redirect_to: String,
+}
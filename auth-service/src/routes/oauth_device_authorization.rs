@@ -0,0 +1,137 @@
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+use axum::{Form, Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+	AuditEvent, AuditEventKind, AuthAPIError, DeviceAuthorization, DeviceAuthorizationStatus, DeviceCode, Email, OAuthError, UserCode,
+};
+use crate::routes::oauth_token::authenticate_client;
+use crate::utils::audit::record_event;
+use crate::utils::auth::{ensure_not_impersonated, AuthenticatedUser, ClientInfo};
+use crate::utils::oidc::{DEVICE_CODE_POLL_INTERVAL_SECONDS, DEVICE_CODE_TTL_SECONDS};
+use crate::AppState;
+
+// Starts the device authorization grant (RFC 8628) for input-constrained clients like CLIs
+pub async fn oauth_device_authorization(
+	State(state): State<AppState>,
+	client_info: ClientInfo,
+	headers: HeaderMap,
+	Form(request): Form<DeviceAuthorizationRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+	// Every call stores an authorization until it expires, so they're limited before anything else
+	let ip = client_info.ip.unwrap_or_default();
+	if !state.device_authorization_limiter.check(&ip) {
+		return Err(OAuthError::TemporarilyUnavailable);
+	}
+
+	let client = authenticate_client(&state, &headers, request.client_id, request.client_secret).await?;
+
+	let scope = request.scope.unwrap_or_else(|| "openid".to_string());
+	if !client.allows_scope(&scope) {
+		return Err(OAuthError::InvalidScope);
+	}
+
+	let device_code = DeviceCode::default();
+	let user_code = UserCode::default();
+	let authorization = DeviceAuthorization {
+		client_id: client.client_id,
+		scope,
+		user_code: user_code.clone(),
+		status: DeviceAuthorizationStatus::Pending,
+		expires_at: Utc::now() + chrono::Duration::seconds(DEVICE_CODE_TTL_SECONDS),
+		interval: DEVICE_CODE_POLL_INTERVAL_SECONDS,
+		last_polled_at: None,
+	};
+
+	state.device_code_store
+		.write().await
+		.add_authorization(device_code.clone(), authorization).await
+		.map_err(|_| OAuthError::ServerError)?;
+
+	let verification_uri = format!("{}/device.html", state.config.auth.issuer);
+	let response = DeviceAuthorizationResponse {
+		device_code: device_code.as_ref().to_string(),
+		verification_uri_complete: format!("{verification_uri}?user_code={}", user_code.as_ref()),
+		user_code: user_code.as_ref().to_string(),
+		verification_uri,
+		expires_in: DEVICE_CODE_TTL_SECONDS,
+		interval: DEVICE_CODE_POLL_INTERVAL_SECONDS,
+	};
+
+	Ok(([(header::CACHE_CONTROL, "no-store")], Json(response)))
+}
+
+// Called by the verification page once the logged in user entered the user code
+pub async fn oauth_device_verify(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	Json(request): Json<DeviceVerifyRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+	// Granting a device access would outlive the impersonation
+	ensure_not_impersonated(&claims)?;
+	let email: Email = claims.sub.parse().map_err(|_| AuthAPIError::InvalidToken)?;
+	let user_code = UserCode::parse(&request.user_code).map_err(|_| AuthAPIError::InvalidUserCode)?;
+
+	let mut device_code_store = state.device_code_store.write().await;
+	let (device_code, mut authorization) = device_code_store
+		.get_authorization_by_user_code(&user_code).await
+		.map_err(|_| AuthAPIError::InvalidUserCode)?;
+
+	if authorization.is_expired() || authorization.status != DeviceAuthorizationStatus::Pending {
+		return Err(AuthAPIError::InvalidUserCode);
+	}
+
+	let details = format!("device for client {} ({})", authorization.client_id, authorization.scope);
+	authorization.status = if request.approve {
+		DeviceAuthorizationStatus::Approved(email.clone())
+	} else {
+		DeviceAuthorizationStatus::Denied
+	};
+	device_code_store
+		.update_authorization(&device_code, authorization).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(device_code_store);
+
+	if request.approve {
+		let event = AuditEvent::new(AuditEventKind::OAuthAccessGranted, email, client.ip).with_details(details);
+		record_event(&state, event).await;
+	}
+
+	Ok(Json(DeviceVerifyResponse {
+		message: if request.approve { "Device approved" } else { "Device denied" }.to_string(),
+	}))
+}
+
+#[derive(Deserialize)]
+pub struct DeviceAuthorizationRequest {
+	pub client_id: Option<String>,
+	pub client_secret: Option<String>,
+	pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceAuthorizationResponse {
+	pub device_code: String,
+	pub user_code: String,
+	pub verification_uri: String,
+	pub verification_uri_complete: String,
+	pub expires_in: i64,
+	pub interval: i64,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceVerifyRequest {
+	#[serde(rename = "userCode")]
+	pub user_code: String,
+	pub approve: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceVerifyResponse {
+	pub message: String,
+}
@@ -0,0 +1,243 @@
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+use axum::{Form, Json};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuthorizationCode, DeviceAuthorizationStatus, DeviceCode, Email, OAuthClient, OAuthError, Permission};
+use crate::utils::auth::{generate_scoped_auth_token, start_session, ClientInfo};
+use crate::utils::oidc::{generate_id_token, generate_service_token, has_scope, verify_pkce, DEVICE_CODE_GRANT_TYPE};
+use crate::AppState;
+
+pub async fn oauth_token(
+	State(state): State<AppState>,
+	client_info: ClientInfo,
+	headers: HeaderMap,
+	Form(request): Form<TokenRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+	let client = authenticate_client(&state, &headers, request.client_id.clone(), request.client_secret.clone()).await?;
+
+	let response = match request.grant_type.as_str() {
+		"authorization_code" => handle_authorization_code(&state, client_info, &client, request).await?,
+		"client_credentials" => handle_client_credentials(&state, &client, request)?,
+		DEVICE_CODE_GRANT_TYPE => handle_device_code(&state, client_info, &client, request).await?,
+		_ => return Err(OAuthError::UnsupportedGrantType),
+	};
+
+	// Token responses must never be cached (RFC 6749 section 5.1)
+	Ok((
+		[(header::CACHE_CONTROL, "no-store"), (header::PRAGMA, "no-cache")],
+		Json(response),
+	))
+}
+
+// Clients identify themselves either with HTTP basic auth or with form parameters.
+// Confidential clients must present their secret, public clients must not have one.
+pub(crate) async fn authenticate_client(
+	state: &AppState,
+	headers: &HeaderMap,
+	client_id: Option<String>,
+	client_secret: Option<String>,
+) -> Result<OAuthClient, OAuthError> {
+	let (client_id, client_secret) = match basic_credentials(headers)? {
+		Some(credentials) => credentials,
+		None => (client_id.ok_or(OAuthError::InvalidClient)?, client_secret),
+	};
+
+	let client = state.client_store
+		.read().await
+		.get_client(&client_id).await
+		.map_err(|_| OAuthError::InvalidClient)?;
+
+	match (client.is_confidential(), client_secret) {
+		(true, Some(secret)) if client.verify_secret(&secret) => Ok(client),
+		(false, None) => Ok(client),
+		_ => Err(OAuthError::InvalidClient),
+	}
+}
+
+fn basic_credentials(headers: &HeaderMap) -> Result<Option<(String, Option<String>)>, OAuthError> {
+	let Some(value) = headers.get(header::AUTHORIZATION) else {
+		return Ok(None);
+	};
+
+	let encoded = value.to_str().ok()
+		.and_then(|value| value.strip_prefix("Basic "))
+		.ok_or(OAuthError::InvalidClient)?;
+	let decoded = STANDARD.decode(encoded).ok()
+		.and_then(|decoded| String::from_utf8(decoded).ok())
+		.ok_or(OAuthError::InvalidClient)?;
+	let (client_id, client_secret) = decoded.split_once(':').ok_or(OAuthError::InvalidClient)?;
+
+	Ok(Some((client_id.to_string(), Some(client_secret.to_string()))))
+}
+
+async fn handle_authorization_code(
+	state: &AppState,
+	client_info: ClientInfo,
+	client: &OAuthClient,
+	request: TokenRequest,
+) -> Result<TokenResponse, OAuthError> {
+	let (Some(code), Some(redirect_uri), Some(code_verifier)) =
+		(request.code, request.redirect_uri, request.code_verifier)
+	else {
+		return Err(OAuthError::InvalidRequest(
+			"code, redirect_uri and code_verifier are required".to_string(),
+		));
+	};
+	let code = AuthorizationCode::parse(code).map_err(|_| OAuthError::InvalidGrant)?;
+
+	let grant = state.authorization_code_store
+		.write().await
+		.take_code(&code).await
+		.map_err(|_| OAuthError::InvalidGrant)?;
+
+	if grant.is_expired() || grant.client_id != client.client_id || grant.redirect_uri != redirect_uri {
+		return Err(OAuthError::InvalidGrant);
+	}
+	if !verify_pkce(&code_verifier, &grant.code_challenge) {
+		return Err(OAuthError::InvalidGrant);
+	}
+
+	user_token_response(state, client_info, &grant.email, &grant.client_id, grant.scope, grant.nonce.as_deref()).await
+}
+
+// Polled by the device until the user approved or denied it on the verification page (RFC 8628 section 3.4)
+async fn handle_device_code(
+	state: &AppState,
+	client_info: ClientInfo,
+	client: &OAuthClient,
+	request: TokenRequest,
+) -> Result<TokenResponse, OAuthError> {
+	let device_code = request.device_code
+		.ok_or_else(|| OAuthError::InvalidRequest("device_code is required".to_string()))?;
+	let device_code = DeviceCode::parse(device_code).map_err(|_| OAuthError::InvalidGrant)?;
+
+	let mut device_code_store = state.device_code_store.write().await;
+	let mut authorization = device_code_store
+		.get_authorization(&device_code).await
+		.map_err(|_| OAuthError::InvalidGrant)?;
+
+	if authorization.client_id != client.client_id {
+		return Err(OAuthError::InvalidGrant);
+	}
+	if authorization.is_expired() {
+		let _ = device_code_store.remove_authorization(&device_code).await;
+		return Err(OAuthError::ExpiredToken);
+	}
+
+	let now = chrono::Utc::now();
+	let too_fast = authorization.last_polled_at
+		.is_some_and(|last| now - last < chrono::Duration::seconds(authorization.interval));
+	authorization.last_polled_at = Some(now);
+
+	if too_fast {
+		authorization.interval += 5;
+		device_code_store.update_authorization(&device_code, authorization).await
+			.map_err(|_| OAuthError::ServerError)?;
+		return Err(OAuthError::SlowDown);
+	}
+
+	match authorization.status.clone() {
+		DeviceAuthorizationStatus::Pending => {
+			device_code_store.update_authorization(&device_code, authorization).await
+				.map_err(|_| OAuthError::ServerError)?;
+			Err(OAuthError::AuthorizationPending)
+		}
+		DeviceAuthorizationStatus::Denied => {
+			let _ = device_code_store.remove_authorization(&device_code).await;
+			Err(OAuthError::AccessDenied)
+		}
+		DeviceAuthorizationStatus::Approved(email) => {
+			device_code_store.remove_authorization(&device_code).await
+				.map_err(|_| OAuthError::InvalidGrant)?;
+			drop(device_code_store);
+			user_token_response(state, client_info, &email, &authorization.client_id, authorization.scope, None).await
+		}
+	}
+}
+
+// Access token for a user, plus an ID token when the client asked for `openid`.
+// Every grant starts its own session, so the user can revoke the client's access.
+async fn user_token_response(
+	state: &AppState,
+	client_info: ClientInfo,
+	email: &Email,
+	client_id: &str,
+	scope: String,
+	nonce: Option<&str>,
+) -> Result<TokenResponse, OAuthError> {
+	// Clients only get the user's permissions they asked for as scopes
+	let permissions: Vec<Permission> = state.user_store
+		.read().await
+		.get_user(email.clone()).await
+		.map_err(|_| OAuthError::InvalidGrant)?
+		.permissions()
+		.into_iter()
+		.filter(|permission| has_scope(&scope, permission.as_str()))
+		.collect();
+
+	let session_id = start_session(state, email, client_info).await
+		.map_err(|_| OAuthError::ServerError)?;
+	let access_token = generate_scoped_auth_token(&state.config, email, &session_id, Some(&scope), &permissions)
+		.map_err(|_| OAuthError::ServerError)?;
+	let id_token = if has_scope(&scope, "openid") {
+		Some(generate_id_token(&state.config, &state.id_token_key, email, client_id, nonce).map_err(|_| OAuthError::ServerError)?)
+	} else {
+		None
+	};
+
+	Ok(TokenResponse {
+		access_token,
+		token_type: "Bearer".to_string(),
+		expires_in: state.config.auth.token_ttl_seconds,
+		id_token,
+		scope,
+	})
+}
+
+// Machine-to-machine grant: the token represents the client itself, not a user
+fn handle_client_credentials(state: &AppState, client: &OAuthClient, request: TokenRequest) -> Result<TokenResponse, OAuthError> {
+	if !client.is_confidential() {
+		return Err(OAuthError::UnauthorizedClient);
+	}
+
+	let scope = request.scope.unwrap_or_else(|| client.allowed_scopes.join(" "));
+	if !client.allows_scope(&scope) {
+		return Err(OAuthError::InvalidScope);
+	}
+
+	let access_token = generate_service_token(&state.config, &client.client_id, &scope).map_err(|_| OAuthError::ServerError)?;
+
+	Ok(TokenResponse {
+		access_token,
+		token_type: "Bearer".to_string(),
+		expires_in: state.config.auth.token_ttl_seconds,
+		id_token: None,
+		scope,
+	})
+}
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+	pub grant_type: String,
+	pub code: Option<String>,
+	pub redirect_uri: Option<String>,
+	pub client_id: Option<String>,
+	pub client_secret: Option<String>,
+	pub code_verifier: Option<String>,
+	pub device_code: Option<String>,
+	pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+	pub access_token: String,
+	pub token_type: String,
+	pub expires_in: i64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub id_token: Option<String>,
+	pub scope: String,
+}
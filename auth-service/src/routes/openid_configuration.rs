@@ -0,0 +1,72 @@
+use axum::extract::State;
+use axum::Json;
+use jsonwebtoken::jwk::JwkSet;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::oidc::DEVICE_CODE_GRANT_TYPE;
+use crate::AppState;
+
+// OpenID Connect discovery document, see OpenID Connect Discovery 1.0 section 3
+pub async fn openid_configuration(State(state): State<AppState>) -> Json<OpenIdConfiguration> {
+	let issuer = state.config.auth.issuer.clone();
+
+	Json(OpenIdConfiguration {
+		authorization_endpoint: format!("{issuer}/oauth/authorize"),
+		token_endpoint: format!("{issuer}/oauth/token"),
+		userinfo_endpoint: format!("{issuer}/userinfo"),
+		device_authorization_endpoint: format!("{issuer}/oauth/device_authorization"),
+		jwks_uri: format!("{issuer}/.well-known/jwks.json"),
+		issuer,
+		response_types_supported: vec!["code".to_string()],
+		grant_types_supported: vec![
+			"authorization_code".to_string(),
+			"client_credentials".to_string(),
+			DEVICE_CODE_GRANT_TYPE.to_string(),
+		],
+		subject_types_supported: vec!["public".to_string()],
+		id_token_signing_alg_values_supported: vec!["ES256".to_string()],
+		scopes_supported: vec!["openid".to_string(), "email".to_string(), "profile".to_string()],
+		token_endpoint_auth_methods_supported: vec![
+			"none".to_string(),
+			"client_secret_basic".to_string(),
+			"client_secret_post".to_string(),
+		],
+		code_challenge_methods_supported: vec!["S256".to_string()],
+		claims_supported: vec![
+			"iss".to_string(),
+			"sub".to_string(),
+			"aud".to_string(),
+			"exp".to_string(),
+			"iat".to_string(),
+			"nonce".to_string(),
+			"email".to_string(),
+			"email_verified".to_string(),
+			"name".to_string(),
+			"locale".to_string(),
+			"picture".to_string(),
+		],
+	})
+}
+
+// Public keys ID tokens are signed with (RFC 7517), found through `jwks_uri`
+pub async fn jwks(State(state): State<AppState>) -> Json<JwkSet> {
+	Json(state.id_token_key.jwks())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+	pub issuer: String,
+	pub authorization_endpoint: String,
+	pub token_endpoint: String,
+	pub userinfo_endpoint: String,
+	pub device_authorization_endpoint: String,
+	pub jwks_uri: String,
+	pub response_types_supported: Vec<String>,
+	pub grant_types_supported: Vec<String>,
+	pub subject_types_supported: Vec<String>,
+	pub id_token_signing_alg_values_supported: Vec<String>,
+	pub scopes_supported: Vec<String>,
+	pub token_endpoint_auth_methods_supported: Vec<String>,
+	pub code_challenge_methods_supported: Vec<String>,
+	pub claims_supported: Vec<String>,
+}
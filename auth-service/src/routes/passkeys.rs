@@ -0,0 +1,355 @@
+use std::str::FromStr;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::domain::{
+	AuditEvent, AuditEventKind, AuthAPIError, Email, Passkey, PasskeyCeremony, PasskeyChallenge, PasskeyId, PasskeyStoreError,
+	PASSKEY_CHALLENGE_TTL_SECONDS,
+};
+use crate::utils::audit::{ensure_active_login, record_event, record_login};
+use crate::utils::auth::{
+	ensure_not_impersonated, generate_auth_cookie, start_session, AuthenticatedUser, ClientInfo,
+};
+use crate::utils::webauthn::{
+	authenticate_passkey, parse_attestation_object, relying_party_id, verify_client_data, PasskeyAssertion, COSE_ALG_ES256,
+};
+use crate::AppState;
+
+const RELYING_PARTY_NAME: &str = "Let's Get Rusty Bootcamp";
+
+// First half of the registration ceremony: the options to pass to `navigator.credentials.create()`
+pub async fn start_passkey_registration(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = credential_owner(&auth)?;
+	let user = state.user_store
+		.read().await
+		.get_user(email.clone()).await
+		.map_err(|_| AuthAPIError::InvalidToken)?;
+
+	let challenge = PasskeyChallenge::new(PasskeyCeremony::Registration(email.clone()));
+	let mut passkey_store = state.passkey_store.write().await;
+	// Authenticators refuse to register a second credential for the same account
+	let exclude_credentials = passkey_store
+		.get_passkeys(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?
+		.iter()
+		.map(|passkey| CredentialDescriptor::new(&passkey.id))
+		.collect();
+	passkey_store.add_challenge(challenge.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(Json(PasskeyRegistrationOptions {
+		challenge: challenge.value,
+		rp: RelyingParty {
+			id: relying_party_id(&state.config),
+			name: RELYING_PARTY_NAME.to_string(),
+		},
+		user: PasskeyUser {
+			// The user handle is stored on the authenticator, so it shouldn't be the email itself
+			id: URL_SAFE_NO_PAD.encode(Sha256::digest(email.as_ref().as_bytes())),
+			name: email.as_ref().to_string(),
+			display_name: user.profile.display_name.unwrap_or_else(|| email.as_ref().to_string()),
+		},
+		pub_key_cred_params: vec![CredentialParameters {
+			kind: "public-key".to_string(),
+			alg: COSE_ALG_ES256,
+		}],
+		timeout: PASSKEY_CHALLENGE_TTL_SECONDS as u64 * 1000,
+		attestation: "none".to_string(),
+		exclude_credentials,
+		authenticator_selection: AuthenticatorSelection {
+			resident_key: "preferred".to_string(),
+			user_verification: "preferred".to_string(),
+		},
+	}))
+}
+
+// Second half of the registration ceremony: check and store the new credential
+pub async fn finish_passkey_registration(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	Json(request): Json<PasskeyRegistrationRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = credential_owner(&auth)?;
+
+	let decode = |value: &str| URL_SAFE_NO_PAD.decode(value).map_err(|_| AuthAPIError::InvalidPasskey);
+	let client_data_json = decode(&request.client_data_json)?;
+	let attestation_object = decode(&request.attestation_object)?;
+	let name = request.name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+
+	let challenge = verify_client_data(&state.config, &client_data_json, "webauthn.create").map_err(|_| AuthAPIError::InvalidPasskey)?;
+	let mut passkey_store = state.passkey_store.write().await;
+	let challenge = passkey_store.take_challenge(&challenge).await.map_err(|_| AuthAPIError::InvalidPasskey)?;
+	if challenge.ceremony != PasskeyCeremony::Registration(email.clone()) {
+		return Err(AuthAPIError::InvalidPasskey);
+	}
+
+	let auth_data = parse_attestation_object(&state.config, &attestation_object).map_err(|_| AuthAPIError::InvalidPasskey)?;
+	let credential = auth_data.credential.ok_or(AuthAPIError::InvalidPasskey)?;
+	let id = PasskeyId::from_bytes(&credential.id);
+	if PasskeyId::parse(&request.id).ok() != Some(id.clone()) {
+		return Err(AuthAPIError::InvalidPasskey);
+	}
+
+	let passkey = Passkey {
+		id,
+		email,
+		name,
+		public_key: credential.public_key,
+		sign_count: auth_data.sign_count,
+		created_at: Utc::now(),
+		last_used_at: None,
+	};
+	passkey_store.add_passkey(passkey.clone()).await.map_err(|error| match error {
+		PasskeyStoreError::PasskeyAlreadyExists => AuthAPIError::InvalidPasskey,
+		_ => AuthAPIError::UnexpectedError,
+	})?;
+	drop(passkey_store);
+
+	let event = AuditEvent::new(AuditEventKind::PasskeyRegistered, passkey.email.clone(), client.ip)
+		.with_details(passkey.id.as_ref());
+	record_event(&state, event).await;
+
+	Ok((StatusCode::CREATED, Json(PasskeyResponse::from(passkey))))
+}
+
+pub async fn list_passkeys(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = auth.email()?;
+
+	let passkeys = state.passkey_store
+		.read().await
+		.get_passkeys(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	let passkeys: Vec<PasskeyResponse> = passkeys.into_iter().map(PasskeyResponse::from).collect();
+	Ok(Json(passkeys))
+}
+
+pub async fn remove_passkey(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	Path(id): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = credential_owner(&auth)?;
+	let id = PasskeyId::parse(&id).map_err(|_| AuthAPIError::PasskeyNotFound)?;
+
+	let mut passkey_store = state.passkey_store.write().await;
+	let passkey = passkey_store.get_passkey(&id).await.map_err(|_| AuthAPIError::PasskeyNotFound)?;
+	// Other users' passkeys are reported as missing rather than forbidden
+	if passkey.email != email {
+		return Err(AuthAPIError::PasskeyNotFound);
+	}
+	passkey_store.remove_passkey(&id).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(passkey_store);
+
+	record_event(&state, AuditEvent::new(AuditEventKind::PasskeyRemoved, email, client.ip).with_details(id.as_ref())).await;
+
+	Ok(StatusCode::NO_CONTENT)
+}
+
+// First half of the authentication ceremony: the options to pass to `navigator.credentials.get()`.
+// Without an email, any discoverable passkey stored on the authenticator can answer. With one, only that
+// user's passkeys can. Their credential IDs are only listed for the login attempt waiting on its second
+// factor, as anyone could ask which accounts exist and have passkeys otherwise.
+pub async fn start_passkey_login(
+	State(state): State<AppState>,
+	Json(request): Json<PasskeyLoginStartRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = request.email
+		.map(|email| Email::from_str(&email))
+		.transpose()
+		.map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+	let pending_login = match (&email, &request.login_attempt_id) {
+		(Some(email), Some(login_attempt_id)) => state.two_fa_code_store
+			.read().await
+			.get_code(email).await
+			.is_ok_and(|(pending, _)| pending.as_ref() == login_attempt_id),
+		_ => false,
+	};
+
+	let challenge = PasskeyChallenge::new(PasskeyCeremony::Authentication(email.clone()));
+	let mut passkey_store = state.passkey_store.write().await;
+	let allow_credentials = match &email {
+		Some(email) if pending_login => passkey_store
+			.get_passkeys(email).await
+			.map_err(|_| AuthAPIError::UnexpectedError)?
+			.iter()
+			.map(|passkey| CredentialDescriptor::new(&passkey.id))
+			.collect(),
+		_ => Vec::new(),
+	};
+	passkey_store.add_challenge(challenge.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(Json(PasskeyAuthenticationOptions {
+		challenge: challenge.value,
+		rp_id: relying_party_id(&state.config),
+		timeout: PASSKEY_CHALLENGE_TTL_SECONDS as u64 * 1000,
+		allow_credentials,
+		// Passwordless logins insist on user verification when the assertion comes back
+		user_verification: "preferred".to_string(),
+	}))
+}
+
+// Passwordless login. The authenticator has to verify the user (PIN or biometrics), which
+// already makes this a multi-factor login, so 2FA accounts skip the 2FA step as well.
+pub async fn finish_passkey_login(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	jar: CookieJar,
+	Json(assertion): Json<PasskeyAssertion>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let passkey = authenticate_passkey(&state, &assertion, None, true).await?;
+
+	let user = state.user_store
+		.read().await
+		.get_user(passkey.email.clone()).await
+		.map_err(|_| AuthAPIError::InvalidPasskey)?;
+	ensure_active_login(&state, &user, client.ip.clone()).await?;
+
+	let ip = client.ip.clone();
+	let session_id = start_session(&state, &passkey.email, client).await?;
+	let auth_cookie = generate_auth_cookie(&state.config, &passkey.email, &session_id, &user.permissions())
+		.map_err(|_| AuthAPIError::TokenCreationError)?;
+	record_login(&state, &passkey.email, ip, "passkey").await;
+
+	Ok((jar.add(auth_cookie), StatusCode::NO_CONTENT))
+}
+
+// The user's email, for routes that add or remove passkeys
+fn credential_owner(auth: &AuthenticatedUser) -> Result<Email, AuthAPIError> {
+	ensure_not_impersonated(&auth.claims)?;
+	auth.email()
+}
+
+#[derive(Deserialize)]
+pub struct PasskeyRegistrationRequest {
+	pub id: String,
+	#[serde(rename = "clientDataJSON")]
+	pub client_data_json: String,
+	#[serde(rename = "attestationObject")]
+	pub attestation_object: String,
+	// Label to tell the user's passkeys apart, e.g. "Work laptop"
+	#[serde(default)]
+	pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PasskeyLoginStartRequest {
+	#[serde(default)]
+	pub email: Option<String>,
+	// The login waiting on its second factor, as returned by /login
+	#[serde(rename = "loginAttemptId", default)]
+	pub login_attempt_id: Option<String>,
+}
+
+// Binary values are base64url-encoded, the browser has to decode them before calling the WebAuthn API
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyRegistrationOptions {
+	pub challenge: String,
+	pub rp: RelyingParty,
+	pub user: PasskeyUser,
+	#[serde(rename = "pubKeyCredParams")]
+	pub pub_key_cred_params: Vec<CredentialParameters>,
+	// Milliseconds
+	pub timeout: u64,
+	pub attestation: String,
+	#[serde(rename = "excludeCredentials")]
+	pub exclude_credentials: Vec<CredentialDescriptor>,
+	#[serde(rename = "authenticatorSelection")]
+	pub authenticator_selection: AuthenticatorSelection,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyAuthenticationOptions {
+	pub challenge: String,
+	#[serde(rename = "rpId")]
+	pub rp_id: String,
+	// Milliseconds
+	pub timeout: u64,
+	#[serde(rename = "allowCredentials")]
+	pub allow_credentials: Vec<CredentialDescriptor>,
+	#[serde(rename = "userVerification")]
+	pub user_verification: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelyingParty {
+	pub id: String,
+	pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyUser {
+	pub id: String,
+	pub name: String,
+	#[serde(rename = "displayName")]
+	pub display_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CredentialParameters {
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub alg: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CredentialDescriptor {
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub id: String,
+}
+
+impl CredentialDescriptor {
+	fn new(id: &PasskeyId) -> Self {
+		Self {
+			kind: "public-key".to_string(),
+			id: id.as_ref().to_string(),
+		}
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthenticatorSelection {
+	#[serde(rename = "residentKey")]
+	pub resident_key: String,
+	#[serde(rename = "userVerification")]
+	pub user_verification: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasskeyResponse {
+	pub id: String,
+	pub name: Option<String>,
+	#[serde(rename = "createdAt")]
+	pub created_at: DateTime<Utc>,
+	#[serde(rename = "lastUsedAt")]
+	pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<Passkey> for PasskeyResponse {
+	fn from(passkey: Passkey) -> Self {
+		Self {
+			id: passkey.id.as_ref().to_string(),
+			name: passkey.name,
+			created_at: passkey.created_at,
+			last_used_at: passkey.last_used_at,
+		}
+	}
+}
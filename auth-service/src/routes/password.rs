@@ -0,0 +1,60 @@
+use std::str::FromStr;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+use crate::domain::{AuditEvent, AuditEventKind, AuthAPIError, Email, Password};
+use crate::utils::audit::record_event;
+use crate::utils::auth::{ensure_active, verify_password, ClientInfo};
+use crate::AppState;
+
+// Change the password with the current one. This doesn't need a login, since accounts an
+// administrator flagged for a password reset can't log in until they've been through here.
+pub async fn change_password(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	Json(request): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let Ok(email) = Email::from_str(&request.email) else {
+		return Err(AuthAPIError::InvalidCredentials);
+	};
+	let Ok(current_password) = Password::from_str(&request.current_password) else {
+		return Err(AuthAPIError::InvalidCredentials);
+	};
+	let Ok(new_password) = Password::from_str(&request.new_password) else {
+		return Err(AuthAPIError::InvalidCredentials);
+	};
+	if new_password == current_password {
+		return Err(AuthAPIError::InvalidCredentials);
+	}
+
+	let mut user = verify_password(&state, &email, current_password, client.ip.clone()).await?;
+	ensure_active(&user)?;
+	user.set_password(new_password);
+	state.user_store
+		.write().await
+		.update_user(user).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	// Whoever knew the old password shouldn't stay logged in
+	state.session_store
+		.write().await
+		.remove_sessions(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	record_event(&state, AuditEvent::new(AuditEventKind::PasswordChanged, email, client.ip)).await;
+
+	Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+	pub email: String,
+	#[serde(rename = "currentPassword")]
+	pub current_password: String,
+	#[serde(rename = "newPassword")]
+	pub new_password: String,
+}
@@ -0,0 +1,84 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuthAPIError, User};
+use crate::utils::auth::AuthenticatedUser;
+use crate::AppState;
+
+pub async fn get_profile(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+
+	let user_store = state.user_store.read().await;
+	let user = user_store.get_user_str(&claims.sub).await.map_err(|_| AuthAPIError::InvalidToken)?;
+
+	Ok(Json(ProfileResponse::from(&user)))
+}
+
+pub async fn update_profile(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+	Json(request): Json<UpdateProfileRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+
+	let mut user_store = state.user_store.write().await;
+	let mut user = user_store.get_user_str(&claims.sub).await.map_err(|_| AuthAPIError::InvalidToken)?;
+
+	let mut profile = user.profile.clone();
+	apply_field(&mut profile.display_name, request.display_name);
+	apply_field(&mut profile.locale, request.locale);
+	apply_field(&mut profile.avatar_url, request.avatar_url);
+	profile.validate().map_err(|_| AuthAPIError::InvalidProfile)?;
+
+	user.profile = profile;
+	user_store.update_user(user.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(Json(ProfileResponse::from(&user)))
+}
+
+// Fields left out of the request are kept, empty strings clear them
+fn apply_field(field: &mut Option<String>, value: Option<String>) {
+	match value {
+		Some(value) if value.is_empty() => *field = None,
+		Some(value) => *field = Some(value),
+		None => {}
+	}
+}
+
+#[derive(Deserialize)]
+pub struct UpdateProfileRequest {
+	#[serde(rename = "displayName")]
+	pub display_name: Option<String>,
+	pub locale: Option<String>,
+	#[serde(rename = "avatarUrl")]
+	pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileResponse {
+	pub email: String,
+	#[serde(rename = "emailVerified")]
+	pub email_verified: bool,
+	#[serde(rename = "displayName")]
+	pub display_name: Option<String>,
+	pub locale: Option<String>,
+	#[serde(rename = "avatarUrl")]
+	pub avatar_url: Option<String>,
+}
+
+impl From<&User> for ProfileResponse {
+	fn from(user: &User) -> Self {
+		Self {
+			email: user.email_str().to_string(),
+			email_verified: user.email_verified,
+			display_name: user.profile.display_name.clone(),
+			locale: user.profile.locale.clone(),
+			avatar_url: user.profile.avatar_url.clone(),
+		}
+	}
+}
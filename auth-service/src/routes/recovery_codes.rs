@@ -0,0 +1,71 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuditEventKind, AuthAPIError, RecoveryCode, User};
+use crate::utils::audit::{record_event, token_event};
+use crate::utils::auth::{ensure_not_impersonated, AuthenticatedUser, Claims, ClientInfo};
+use crate::AppState;
+
+// How many unused recovery codes the logged in user has left
+pub async fn get_recovery_codes(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+	let user = two_fa_user(&state, &claims).await?;
+
+	let remaining = state.recovery_code_store
+		.read().await
+		.remaining_codes(&user.email()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(Json(RecoveryCodesStatusResponse { remaining }))
+}
+
+// Replace the user's recovery codes with a fresh set, invalidating the old ones
+pub async fn regenerate_recovery_codes(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+	ensure_not_impersonated(&claims)?;
+	let user = two_fa_user(&state, &claims).await?;
+
+	let codes = RecoveryCode::generate_set();
+	state.recovery_code_store
+		.write().await
+		.set_codes(user.email(), codes.clone()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	record_event(&state, token_event(AuditEventKind::RecoveryCodesRegenerated, &claims, client.ip)?).await;
+
+	Ok(Json(RecoveryCodesResponse {
+		recovery_codes: codes.iter().map(|code| code.as_ref().to_string()).collect(),
+	}))
+}
+
+async fn two_fa_user(state: &AppState, claims: &Claims) -> Result<User, AuthAPIError> {
+	let user = state.user_store
+		.read().await
+		.get_user_str(&claims.sub).await
+		.map_err(|_| AuthAPIError::InvalidToken)?;
+
+	if !user.requires_2fa {
+		return Err(AuthAPIError::TwoFactorNotEnabled);
+	}
+
+	Ok(user)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesStatusResponse {
+	pub remaining: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+	#[serde(rename = "recoveryCodes")]
+	pub recovery_codes: Vec<String>,
+}
@@ -0,0 +1,71 @@
+use std::str::FromStr;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::domain::{AuthAPIError, Email, LoginAttemptId, TwoFACode};
+use crate::routes::login::TwoFactorAuthResponse;
+use crate::utils::two_fa::send_2fa_code;
+use crate::AppState;
+
+// Sends a fresh code for a pending 2FA login, e.g. when the first email got lost.
+// The login attempt ID stays the same, so the login page can keep using it.
+pub async fn resend_2fa(
+	State(state): State<AppState>,
+	Json(request): Json<Resend2FARequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let Ok(email) = Email::from_str(&request.email) else {
+		return Err(AuthAPIError::InvalidCredentials);
+	};
+	let Ok(login_attempt_id) = LoginAttemptId::parse(request.login_attempt_id) else {
+		return Err(AuthAPIError::InvalidCredentials);
+	};
+
+	let mut two_fa_code_store = state.two_fa_code_store.write().await;
+
+	let (pending_login_attempt_id, _) = two_fa_code_store
+		.get_code(&email).await
+		.map_err(|_| AuthAPIError::Invalid2FACredentials)?;
+	if pending_login_attempt_id != login_attempt_id {
+		return Err(AuthAPIError::Invalid2FACredentials);
+	}
+
+	let delivery = two_fa_code_store
+		.get_code_delivery(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	let policy = &state.resend_2fa_policy;
+	if delivery.resend_count >= policy.max_resends {
+		return Err(AuthAPIError::Resend2FALimitReached);
+	}
+	if Utc::now() - delivery.sent_at < policy.cooldown {
+		return Err(AuthAPIError::Resend2FACooldown);
+	}
+
+	let two_fa_code = TwoFACode::default();
+	two_fa_code_store
+		.replace_code(&email, two_fa_code.clone()).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(two_fa_code_store);
+
+	let user = state.user_store
+		.read().await
+		.get_user(email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	send_2fa_code(&state, &user, &two_fa_code).await?;
+
+	Ok(Json(TwoFactorAuthResponse {
+		message: "2FA code resent".to_string(),
+		login_attempt_id: login_attempt_id.as_ref().to_string(),
+		channel: user.two_fa_channel.name().to_string(),
+	}))
+}
+
+#[derive(Deserialize)]
+pub struct Resend2FARequest {
+	pub email: String,
+	#[serde(rename = "loginAttemptId")]
+	pub login_attempt_id: String,
+}
@@ -0,0 +1,112 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuditEventKind, AuthAPIError, Session, SessionId};
+use crate::utils::audit::{record_event, token_event};
+use crate::utils::auth::{auth_cookie_removal, AuthenticatedUser, ClientInfo, TokenSource};
+use crate::AppState;
+
+// Lists every session of the logged in user, including OAuth client grants
+pub async fn list_sessions(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = auth.email()?;
+	let claims = auth.claims;
+
+	let sessions = state.session_store
+		.read().await
+		.get_sessions(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	let sessions: Vec<SessionResponse> = sessions
+		.into_iter()
+		.map(|session| SessionResponse::new(session, &claims.sid))
+		.collect();
+
+	Ok(Json(sessions))
+}
+
+pub async fn revoke_session(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	jar: CookieJar,
+	Path(id): Path<String>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let email = auth.email()?;
+	let claims = auth.claims;
+	let session_id = SessionId::parse(id).map_err(|_| AuthAPIError::SessionNotFound)?;
+
+	let mut session_store = state.session_store.write().await;
+	let session = session_store.get_session(&session_id).await.map_err(|_| AuthAPIError::SessionNotFound)?;
+	// Other users' sessions are reported as missing rather than forbidden
+	if session.email != email {
+		return Err(AuthAPIError::SessionNotFound);
+	}
+	session_store.remove_session(&session_id).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(session_store);
+
+	let event = token_event(AuditEventKind::SessionRevoked, &claims, client.ip)?.with_details(session_id.as_ref());
+	record_event(&state, event).await;
+
+	// A client using a bearer token may have revoked its own session, which says nothing about the cookie
+	let jar = if auth.source == TokenSource::Cookie && session_id.as_ref() == claims.sid {
+		jar.remove(auth_cookie_removal(&state.config))
+	} else {
+		jar
+	};
+
+	Ok((jar, StatusCode::NO_CONTENT))
+}
+
+// "Log out everywhere": revokes all of the user's sessions, the current one included
+pub async fn revoke_all_sessions(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	jar: CookieJar,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let email = auth.email()?;
+	let claims = auth.claims;
+
+	state.session_store
+		.write().await
+		.remove_sessions(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	record_event(&state, token_event(AuditEventKind::SessionRevoked, &claims, client.ip)?.with_details("all")).await;
+
+	Ok((jar.remove(auth_cookie_removal(&state.config)), StatusCode::NO_CONTENT))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+	pub id: String,
+	#[serde(rename = "createdAt")]
+	pub created_at: DateTime<Utc>,
+	#[serde(rename = "lastSeenAt")]
+	pub last_seen_at: DateTime<Utc>,
+	#[serde(rename = "userAgent")]
+	pub user_agent: Option<String>,
+	pub ip: Option<String>,
+	// Whether this is the session making the request
+	pub current: bool,
+}
+
+impl SessionResponse {
+	fn new(session: Session, current_sid: &str) -> Self {
+		Self {
+			current: session.id.as_ref() == current_sid,
+			id: session.id.as_ref().to_string(),
+			created_at: session.created_at,
+			last_seen_at: session.last_seen_at,
+			user_agent: session.user_agent,
+			ip: session.ip,
+		}
+	}
+}
@@ -4,32 +4,57 @@ use axum::Json;
 use axum::response::IntoResponse;
 use axum::http::StatusCode;
 use axum::extract::State;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::domain::{AuthAPIError, Email, Password, User};
+use crate::domain::{AuditEvent, AuditEventKind, AuthAPIError, Email, Password, RecoveryCode, User};
+use crate::utils::audit::record_event;
+use crate::utils::auth::ClientInfo;
 use crate::AppState;
 
-pub async fn signup(State(state): State<AppState>, Json(request): Json<SignupRequest>) -> Result<impl IntoResponse, AuthAPIError> {
+pub async fn signup(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	Json(request): Json<SignupRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
 	let Ok(user) = request.to_user() else {
 		return Err(AuthAPIError::InvalidCredentials);
 	};
 	let mut user_store = state.user_store.write().await;
 
-	let response = SignupResponse {
-		message: format!("User {} created successfully", user.email_str()),
-	};
-
 	let Ok(user_email) = Email::from_str(&request.email) else {
 		return Err(AuthAPIError::InvalidCredentials);
 	};
 
-	if user_store.get_user(user_email).await.is_ok() {
+	if user_store.get_user(user_email.clone()).await.is_ok() {
 		return Err(AuthAPIError::UserAlreadyExists);
 	}
 
+	let message = format!("User {} created successfully", user.email_str());
+	let requires_2fa = user.requires_2fa;
 	if user_store.add_user(user).await.is_err() {
 		return Err(AuthAPIError::UnexpectedError);
 	}
+	drop(user_store);
+	state.metrics.signups.inc();
+	record_event(&state, AuditEvent::new(AuditEventKind::SignedUp, user_email.clone(), client.ip)).await;
+
+	// 2FA users get recovery codes in case they lose access to their mailbox.
+	// Only their hashes are kept, so this response is the one chance to see them.
+	let recovery_codes = if requires_2fa {
+		let codes = RecoveryCode::generate_set();
+		state.recovery_code_store
+			.write().await
+			.set_codes(user_email, codes.clone()).await
+			.map_err(|_| AuthAPIError::UnexpectedError)?;
+		Some(codes.iter().map(|code| code.as_ref().to_string()).collect())
+	} else {
+		None
+	};
+
+	let response = SignupResponse {
+		message,
+		recovery_codes,
+	};
 
 	Ok((StatusCode::CREATED, Json(response)))
 }
@@ -37,18 +62,32 @@ pub async fn signup(State(state): State<AppState>, Json(request): Json<SignupReq
 #[derive(Deserialize)]
 pub struct SignupRequest {
 	pub email: String,
-	pub password: String,
+	// Has to be present, but can be `null` for a passwordless account that logs in through magic links
+	#[serde(deserialize_with = "deserialize_nullable")]
+	pub password: Option<String>,
 	#[serde(rename = "requires2FA")]
 	pub requires_2fa: bool,
 }
 
 impl SignupRequest{
 	pub fn to_user(&self) -> Result<User, String> {
-		Ok(User::new(Email::from_str(&self.email)?, Password::from_str(&self.password)?, self.requires_2fa))
+		let email = Email::from_str(&self.email)?;
+		match &self.password {
+			Some(password) => Ok(User::new(email, Password::from_str(password)?, self.requires_2fa)),
+			// The mailbox is the only factor of a passwordless account, so there's no second one
+			None if self.requires_2fa => Err("Passwordless accounts can't use 2FA".to_string()),
+			None => Ok(User::new_passwordless(email)),
+		}
 	}
 }
 
+fn deserialize_nullable<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+	Option::<String>::deserialize(deserializer)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SignupResponse {
 	pub message: String,
+	#[serde(rename = "recoveryCodes", default, skip_serializing_if = "Option::is_none")]
+	pub recovery_codes: Option<Vec<String>>,
 }
@@ -0,0 +1,114 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuditEventKind, AuthAPIError, TrustedDevice, TrustedDeviceId};
+use crate::utils::audit::{record_event, token_event};
+use crate::utils::auth::{AuthenticatedUser, ClientInfo};
+use crate::utils::trusted_device::{trusted_device_cookie_removal, trusted_device_id};
+use crate::AppState;
+
+// Lists the browsers that can log in as the user without going through 2FA
+pub async fn list_trusted_devices(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+	jar: CookieJar,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let email = auth.email()?;
+	let current = trusted_device_id(&state, &jar, &email).await;
+
+	let devices = state.trusted_device_store
+		.read().await
+		.get_devices(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	let devices: Vec<TrustedDeviceResponse> = devices
+		.into_iter()
+		.map(|device| TrustedDeviceResponse::new(device, current.as_ref()))
+		.collect();
+
+	Ok(Json(devices))
+}
+
+pub async fn revoke_trusted_device(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	jar: CookieJar,
+	Path(id): Path<String>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let email = auth.email()?;
+	let claims = auth.claims;
+	let device_id = TrustedDeviceId::parse(id).map_err(|_| AuthAPIError::TrustedDeviceNotFound)?;
+	let current = trusted_device_id(&state, &jar, &email).await;
+
+	let mut trusted_device_store = state.trusted_device_store.write().await;
+	let device = trusted_device_store
+		.get_device(&device_id).await
+		.map_err(|_| AuthAPIError::TrustedDeviceNotFound)?;
+	// Other users' devices are reported as missing rather than forbidden
+	if device.email != email {
+		return Err(AuthAPIError::TrustedDeviceNotFound);
+	}
+	trusted_device_store.remove_device(&device_id).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(trusted_device_store);
+
+	let event = token_event(AuditEventKind::TrustedDeviceRevoked, &claims, client.ip)?.with_details(device_id.as_ref());
+	record_event(&state, event).await;
+
+	let jar = if current.as_ref() == Some(&device_id) {
+		jar.add(trusted_device_cookie_removal(&state.config))
+	} else {
+		jar
+	};
+
+	Ok((jar, StatusCode::NO_CONTENT))
+}
+
+// Forget every trusted device, so all future logins go through 2FA again
+pub async fn revoke_all_trusted_devices(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	jar: CookieJar,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let email = auth.email()?;
+	let claims = auth.claims;
+
+	state.trusted_device_store
+		.write().await
+		.remove_devices(&email).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+	record_event(&state, token_event(AuditEventKind::TrustedDeviceRevoked, &claims, client.ip)?.with_details("all")).await;
+
+	Ok((jar.add(trusted_device_cookie_removal(&state.config)), StatusCode::NO_CONTENT))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrustedDeviceResponse {
+	pub id: String,
+	#[serde(rename = "createdAt")]
+	pub created_at: DateTime<Utc>,
+	#[serde(rename = "expiresAt")]
+	pub expires_at: DateTime<Utc>,
+	#[serde(rename = "userAgent")]
+	pub user_agent: Option<String>,
+	// Whether this is the browser making the request
+	pub current: bool,
+}
+
+impl TrustedDeviceResponse {
+	fn new(device: TrustedDevice, current: Option<&TrustedDeviceId>) -> Self {
+		Self {
+			current: current == Some(&device.id),
+			id: device.id.as_ref().to_string(),
+			created_at: device.created_at,
+			expires_at: device.expires_at,
+			user_agent: device.user_agent,
+		}
+	}
+}
@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuditEventKind, AuthAPIError, PhoneNumber, TwoFAChannel, WebhookUrl};
+use crate::utils::audit::{record_event, token_event};
+use crate::utils::auth::{ensure_not_impersonated, AuthenticatedUser, ClientInfo};
+use crate::AppState;
+
+pub async fn get_two_fa_channel(
+	State(state): State<AppState>,
+	auth: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+
+	let user = state.user_store
+		.read().await
+		.get_user_str(&claims.sub).await
+		.map_err(|_| AuthAPIError::InvalidToken)?;
+
+	Ok(Json(TwoFAChannelBody::from(&user.two_fa_channel)))
+}
+
+// Pick where future 2FA codes get delivered
+pub async fn update_two_fa_channel(
+	State(state): State<AppState>,
+	client: ClientInfo,
+	auth: AuthenticatedUser,
+	Json(request): Json<TwoFAChannelBody>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+	let claims = auth.claims;
+	ensure_not_impersonated(&claims)?;
+	let channel = request.to_channel().map_err(|_| AuthAPIError::InvalidTwoFAChannel)?;
+	if let TwoFAChannel::Webhook(url) = &channel {
+		state.webhook_client.check_url(url).map_err(|_| AuthAPIError::InvalidTwoFAChannel)?;
+	}
+
+	let mut user_store = state.user_store.write().await;
+	let mut user = user_store.get_user_str(&claims.sub).await.map_err(|_| AuthAPIError::InvalidToken)?;
+	user.two_fa_channel = channel;
+	user_store.update_user(user.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(user_store);
+
+	let event = token_event(AuditEventKind::TwoFactorChannelChanged, &claims, client.ip)?
+		.with_details(user.two_fa_channel.name());
+	record_event(&state, event).await;
+
+	Ok(Json(TwoFAChannelBody::from(&user.two_fa_channel)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFAChannelBody {
+	// "email", "sms" or "webhook"
+	pub channel: String,
+	#[serde(rename = "phoneNumber", default, skip_serializing_if = "Option::is_none")]
+	pub phone_number: Option<String>,
+	#[serde(rename = "webhookUrl", default, skip_serializing_if = "Option::is_none")]
+	pub webhook_url: Option<String>,
+}
+
+impl TwoFAChannelBody {
+	fn to_channel(&self) -> Result<TwoFAChannel, String> {
+		match self.channel.as_str() {
+			"email" => Ok(TwoFAChannel::Email),
+			"sms" => {
+				let phone_number = self.phone_number.as_deref().ok_or("phoneNumber is required")?;
+				Ok(TwoFAChannel::Sms(PhoneNumber::from_str(phone_number)?))
+			}
+			"webhook" => {
+				let webhook_url = self.webhook_url.as_deref().ok_or("webhookUrl is required")?;
+				Ok(TwoFAChannel::Webhook(WebhookUrl::from_str(webhook_url)?))
+			}
+			_ => Err("Unknown channel".to_string()),
+		}
+	}
+}
+
+impl From<&TwoFAChannel> for TwoFAChannelBody {
+	fn from(channel: &TwoFAChannel) -> Self {
+		let (phone_number, webhook_url) = match channel {
+			TwoFAChannel::Email => (None, None),
+			TwoFAChannel::Sms(phone_number) => (Some(phone_number.as_ref().to_string()), None),
+			TwoFAChannel::Webhook(url) => (None, Some(url.as_ref().to_string())),
+		};
+
+		Self {
+			channel: channel.name().to_string(),
+			phone_number,
+			webhook_url,
+		}
+	}
+}
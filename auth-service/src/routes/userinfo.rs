@@ -0,0 +1,65 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuthAPIError, User};
+use crate::utils::auth::{AccessToken, Claims};
+use crate::AppState;
+
+// OIDC userinfo endpoint, returns the claims the access token's scope allows
+pub async fn userinfo(
+	State(state): State<AppState>,
+	AccessToken { claims }: AccessToken,
+) -> Result<impl IntoResponse, AuthAPIError> {
+
+	if !claims.has_scope("openid") {
+		return Err(AuthAPIError::InsufficientScope);
+	}
+
+	let user_store = state.user_store.read().await;
+	let user = user_store.get_user_str(&claims.sub).await.map_err(|_| AuthAPIError::InvalidToken)?;
+
+	Ok(Json(UserInfoResponse::new(&user, &claims)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+	pub sub: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub email: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub email_verified: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub locale: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub picture: Option<String>,
+}
+
+impl UserInfoResponse {
+	pub fn new(user: &User, claims: &Claims) -> Self {
+		let mut response = Self {
+			sub: user.email_str().to_string(),
+			email: None,
+			email_verified: None,
+			name: None,
+			locale: None,
+			picture: None,
+		};
+
+		if claims.has_scope("email") {
+			response.email = Some(user.email_str().to_string());
+			response.email_verified = Some(user.email_verified);
+		}
+
+		if claims.has_scope("profile") {
+			response.name = user.profile.display_name.clone();
+			response.locale = user.profile.locale.clone();
+			response.picture = user.profile.avatar_url.clone();
+		}
+
+		response
+	}
+}
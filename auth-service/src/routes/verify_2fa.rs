@@ -7,54 +7,186 @@ use axum::Json;
 use axum_extra::extract::CookieJar;
 use serde::Deserialize;
 
-use crate::domain::{AuthAPIError, LoginAttemptId, TwoFACode};
+use crate::domain::{AuditEvent, AuditEventKind, AuthAPIError, LoginAttemptId, RecoveryCode, TwoFACode};
+use crate::utils::audit::{ensure_active_login, record_event, record_login};
+use crate::utils::auth::{start_session, verify_email, ClientInfo};
+use crate::utils::trusted_device::remember_device;
+use crate::utils::webauthn::{authenticate_passkey, PasskeyAssertion};
+use crate::domain::TwoFAChannel;
 use crate::{AppState, Email};
 
 pub async fn verify_2fa(
 	State(state): State<AppState>,
+	client: ClientInfo,
 	jar: CookieJar,
 	Json(request): Json<Verify2FARequest>
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+	let result = attempt_verify_2fa(&state, client, jar, request).await;
+
+	let outcome = if result.is_ok() { "success" } else { "failure" };
+	state.metrics.two_fa_verifications.with_label_values(&[outcome]).inc();
+
+	result
+}
+
+async fn attempt_verify_2fa(
+	state: &AppState,
+	client: ClientInfo,
+	jar: CookieJar,
+	request: Verify2FARequest,
+) -> Result<(CookieJar, StatusCode), AuthAPIError> {
 	let Ok(user_email) = Email::from_str(&request.email) else {
 		return Err(AuthAPIError::InvalidCredentials);
 	};
 	let Ok(login_attempt_id) = LoginAttemptId::parse(request.login_attempt_id) else {
 		return Err(AuthAPIError::InvalidCredentials);
 	};
-	let Ok(two_fa_code) = TwoFACode::parse(request.two_fa_code) else {
-		return Err(AuthAPIError::InvalidCredentials);
+	// The code field takes either the emailed code or one of the user's recovery codes
+	let second_factor = match request.second_factor {
+		SecondFactorInput::Code { two_fa_code } => {
+			if let Ok(code) = TwoFACode::parse(two_fa_code.clone()) {
+				SecondFactor::TwoFACode(code)
+			} else if let Ok(code) = RecoveryCode::parse(&two_fa_code) {
+				SecondFactor::RecoveryCode(code)
+			} else {
+				return Err(AuthAPIError::InvalidCredentials);
+			}
+		}
+		SecondFactorInput::Passkey { passkey } => SecondFactor::Passkey(passkey),
 	};
 
 	let mut two_fa_code_store = state.two_fa_code_store.write().await;
 
-	let code_tuple = two_fa_code_store
-		.get_code(&user_email).await
-		.map_err(|_| AuthAPIError::Invalid2FACredentials)?;
+	let Ok(code_tuple) = two_fa_code_store.get_code(&user_email).await else {
+		return Err(second_factor_failed(state, &user_email, &client, "no pending login", AuthAPIError::Invalid2FACredentials).await);
+	};
 
 	if code_tuple.0 != login_attempt_id {
-		return Err(AuthAPIError::Invalid2FACredentials);
+		return Err(second_factor_failed(state, &user_email, &client, "wrong login attempt", AuthAPIError::Invalid2FACredentials).await);
 	}
-	if code_tuple.1 != two_fa_code {
-		return Err(AuthAPIError::Invalid2FACredentials);
+	match &second_factor {
+		SecondFactor::TwoFACode(code) => {
+			if code_tuple.1 != *code {
+				return Err(second_factor_failed(state, &user_email, &client, "wrong code", AuthAPIError::Invalid2FACredentials).await);
+			}
+		}
+		SecondFactor::RecoveryCode(code) => {
+			if let Err(error) = use_recovery_code(state, &user_email, code).await {
+				return Err(second_factor_failed(state, &user_email, &client, "recovery code not accepted", error).await);
+			}
+			record_event(state, AuditEvent::new(AuditEventKind::RecoveryCodeUsed, user_email.clone(), client.ip.clone())).await;
+		}
+		// User presence is enough here, the password was the first factor
+		SecondFactor::Passkey(assertion) => {
+			if let Err(error) = authenticate_passkey(state, assertion, Some(&user_email), false).await {
+				return Err(second_factor_failed(state, &user_email, &client, "passkey not accepted", error).await);
+			}
+		}
 	}
 
 	two_fa_code_store
 		.remove_code(&user_email).await
 		.map_err(|_| AuthAPIError::UnexpectedError)?;
 
-	let auth_cookie = crate::utils::auth::generate_auth_cookie(&user_email)
+	let mut user_store = state.user_store.write().await;
+	let mut user = user_store.get_user(user_email.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	// The account could have been deactivated while the code was on its way
+	ensure_active_login(state, &user, client.ip.clone()).await?;
+	// Only a code that was emailed proves the user owns the mailbox, recovery codes are handed out at signup
+	let emailed_code = matches!(second_factor, SecondFactor::TwoFACode(_)) && user.two_fa_channel == TwoFAChannel::Email;
+	if emailed_code && !user.email_verified {
+		verify_email(&state.config, &mut user);
+		user_store.update_user(user.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	}
+	let permissions = user.permissions();
+	drop(user_store);
+	drop(two_fa_code_store);
+
+	let session_id = start_session(state, &user_email, client.clone()).await?;
+
+	let auth_cookie = crate::utils::auth::generate_auth_cookie(&state.config, &user_email, &session_id, &permissions)
 		.map_err(|_| AuthAPIError::TokenCreationError)?;
 
-	let updated_jar = jar.add(auth_cookie);
+	let mut updated_jar = jar.add(auth_cookie);
+	record_login(state, &user_email, client.ip.clone(), second_factor.login_method()).await;
+	if request.remember_device {
+		let device_cookie = remember_device(state, &user_email, client).await?;
+		updated_jar = updated_jar.add(device_cookie);
+	}
 
 	Ok((updated_jar, StatusCode::OK))
 }
 
+enum SecondFactor {
+	TwoFACode(TwoFACode),
+	RecoveryCode(RecoveryCode),
+	Passkey(PasskeyAssertion),
+}
+
+impl SecondFactor {
+	fn login_method(&self) -> &'static str {
+		match self {
+			SecondFactor::TwoFACode(_) => "password and 2FA code",
+			SecondFactor::RecoveryCode(_) => "password and recovery code",
+			SecondFactor::Passkey(_) => "password and passkey",
+		}
+	}
+}
+
+// Audit a rejected second factor, handing back the error to answer with
+async fn second_factor_failed(
+	state: &AppState,
+	email: &Email,
+	client: &ClientInfo,
+	details: &str,
+	error: AuthAPIError,
+) -> AuthAPIError {
+	let event = AuditEvent::new(AuditEventKind::TwoFactorFailed, email.clone(), client.ip.clone()).with_details(details);
+	record_event(state, event).await;
+	error
+}
+
+// Consume the recovery code and warn the user, in case it wasn't them who used it
+async fn use_recovery_code(state: &AppState, email: &Email, code: &RecoveryCode) -> Result<(), AuthAPIError> {
+	let remaining = state.recovery_code_store
+		.write().await
+		.use_code(email, code).await
+		.map_err(|_| AuthAPIError::Invalid2FACredentials)?;
+
+	state.email_client
+		.write().await
+		.send_email(
+			email,
+			"Let's Get Rusty Bootcamp: Recovery code used",
+			format!(
+				"A recovery code was just used to log in to your account. You have {remaining} recovery codes left. \
+				If this wasn't you, change your password and regenerate your recovery codes."
+			).as_str()
+		).await
+		.map_err(|_| AuthAPIError::UnexpectedError)
+}
+
 #[derive(Deserialize)]
 pub struct Verify2FARequest {
 	pub email: String,
 	#[serde(rename = "loginAttemptId")]
 	pub login_attempt_id: String,
-	#[serde(rename = "2FACode")]
-	pub two_fa_code: String,
+	#[serde(flatten)]
+	pub second_factor: SecondFactorInput,
+	// Opt in to skipping 2FA on this browser for future logins
+	#[serde(rename = "rememberDevice", default)]
+	pub remember_device: bool,
+}
+
+// The second factor is either a code or a passkey assertion answering a `/passkeys/login/start` challenge
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum SecondFactorInput {
+	Code {
+		#[serde(rename = "2FACode")]
+		two_fa_code: String,
+	},
+	Passkey {
+		passkey: PasskeyAssertion,
+	},
 }
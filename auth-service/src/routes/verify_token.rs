@@ -1,25 +1,75 @@
-use axum::extract::State;
-use axum::response::IntoResponse;
+use axum::extract::{FromRequest, FromRequestParts, Request, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::domain::AuthAPIError;
-use crate::utils::auth::validate_token;
+use crate::domain::{AccountStatus, AuthAPIError, Role};
+use crate::utils::auth::{ensure_active, validate_token, AuthenticatedUser, Claims};
 use crate::AppState;
 
 pub async fn verify_token(
 	State(state): State<AppState>,
-	Json(request): Json<VerifyTokenRequest>,
+	TokenToVerify(claim): TokenToVerify,
 ) -> Result<impl IntoResponse, AuthAPIError> {
-	let claim = validate_token(&request.token, &state.banned_token_store).await.map_err(|_| AuthAPIError::InvalidToken)?;
-
 	let user_store = state.user_store.read().await;
-	let user_email = user_store.get_user_str(&claim.sub).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	let user = user_store.get_user_str(&claim.sub).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	// Tokens issued before the account was deactivated stop working right away
+	ensure_active(&user)?;
 
-	Ok(Json(user_email))
+	Ok(Json(VerifyTokenResponse {
+		email: user.email_str().to_string(),
+		roles: user.roles.clone(),
+		status: user.status,
+		requires_2fa: user.requires_2fa,
+		two_fa_channel: user.two_fa_channel.name().to_string(),
+		impersonated: claim.act.is_some(),
+		impersonated_by: claim.act.map(|actor| actor.sub),
+	}))
 }
 
 #[derive(Deserialize)]
 pub struct VerifyTokenRequest {
 	pub token: String,
 }
+
+// Claims of the token in the JSON body, as services checking their users' tokens send them. Without a body
+// it's the request's own token, found like for any authenticated route: bearer token first, then the cookie.
+pub struct TokenToVerify(Claims);
+
+#[axum::async_trait]
+impl FromRequest<AppState> for TokenToVerify {
+	type Rejection = Response;
+
+	async fn from_request(request: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+		let (mut parts, body) = request.into_parts();
+		// Any body has to be JSON, so a request with no content type has none
+		if !parts.headers.contains_key(header::CONTENT_TYPE) {
+			let auth = AuthenticatedUser::from_request_parts(&mut parts, state).await.map_err(IntoResponse::into_response)?;
+			return Ok(Self(auth.claims));
+		}
+
+		let Json(request) = Json::<VerifyTokenRequest>::from_request(Request::from_parts(parts, body), state)
+			.await
+			.map_err(IntoResponse::into_response)?;
+		let claims = validate_token(&request.token, state).await.map_err(|_| AuthAPIError::InvalidToken.into_response())?;
+
+		Ok(Self(claims))
+	}
+}
+
+// The token's user, and whether an administrator is acting as them. Only what services acting on the token
+// need, the account's secrets and lockout state stay in the store.
+#[derive(Serialize)]
+pub struct VerifyTokenResponse {
+	pub email: String,
+	pub roles: Vec<Role>,
+	pub status: AccountStatus,
+	#[serde(rename = "requires2FA")]
+	pub requires_2fa: bool,
+	#[serde(rename = "twoFAChannel")]
+	pub two_fa_channel: String,
+	pub impersonated: bool,
+	#[serde(rename = "impersonatedBy", skip_serializing_if = "Option::is_none")]
+	pub impersonated_by: Option<String>,
+}
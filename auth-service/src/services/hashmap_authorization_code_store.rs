@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::domain::{AuthorizationCode, AuthorizationCodeStore, AuthorizationCodeStoreError, AuthorizationGrant};
+
+#[derive(Default)]
+pub struct HashmapAuthorizationCodeStore {
+	codes: HashMap<AuthorizationCode, AuthorizationGrant>,
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for HashmapAuthorizationCodeStore {
+	async fn add_code(
+		&mut self,
+		code: AuthorizationCode,
+		grant: AuthorizationGrant,
+	) -> Result<(), AuthorizationCodeStoreError> {
+		if self.codes.contains_key(&code) {
+			return Err(AuthorizationCodeStoreError::CodeAlreadyExists);
+		}
+
+		self.codes.insert(code, grant);
+		Ok(())
+	}
+
+	async fn take_code(
+		&mut self,
+		code: &AuthorizationCode,
+	) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+		self.codes.remove(code).ok_or(AuthorizationCodeStoreError::CodeNotFound)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use chrono::Utc;
+
+	use super::*;
+	use crate::domain::Email;
+
+	#[tokio::test]
+	async fn should_only_redeem_code_once() {
+		let mut store = HashmapAuthorizationCodeStore::default();
+		let code = AuthorizationCode::default();
+		let grant = AuthorizationGrant {
+			email: Email::from_str("test@example.com").unwrap(),
+			client_id: "client".to_string(),
+			redirect_uri: "http://localhost/callback".to_string(),
+			scope: "openid".to_string(),
+			nonce: None,
+			code_challenge: "challenge".to_string(),
+			expires_at: Utc::now(),
+		};
+
+		store.add_code(code.clone(), grant.clone()).await.unwrap();
+		assert_eq!(store.take_code(&code).await.unwrap(), grant);
+		assert_eq!(store.take_code(&code).await, Err(AuthorizationCodeStoreError::CodeNotFound));
+	}
+}
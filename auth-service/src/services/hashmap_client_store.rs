@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use crate::domain::{ClientStore, ClientStoreError, OAuthClient};
+
+#[derive(Default)]
+pub struct HashmapClientStore {
+	clients: HashMap<String, OAuthClient>,
+}
+
+#[async_trait::async_trait]
+impl ClientStore for HashmapClientStore {
+	async fn add_client(&mut self, client: OAuthClient) -> Result<(), ClientStoreError> {
+		if self.clients.contains_key(&client.client_id) {
+			return Err(ClientStoreError::ClientAlreadyExists);
+		}
+
+		self.clients.insert(client.client_id.clone(), client);
+		Ok(())
+	}
+
+	async fn get_client(&self, client_id: &str) -> Result<OAuthClient, ClientStoreError> {
+		self.clients.get(client_id).cloned().ok_or(ClientStoreError::ClientNotFound)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_add_and_get_client() {
+		let mut store = HashmapClientStore::default();
+		let client = OAuthClient {
+			client_id: "client".to_string(),
+			secret_hash: None,
+			redirect_uris: vec!["http://localhost/callback".to_string()],
+			allowed_scopes: vec!["openid".to_string()],
+		};
+
+		store.add_client(client.clone()).await.unwrap();
+		assert_eq!(store.get_client("client").await.unwrap(), client);
+		assert_eq!(store.add_client(client).await, Err(ClientStoreError::ClientAlreadyExists));
+		assert_eq!(store.get_client("other").await, Err(ClientStoreError::ClientNotFound));
+	}
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::domain::{DeviceAuthorization, DeviceCode, DeviceCodeStore, DeviceCodeStoreError, UserCode};
+
+#[derive(Default)]
+pub struct HashmapDeviceCodeStore {
+	authorizations: HashMap<DeviceCode, DeviceAuthorization>,
+}
+
+#[async_trait::async_trait]
+impl DeviceCodeStore for HashmapDeviceCodeStore {
+	async fn add_authorization(
+		&mut self,
+		device_code: DeviceCode,
+		authorization: DeviceAuthorization,
+	) -> Result<(), DeviceCodeStoreError> {
+		let user_code_taken = self.authorizations.values().any(|a| a.user_code == authorization.user_code);
+		if self.authorizations.contains_key(&device_code) || user_code_taken {
+			return Err(DeviceCodeStoreError::CodeAlreadyExists);
+		}
+
+		self.authorizations.insert(device_code, authorization);
+		Ok(())
+	}
+
+	async fn get_authorization(&self, device_code: &DeviceCode) -> Result<DeviceAuthorization, DeviceCodeStoreError> {
+		self.authorizations.get(device_code).cloned().ok_or(DeviceCodeStoreError::CodeNotFound)
+	}
+
+	async fn get_authorization_by_user_code(
+		&self,
+		user_code: &UserCode,
+	) -> Result<(DeviceCode, DeviceAuthorization), DeviceCodeStoreError> {
+		self.authorizations
+			.iter()
+			.find(|(_, authorization)| &authorization.user_code == user_code)
+			.map(|(device_code, authorization)| (device_code.clone(), authorization.clone()))
+			.ok_or(DeviceCodeStoreError::CodeNotFound)
+	}
+
+	async fn update_authorization(
+		&mut self,
+		device_code: &DeviceCode,
+		authorization: DeviceAuthorization,
+	) -> Result<(), DeviceCodeStoreError> {
+		match self.authorizations.get_mut(device_code) {
+			Some(existing) => {
+				*existing = authorization;
+				Ok(())
+			}
+			None => Err(DeviceCodeStoreError::CodeNotFound),
+		}
+	}
+
+	async fn remove_authorization(&mut self, device_code: &DeviceCode) -> Result<(), DeviceCodeStoreError> {
+		self.authorizations.remove(device_code).map(|_| ()).ok_or(DeviceCodeStoreError::CodeNotFound)
+	}
+
+	async fn remove_expired(&mut self) -> Result<usize, DeviceCodeStoreError> {
+		let before = self.authorizations.len();
+		self.authorizations.retain(|_, authorization| !authorization.is_expired());
+		Ok(before - self.authorizations.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::{DateTime, Duration, Utc};
+
+	use super::*;
+	use crate::domain::DeviceAuthorizationStatus;
+
+	fn authorization(expires_at: DateTime<Utc>) -> DeviceAuthorization {
+		DeviceAuthorization {
+			client_id: "client".to_string(),
+			scope: "openid".to_string(),
+			user_code: UserCode::default(),
+			status: DeviceAuthorizationStatus::Pending,
+			expires_at,
+			interval: 5,
+			last_polled_at: None,
+		}
+	}
+
+	#[tokio::test]
+	async fn should_find_authorization_by_user_code() {
+		let mut store = HashmapDeviceCodeStore::default();
+		let device_code = DeviceCode::default();
+		let authorization = authorization(Utc::now());
+
+		store.add_authorization(device_code.clone(), authorization.clone()).await.unwrap();
+		let (found_code, found) = store.get_authorization_by_user_code(&authorization.user_code).await.unwrap();
+		assert_eq!(found_code, device_code);
+		assert_eq!(found, authorization);
+
+		store.remove_authorization(&device_code).await.unwrap();
+		assert_eq!(store.get_authorization(&device_code).await, Err(DeviceCodeStoreError::CodeNotFound));
+	}
+
+	#[tokio::test]
+	async fn should_remove_expired_authorizations() {
+		let mut store = HashmapDeviceCodeStore::default();
+		let live = DeviceCode::default();
+		store.add_authorization(DeviceCode::default(), authorization(Utc::now() - Duration::seconds(1))).await.unwrap();
+		store.add_authorization(live.clone(), authorization(Utc::now() + Duration::minutes(10))).await.unwrap();
+
+		assert_eq!(store.remove_expired().await, Ok(1));
+		assert!(store.get_authorization(&live).await.is_ok());
+	}
+}
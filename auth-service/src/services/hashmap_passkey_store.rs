@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::domain::{Email, Passkey, PasskeyChallenge, PasskeyId, PasskeyStore, PasskeyStoreError};
+
+#[derive(Default)]
+pub struct HashmapPasskeyStore {
+	passkeys: HashMap<PasskeyId, Passkey>,
+	challenges: HashMap<String, PasskeyChallenge>,
+}
+
+#[async_trait::async_trait]
+impl PasskeyStore for HashmapPasskeyStore {
+	async fn add_passkey(&mut self, passkey: Passkey) -> Result<(), PasskeyStoreError> {
+		if self.passkeys.contains_key(&passkey.id) {
+			return Err(PasskeyStoreError::PasskeyAlreadyExists);
+		}
+
+		self.passkeys.insert(passkey.id.clone(), passkey);
+		Ok(())
+	}
+
+	async fn get_passkey(&self, id: &PasskeyId) -> Result<Passkey, PasskeyStoreError> {
+		self.passkeys.get(id).cloned().ok_or(PasskeyStoreError::PasskeyNotFound)
+	}
+
+	async fn get_passkeys(&self, email: &Email) -> Result<Vec<Passkey>, PasskeyStoreError> {
+		let mut passkeys: Vec<Passkey> = self.passkeys
+			.values()
+			.filter(|passkey| &passkey.email == email)
+			.cloned()
+			.collect();
+		passkeys.sort_by_key(|passkey| passkey.created_at);
+		Ok(passkeys)
+	}
+
+	async fn update_sign_count(&mut self, id: &PasskeyId, sign_count: u32) -> Result<(), PasskeyStoreError> {
+		let passkey = self.passkeys.get_mut(id).ok_or(PasskeyStoreError::PasskeyNotFound)?;
+		passkey.sign_count = sign_count;
+		passkey.last_used_at = Some(Utc::now());
+		Ok(())
+	}
+
+	async fn remove_passkey(&mut self, id: &PasskeyId) -> Result<(), PasskeyStoreError> {
+		self.passkeys.remove(id).map(|_| ()).ok_or(PasskeyStoreError::PasskeyNotFound)
+	}
+
+	async fn add_challenge(&mut self, challenge: PasskeyChallenge) -> Result<(), PasskeyStoreError> {
+		self.challenges.insert(challenge.value.clone(), challenge);
+		Ok(())
+	}
+
+	async fn take_challenge(&mut self, value: &str) -> Result<PasskeyChallenge, PasskeyStoreError> {
+		self.challenges
+			.remove(value)
+			.filter(|challenge| !challenge.is_expired())
+			.ok_or(PasskeyStoreError::ChallengeNotFound)
+	}
+
+	async fn remove_expired_challenges(&mut self) -> Result<usize, PasskeyStoreError> {
+		let before = self.challenges.len();
+		self.challenges.retain(|_, challenge| !challenge.is_expired());
+		Ok(before - self.challenges.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+	use crate::domain::PasskeyCeremony;
+
+	#[tokio::test]
+	async fn should_only_hand_out_challenge_once() {
+		let mut store = HashmapPasskeyStore::default();
+		let email = Email::from_str("test@example.com").unwrap();
+
+		let challenge = PasskeyChallenge::new(PasskeyCeremony::Registration(email));
+		store.add_challenge(challenge.clone()).await.unwrap();
+
+		assert_eq!(store.take_challenge(&challenge.value).await, Ok(challenge.clone()));
+		assert_eq!(store.take_challenge(&challenge.value).await, Err(PasskeyStoreError::ChallengeNotFound));
+	}
+
+	#[tokio::test]
+	async fn should_remove_expired_challenges() {
+		let mut store = HashmapPasskeyStore::default();
+		let mut expired = PasskeyChallenge::new(PasskeyCeremony::Authentication(None));
+		expired.expires_at = Utc::now() - chrono::Duration::seconds(1);
+		store.add_challenge(expired).await.unwrap();
+		let live = PasskeyChallenge::new(PasskeyCeremony::Authentication(None));
+		store.add_challenge(live.clone()).await.unwrap();
+
+		assert_eq!(store.remove_expired_challenges().await, Ok(1));
+		assert_eq!(store.take_challenge(&live.value).await, Ok(live));
+	}
+}
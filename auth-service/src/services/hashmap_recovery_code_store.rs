@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use crate::domain::{Email, RecoveryCode, RecoveryCodeStore, RecoveryCodeStoreError};
+use crate::utils::crypto::verify_secret;
+
+#[derive(Default)]
+pub struct HashmapRecoveryCodeStore {
+	// Hashes of the unused codes of each user
+	codes: HashMap<Email, Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl RecoveryCodeStore for HashmapRecoveryCodeStore {
+	async fn set_codes(&mut self, email: Email, codes: Vec<RecoveryCode>) -> Result<(), RecoveryCodeStoreError> {
+		self.codes.insert(email, codes.iter().map(RecoveryCode::hash).collect());
+		Ok(())
+	}
+
+	async fn use_code(&mut self, email: &Email, code: &RecoveryCode) -> Result<usize, RecoveryCodeStoreError> {
+		let hashes = self.codes.get_mut(email).ok_or(RecoveryCodeStoreError::CodeNotFound)?;
+		let position = hashes
+			.iter()
+			.position(|hash| verify_secret(code.as_ref(), hash))
+			.ok_or(RecoveryCodeStoreError::CodeNotFound)?;
+		hashes.remove(position);
+		Ok(hashes.len())
+	}
+
+	async fn remaining_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError> {
+		Ok(self.codes.get(email).map_or(0, Vec::len))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn should_consume_each_code_once() {
+		let mut store = HashmapRecoveryCodeStore::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let codes = RecoveryCode::generate_set();
+
+		store.set_codes(email.clone(), codes.clone()).await.unwrap();
+		assert_eq!(store.use_code(&email, &codes[0]).await, Ok(codes.len() - 1));
+		assert_eq!(store.use_code(&email, &codes[0]).await, Err(RecoveryCodeStoreError::CodeNotFound));
+
+		// Regenerating invalidates the old codes
+		store.set_codes(email.clone(), RecoveryCode::generate_set()).await.unwrap();
+		assert_eq!(store.use_code(&email, &codes[1]).await, Err(RecoveryCodeStoreError::CodeNotFound));
+		assert_eq!(store.remaining_codes(&email).await, Ok(codes.len()));
+	}
+}
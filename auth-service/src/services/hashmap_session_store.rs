@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::domain::{Email, Session, SessionId, SessionStore, SessionStoreError};
+
+#[derive(Default)]
+pub struct HashmapSessionStore {
+	sessions: HashMap<SessionId, Session>,
+}
+
+#[async_trait::async_trait]
+impl SessionStore for HashmapSessionStore {
+	async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+		if self.sessions.contains_key(&session.id) {
+			return Err(SessionStoreError::SessionAlreadyExists);
+		}
+
+		self.sessions.insert(session.id.clone(), session);
+		Ok(())
+	}
+
+	async fn get_session(&self, id: &SessionId) -> Result<Session, SessionStoreError> {
+		self.sessions.get(id).cloned().ok_or(SessionStoreError::SessionNotFound)
+	}
+
+	async fn get_sessions(&self, email: &Email) -> Result<Vec<Session>, SessionStoreError> {
+		let mut sessions: Vec<Session> = self.sessions
+			.values()
+			.filter(|session| &session.email == email)
+			.cloned()
+			.collect();
+		sessions.sort_by_key(|session| session.created_at);
+		Ok(sessions)
+	}
+
+	async fn touch_session(&mut self, id: &SessionId) -> Result<(), SessionStoreError> {
+		let session = self.sessions.get_mut(id).ok_or(SessionStoreError::SessionNotFound)?;
+		session.last_seen_at = Utc::now();
+		Ok(())
+	}
+
+	async fn remove_session(&mut self, id: &SessionId) -> Result<(), SessionStoreError> {
+		self.sessions.remove(id).map(|_| ()).ok_or(SessionStoreError::SessionNotFound)
+	}
+
+	async fn remove_sessions(&mut self, email: &Email) -> Result<(), SessionStoreError> {
+		self.sessions.retain(|_, session| &session.email != email);
+		Ok(())
+	}
+
+	async fn remove_expired(&mut self, idle_ttl_seconds: i64) -> Result<usize, SessionStoreError> {
+		let before = self.sessions.len();
+		self.sessions.retain(|_, session| !session.is_expired(idle_ttl_seconds));
+		Ok(before - self.sessions.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use chrono::Duration;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn should_remove_only_the_users_sessions() {
+		let mut store = HashmapSessionStore::default();
+		let alice = Email::from_str("alice@example.com").unwrap();
+		let bob = Email::from_str("bob@example.com").unwrap();
+
+		store.add_session(Session::new(alice.clone(), None, None)).await.unwrap();
+		store.add_session(Session::new(alice.clone(), Some("curl".to_string()), None)).await.unwrap();
+		let bob_session = Session::new(bob.clone(), None, None);
+		store.add_session(bob_session.clone()).await.unwrap();
+		assert_eq!(store.get_sessions(&alice).await.unwrap().len(), 2);
+
+		store.remove_sessions(&alice).await.unwrap();
+		assert!(store.get_sessions(&alice).await.unwrap().is_empty());
+		assert_eq!(store.get_session(&bob_session.id).await.unwrap(), bob_session);
+	}
+
+	#[tokio::test]
+	async fn should_remove_idle_sessions() {
+		let mut store = HashmapSessionStore::default();
+		let email = Email::from_str("alice@example.com").unwrap();
+		let active = Session::new(email.clone(), None, None);
+		store.add_session(active.clone()).await.unwrap();
+		let mut idle = Session::new(email.clone(), None, None);
+		idle.last_seen_at -= Duration::hours(2);
+		store.add_session(idle).await.unwrap();
+
+		assert_eq!(store.remove_expired(3600).await, Ok(1));
+		assert_eq!(store.get_sessions(&email).await.unwrap(), vec![active]);
+	}
+}
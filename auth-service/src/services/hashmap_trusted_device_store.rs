@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use crate::domain::{Email, TrustedDevice, TrustedDeviceId, TrustedDeviceStore, TrustedDeviceStoreError};
+
+#[derive(Default)]
+pub struct HashmapTrustedDeviceStore {
+	devices: HashMap<TrustedDeviceId, TrustedDevice>,
+}
+
+#[async_trait::async_trait]
+impl TrustedDeviceStore for HashmapTrustedDeviceStore {
+	async fn add_device(&mut self, device: TrustedDevice) -> Result<(), TrustedDeviceStoreError> {
+		if self.devices.contains_key(&device.id) {
+			return Err(TrustedDeviceStoreError::DeviceAlreadyExists);
+		}
+
+		self.devices.insert(device.id.clone(), device);
+		Ok(())
+	}
+
+	async fn get_device(&self, id: &TrustedDeviceId) -> Result<TrustedDevice, TrustedDeviceStoreError> {
+		self.devices.get(id).cloned().ok_or(TrustedDeviceStoreError::DeviceNotFound)
+	}
+
+	async fn get_devices(&self, email: &Email) -> Result<Vec<TrustedDevice>, TrustedDeviceStoreError> {
+		let mut devices: Vec<TrustedDevice> = self.devices
+			.values()
+			.filter(|device| &device.email == email && !device.is_expired())
+			.cloned()
+			.collect();
+		devices.sort_by_key(|device| device.created_at);
+		Ok(devices)
+	}
+
+	async fn remove_device(&mut self, id: &TrustedDeviceId) -> Result<(), TrustedDeviceStoreError> {
+		self.devices.remove(id).map(|_| ()).ok_or(TrustedDeviceStoreError::DeviceNotFound)
+	}
+
+	async fn remove_devices(&mut self, email: &Email) -> Result<(), TrustedDeviceStoreError> {
+		self.devices.retain(|_, device| &device.email != email);
+		Ok(())
+	}
+
+	async fn remove_expired(&mut self) -> Result<usize, TrustedDeviceStoreError> {
+		let before = self.devices.len();
+		self.devices.retain(|_, device| !device.is_expired());
+		Ok(before - self.devices.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn should_not_list_expired_devices() {
+		let mut store = HashmapTrustedDeviceStore::default();
+		let email = Email::from_str("test@example.com").unwrap();
+
+		let device = TrustedDevice::new(email.clone(), None, 30);
+		store.add_device(device.clone()).await.unwrap();
+		store.add_device(TrustedDevice::new(email.clone(), None, -1)).await.unwrap();
+
+		assert_eq!(store.get_devices(&email).await.unwrap(), vec![device]);
+	}
+
+	#[tokio::test]
+	async fn should_remove_expired_devices() {
+		let mut store = HashmapTrustedDeviceStore::default();
+		let email = Email::from_str("test@example.com").unwrap();
+
+		let device = TrustedDevice::new(email.clone(), None, 30);
+		store.add_device(device.clone()).await.unwrap();
+		store.add_device(TrustedDevice::new(email.clone(), None, -1)).await.unwrap();
+
+		assert_eq!(store.remove_expired().await, Ok(1));
+		assert_eq!(store.get_device(&device.id).await, Ok(device));
+	}
+}
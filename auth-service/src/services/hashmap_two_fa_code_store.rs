@@ -1,28 +1,44 @@
 use std::collections::HashMap;
 
-use crate::domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError};
+use chrono::Utc;
+
+use crate::domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeDelivery, TwoFACodeStore, TwoFACodeStoreError};
 
 #[derive(Default)]
 pub struct HashmapTwoFACodeStore {
-	codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+	codes: HashMap<Email, (LoginAttemptId, TwoFACode, TwoFACodeDelivery)>,
 }
 
 #[async_trait::async_trait]
 impl TwoFACodeStore for HashmapTwoFACodeStore {
+	#[tracing::instrument(name = "two_fa_code_store.add_code", skip_all, fields(email = %email.as_ref()))]
 	async fn add_code(
 		&mut self,
 		email: Email,
 		login_attempt_id: LoginAttemptId,
 		code: TwoFACode,
 	) -> Result<(), TwoFACodeStoreError> {
-		if self.codes.contains_key(&email) {
-			return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
-		}
+		let delivery = TwoFACodeDelivery {
+			sent_at: Utc::now(),
+			resend_count: 0,
+		};
+		self.codes.insert(email, (login_attempt_id, code, delivery));
+		Ok(())
+	}
+
+	#[tracing::instrument(name = "two_fa_code_store.replace_code", skip_all, fields(email = %email.as_ref()))]
+	async fn replace_code(&mut self, email: &Email, code: TwoFACode) -> Result<(), TwoFACodeStoreError> {
+		let (_, current_code, delivery) = self.codes
+			.get_mut(email)
+			.ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
 
-		self.codes.insert(email, (login_attempt_id, code));
+		*current_code = code;
+		delivery.sent_at = Utc::now();
+		delivery.resend_count += 1;
 		Ok(())
 	}
 
+	#[tracing::instrument(name = "two_fa_code_store.remove_code", skip_all, fields(email = %email.as_ref()))]
 	async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
 		if !self.codes.contains_key(email) {
 			return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
@@ -32,6 +48,7 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
 		Ok(())
 	}
 
+	#[tracing::instrument(name = "two_fa_code_store.get_code", skip_all, fields(email = %email.as_ref()))]
 	async fn get_code(
 		&self,
 		email: &Email,
@@ -40,9 +57,22 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
 			return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
 		}
 
-		let (login_attempt_id, code) = self.codes.get(email).unwrap();
+		let (login_attempt_id, code, _) = self.codes.get(email).unwrap();
 		Ok((login_attempt_id.clone(), code.clone()))
 	}
+
+	#[tracing::instrument(name = "two_fa_code_store.get_code_delivery", skip_all, fields(email = %email.as_ref()))]
+	async fn get_code_delivery(&self, email: &Email) -> Result<TwoFACodeDelivery, TwoFACodeStoreError> {
+		self.codes
+			.get(email)
+			.map(|(_, _, delivery)| delivery.clone())
+			.ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)
+	}
+
+	#[tracing::instrument(name = "two_fa_code_store.count", skip_all)]
+	async fn count(&self) -> Result<usize, TwoFACodeStoreError> {
+		Ok(self.codes.len())
+	}
 }
 
 #[cfg(test)]
@@ -64,4 +94,33 @@ mod tests {
 		assert_eq!(login_attempt_id, login_attempt_id_2);
 		assert_eq!(code, code_2);
 	}
+
+	#[tokio::test]
+	async fn should_replace_code_and_keep_login_attempt_id() {
+		let mut store = HashmapTwoFACodeStore::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let login_attempt_id = LoginAttemptId::default();
+
+		store.add_code(email.clone(), login_attempt_id.clone(), TwoFACode::default()).await.unwrap();
+		let new_code = TwoFACode::default();
+		store.replace_code(&email, new_code.clone()).await.unwrap();
+
+		assert_eq!(store.get_code(&email).await.unwrap(), (login_attempt_id, new_code));
+		assert_eq!(store.get_code_delivery(&email).await.unwrap().resend_count, 1);
+	}
+
+	#[tokio::test]
+	async fn should_replace_pending_login_attempt() {
+		let mut store = HashmapTwoFACodeStore::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		store.add_code(email.clone(), LoginAttemptId::default(), TwoFACode::default()).await.unwrap();
+		store.replace_code(&email, TwoFACode::default()).await.unwrap();
+
+		let login_attempt_id = LoginAttemptId::default();
+		let code = TwoFACode::default();
+		store.add_code(email.clone(), login_attempt_id.clone(), code.clone()).await.unwrap();
+
+		assert_eq!(store.get_code(&email).await.unwrap(), (login_attempt_id, code));
+		assert_eq!(store.get_code_delivery(&email).await.unwrap().resend_count, 0);
+	}
 }
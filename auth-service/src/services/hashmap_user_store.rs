@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::domain::{Email, Password, User, UserStore, UserStoreError};
+use crate::domain::{Email, Password, User, UserPage, UserStore, UserStoreError};
 
 // TODO: Create a new struct called `HashmapUserStore` containing a `users` field
 // which stores a `HashMap`` of email `String`s mapped to `User` objects.
@@ -13,6 +13,7 @@ pub struct HashmapUserStore {
 
 #[async_trait::async_trait]
 impl UserStore for HashmapUserStore {
+	#[tracing::instrument(name = "user_store.add_user", skip_all, fields(email = %user.email_str()))]
 	async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
 		if self.users.contains_key(user.email_str()) {
 			return Err(UserStoreError::UserAlreadyExists);
@@ -22,6 +23,7 @@ impl UserStore for HashmapUserStore {
 		Ok(())
 	}
 
+	#[tracing::instrument(name = "user_store.get_user", skip_all, fields(email = %email.as_ref()))]
 	async fn get_user(&self, email: Email) -> Result<User, UserStoreError> {
 		match self.users.get(email.as_ref()) {
 			Some(user) => Ok(user.clone()),
@@ -29,17 +31,48 @@ impl UserStore for HashmapUserStore {
 		}
 	}
 
+	#[tracing::instrument(name = "user_store.update_user", skip_all, fields(email = %user.email_str()))]
+	async fn update_user(&mut self, user: User) -> Result<(), UserStoreError> {
+		match self.users.get_mut(user.email_str()) {
+			Some(existing) => {
+				*existing = user;
+				Ok(())
+			}
+			None => Err(UserStoreError::UserNotFound),
+		}
+	}
+
+	#[tracing::instrument(name = "user_store.validate_user", skip_all, fields(email = %email.as_ref()))]
 	async fn validate_user(&self, email: Email, password: Password) -> Result<(), UserStoreError> {
 		let Ok(user) = self.get_user(email).await else {
 			return Err(UserStoreError::UserNotFound);
 		};
 
-		if user.password() == password {
+		if user.password() == Some(&password) {
 			Ok(())
 		} else {
 			Err(UserStoreError::InvalidCredentials)
 		}
 	}
+
+	#[tracing::instrument(name = "user_store.list_users", skip(self))]
+	async fn list_users(&self, search: Option<&str>, offset: usize, limit: usize) -> Result<UserPage, UserStoreError> {
+		let search = search.map(str::to_lowercase);
+		let mut users: Vec<&User> = self.users
+			.values()
+			.filter(|user| match &search {
+				Some(search) => user.email_str().to_lowercase().contains(search)
+					|| user.profile.display_name.as_ref().is_some_and(|name| name.to_lowercase().contains(search)),
+				None => true,
+			})
+			.collect();
+		users.sort_by(|a, b| a.email_str().cmp(b.email_str()));
+
+		Ok(UserPage {
+			total: users.len(),
+			users: users.into_iter().skip(offset).take(limit).cloned().collect(),
+		})
+	}
 }
 
 #[cfg(test)]
@@ -70,4 +103,34 @@ mod tests {
 		assert!(store.validate_user_str("hello@example.com", "wrong").await.is_err());
 		assert!(store.validate_user_str("another@example.com", "12341234").await.is_err());
 	}
+
+	#[tokio::test]
+	async fn test_update_user() {
+		let mut store = HashmapUserStore::default();
+		let mut user = User::from_str("hello@example.com", "12341234", false).unwrap();
+		assert_eq!(store.update_user(user.clone()).await, Err(UserStoreError::UserNotFound));
+
+		store.add_user(user.clone()).await.unwrap();
+		user.profile.display_name = Some("Hello".to_string());
+		store.update_user(user).await.unwrap();
+
+		let user = store.get_user_str("hello@example.com").await.unwrap();
+		assert_eq!(user.profile.display_name.as_deref(), Some("Hello"));
+	}
+
+	#[tokio::test]
+	async fn test_list_users() {
+		let mut store = HashmapUserStore::default();
+		for email in ["carol@example.com", "alice@example.com", "bob@test.com"] {
+			store.add_user(User::from_str(email, "12341234", false).unwrap()).await.unwrap();
+		}
+
+		let page = store.list_users(None, 1, 1).await.unwrap();
+		assert_eq!(page.total, 3);
+		assert_eq!(page.users.iter().map(User::email_str).collect::<Vec<_>>(), vec!["bob@test.com"]);
+
+		let page = store.list_users(Some("EXAMPLE"), 0, 10).await.unwrap();
+		assert_eq!(page.total, 2);
+		assert_eq!(page.users.iter().map(User::email_str).collect::<Vec<_>>(), vec!["alice@example.com", "carol@example.com"]);
+	}
 }
@@ -1,5 +1,10 @@
 use std::collections::HashSet;
 
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use serde::Deserialize;
+
 use crate::domain::{BannedTokenStore, BannedTokenStoreError};
 
 
@@ -10,12 +15,14 @@ pub struct HashsetBannedTokenStore {
 
 #[async_trait::async_trait]
 impl BannedTokenStore for HashsetBannedTokenStore {
+	#[tracing::instrument(name = "banned_token_store.add", skip_all)]
 	async fn add(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
 		self.check(&token).await?;
 		self.tokens.insert(token);
 		Ok(())
 	}
 
+	#[tracing::instrument(name = "banned_token_store.check", skip_all)]
 	async fn check(&self, token: &str) -> Result<(), BannedTokenStoreError> {
 		if self.tokens.contains(token) {
 			Err(BannedTokenStoreError::TokenIsBanned)
@@ -23,4 +30,54 @@ impl BannedTokenStore for HashsetBannedTokenStore {
 			Ok(())
 		}
 	}
+
+	#[tracing::instrument(name = "banned_token_store.count", skip_all)]
+	async fn count(&self) -> Result<usize, BannedTokenStoreError> {
+		Ok(self.tokens.len())
+	}
+
+	#[tracing::instrument(name = "banned_token_store.remove_expired", skip_all)]
+	async fn remove_expired(&mut self) -> Result<usize, BannedTokenStoreError> {
+		let now = Utc::now().timestamp();
+		let before = self.tokens.len();
+		self.tokens.retain(|token| token_expiry(token).is_none_or(|exp| exp > now));
+		Ok(before - self.tokens.len())
+	}
+}
+
+#[derive(Deserialize)]
+struct ExpiryClaim {
+	exp: i64,
+}
+
+// The `exp` claim of a JWT. The signature was checked before the token was banned, so it isn't checked again.
+// Tokens that can't be read are kept.
+fn token_expiry(token: &str) -> Option<i64> {
+	let payload = token.split('.').nth(1)?;
+	let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+	serde_json::from_slice::<ExpiryClaim>(&payload).ok().map(|claim| claim.exp)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn token(exp: i64) -> String {
+		let payload = URL_SAFE_NO_PAD.encode(serde_json::json!({"sub": "test@example.com", "exp": exp}).to_string());
+		format!("header.{payload}.signature")
+	}
+
+	#[tokio::test]
+	async fn test_remove_expired_keeps_live_tokens() {
+		let mut store = HashsetBannedTokenStore::default();
+		let now = Utc::now().timestamp();
+		store.add(token(now - 60)).await.unwrap();
+		store.add(token(now + 60)).await.unwrap();
+		store.add("not-a-jwt".to_string()).await.unwrap();
+
+		assert_eq!(store.remove_expired().await, Ok(1));
+		assert_eq!(store.check(&token(now - 60)).await, Ok(()));
+		assert_eq!(store.check(&token(now + 60)).await, Err(BannedTokenStoreError::TokenIsBanned));
+		assert_eq!(store.check("not-a-jwt").await, Err(BannedTokenStoreError::TokenIsBanned));
+	}
 }
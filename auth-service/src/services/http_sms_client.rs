@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+use crate::domain::{PhoneNumber, SmsClient};
+
+// Sends text messages through an HTTP SMS gateway, which gets `POST {base_url}/messages`
+// with a JSON body and the API key as a bearer token
+pub struct HttpSmsClient {
+	http_client: reqwest::Client,
+	base_url: String,
+	api_key: String,
+}
+
+impl HttpSmsClient {
+	pub fn new(http_client: reqwest::Client, base_url: String, api_key: String) -> Self {
+		Self {
+			http_client,
+			base_url: base_url.trim_end_matches('/').to_string(),
+			api_key,
+		}
+	}
+}
+
+#[derive(Serialize)]
+struct SendSmsRequest<'a> {
+	to: &'a str,
+	message: &'a str,
+}
+
+#[async_trait::async_trait]
+impl SmsClient for HttpSmsClient {
+	async fn send_sms(&self, recipient: &PhoneNumber, content: &str) -> Result<(), String> {
+		let request = SendSmsRequest {
+			to: recipient.as_ref(),
+			message: content,
+		};
+
+		self.http_client
+			.post(format!("{}/messages", self.base_url))
+			.bearer_auth(&self.api_key)
+			.json(&request)
+			.send()
+			.await
+			.map_err(|e| e.to_string())?
+			.error_for_status()
+			.map_err(|e| e.to_string())?;
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use std::sync::{Arc, Mutex};
+
+	use axum::http::{HeaderMap, StatusCode};
+	use axum::routing::post;
+	use axum::{Json, Router};
+
+	use super::*;
+
+	type Received = Arc<Mutex<Vec<(Option<String>, serde_json::Value)>>>;
+
+	// Local stand-in for the SMS gateway that records what it receives and answers with `status`
+	async fn spawn_gateway(status: StatusCode) -> (String, Received) {
+		let received = Received::default();
+		let recorder = received.clone();
+		let router = Router::new().route("/messages", post(move |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+			let authorization = headers.get("authorization").and_then(|value| value.to_str().ok()).map(str::to_owned);
+			recorder.lock().unwrap().push((authorization, body));
+			status
+		}));
+
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let address = format!("http://{}", listener.local_addr().unwrap());
+		tokio::spawn(async move { axum::serve(listener, router).await });
+
+		(address, received)
+	}
+
+	#[tokio::test]
+	async fn should_post_message_to_gateway() {
+		let (address, received) = spawn_gateway(StatusCode::ACCEPTED).await;
+		let client = HttpSmsClient::new(reqwest::Client::new(), address, "secret".to_string());
+		let recipient = PhoneNumber::from_str("+306912345678").unwrap();
+
+		client.send_sms(&recipient, "Your 2FA code is: 123456").await.unwrap();
+
+		let received = received.lock().unwrap();
+		assert_eq!(received.len(), 1);
+		assert_eq!(received[0].0.as_deref(), Some("Bearer secret"));
+		assert_eq!(received[0].1, serde_json::json!({"to": "+306912345678", "message": "Your 2FA code is: 123456"}));
+	}
+
+	#[tokio::test]
+	async fn should_fail_if_gateway_rejects_message() {
+		let (address, _) = spawn_gateway(StatusCode::BAD_REQUEST).await;
+		let client = HttpSmsClient::new(reqwest::Client::new(), address, "secret".to_string());
+		let recipient = PhoneNumber::from_str("+306912345678").unwrap();
+
+		assert!(client.send_sms(&recipient, "Your 2FA code is: 123456").await.is_err());
+	}
+}
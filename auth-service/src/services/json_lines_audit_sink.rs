@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+use crate::domain::{AuditEvent, AuditEventKind, AuditQuery, AuditSink, AuditSinkError, Email};
+
+// Appends the audit log to a file, one JSON object per line, so it can be shipped to
+// log tooling. Lines are only ever appended, never rewritten.
+pub struct JsonLinesAuditSink {
+	path: PathBuf,
+}
+
+impl JsonLinesAuditSink {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+}
+
+#[async_trait::async_trait]
+impl AuditSink for JsonLinesAuditSink {
+	async fn record(&mut self, event: AuditEvent) -> Result<(), AuditSinkError> {
+		let mut line = serde_json::to_string(&AuditRecord::from(event)).map_err(|_| AuditSinkError::UnexpectedError)?;
+		line.push('\n');
+
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.await
+			.map_err(|_| AuditSinkError::UnexpectedError)?;
+		file.write_all(line.as_bytes()).await.map_err(|_| AuditSinkError::UnexpectedError)?;
+		file.flush().await.map_err(|_| AuditSinkError::UnexpectedError)?;
+
+		Ok(())
+	}
+
+	async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>, AuditSinkError> {
+		let contents = match tokio::fs::read_to_string(&self.path).await {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(_) => return Err(AuditSinkError::UnexpectedError),
+		};
+
+		let mut events = Vec::new();
+		for line in contents.lines().rev().filter(|line| !line.trim().is_empty()) {
+			let record: AuditRecord = serde_json::from_str(line).map_err(|_| AuditSinkError::UnexpectedError)?;
+			let event = AuditEvent::try_from(record)?;
+			if query.matches(&event) {
+				events.push(event);
+				if events.len() == query.limit {
+					break;
+				}
+			}
+		}
+
+		Ok(events)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuditRecord {
+	#[serde(rename = "type")]
+	kind: String,
+	email: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	actor: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	ip: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	details: Option<String>,
+	at: DateTime<Utc>,
+}
+
+impl From<AuditEvent> for AuditRecord {
+	fn from(event: AuditEvent) -> Self {
+		Self {
+			kind: event.kind.to_string(),
+			email: event.email.as_ref().to_owned(),
+			actor: event.actor.map(|actor| actor.as_ref().to_owned()),
+			ip: event.ip,
+			details: event.details,
+			at: event.at,
+		}
+	}
+}
+
+impl TryFrom<AuditRecord> for AuditEvent {
+	type Error = AuditSinkError;
+
+	fn try_from(record: AuditRecord) -> Result<Self, Self::Error> {
+		Ok(Self {
+			kind: AuditEventKind::from_str(&record.kind).map_err(|_| AuditSinkError::UnexpectedError)?,
+			email: Email::from_str(&record.email).map_err(|_| AuditSinkError::UnexpectedError)?,
+			actor: record.actor
+				.map(|actor| Email::from_str(&actor))
+				.transpose()
+				.map_err(|_| AuditSinkError::UnexpectedError)?,
+			ip: record.ip,
+			details: record.details,
+			at: record.at,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_record_and_query_events() {
+		let path = std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()));
+		let mut sink = JsonLinesAuditSink::new(&path);
+		let email = Email::from_str("test@example.com").unwrap();
+		let admin = Email::from_str("admin@example.com").unwrap();
+
+		assert_eq!(sink.query(&AuditQuery { limit: 10, ..Default::default() }).await.unwrap(), vec![]);
+
+		let login = AuditEvent::new(AuditEventKind::LoginSucceeded, email.clone(), Some("127.0.0.1".to_string()))
+			.with_details("password");
+		let unlock = AuditEvent::new(AuditEventKind::AccountUnlocked, email.clone(), None).by(admin);
+		sink.record(login.clone()).await.unwrap();
+		sink.record(unlock.clone()).await.unwrap();
+
+		let contents = tokio::fs::read_to_string(&path).await.unwrap();
+		assert_eq!(contents.lines().count(), 2);
+		assert!(contents.starts_with(r#"{"type":"login_succeeded","email":"test@example.com""#));
+
+		let query = AuditQuery { email: Some(email), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![unlock.clone(), login.clone()]);
+		let query = AuditQuery { kind: Some(AuditEventKind::LoginSucceeded), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![login]);
+		let query = AuditQuery { limit: 1, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![unlock]);
+
+		tokio::fs::remove_file(&path).await.unwrap();
+	}
+}
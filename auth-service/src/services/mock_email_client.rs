@@ -1,10 +1,29 @@
+use std::sync::{Arc, Mutex};
+
 use crate::domain::{Email, EmailClient};
 
-#[derive(Default)]
-pub struct MockEmailClient;
+// Prints emails instead of sending them, and keeps them around so tests can inspect them
+#[derive(Default, Clone)]
+pub struct MockEmailClient {
+	sent_emails: Arc<Mutex<Vec<SentEmail>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentEmail {
+	pub recipient: String,
+	pub subject: String,
+	pub content: String,
+}
+
+impl MockEmailClient {
+	pub fn sent_emails(&self) -> Vec<SentEmail> {
+		self.sent_emails.lock().unwrap().clone()
+	}
+}
 
 #[async_trait::async_trait]
 impl EmailClient for MockEmailClient {
+	#[tracing::instrument(name = "email_client.send_email", skip_all, fields(recipient = %recipient.as_ref(), subject = %subject))]
 	async fn send_email(
 		&self,
 		recipient: &Email,
@@ -18,6 +37,12 @@ impl EmailClient for MockEmailClient {
 			content
 		);
 
+		self.sent_emails.lock().map_err(|e| e.to_string())?.push(SentEmail {
+			recipient: recipient.as_ref().to_string(),
+			subject: subject.to_string(),
+			content: content.to_string(),
+		});
+
 		Ok(())
 	}
 }
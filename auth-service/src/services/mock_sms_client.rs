@@ -0,0 +1,35 @@
+use std::sync::{Arc, Mutex};
+
+use crate::domain::{PhoneNumber, SmsClient};
+
+// Prints text messages instead of sending them, and keeps them around so tests can inspect them
+#[derive(Default, Clone)]
+pub struct MockSmsClient {
+	sent_messages: Arc<Mutex<Vec<SentSms>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentSms {
+	pub recipient: String,
+	pub content: String,
+}
+
+impl MockSmsClient {
+	pub fn sent_messages(&self) -> Vec<SentSms> {
+		self.sent_messages.lock().unwrap().clone()
+	}
+}
+
+#[async_trait::async_trait]
+impl SmsClient for MockSmsClient {
+	async fn send_sms(&self, recipient: &PhoneNumber, content: &str) -> Result<(), String> {
+		println!("Sending SMS to {} with content: {}", recipient.as_ref(), content);
+
+		self.sent_messages.lock().map_err(|e| e.to_string())?.push(SentSms {
+			recipient: recipient.as_ref().to_string(),
+			content: content.to_string(),
+		});
+
+		Ok(())
+	}
+}
@@ -2,3 +2,17 @@ pub mod hashmap_user_store;
 pub mod hashset_banned_token_store;
 pub mod hashmap_two_fa_code_store;
 pub mod mock_email_client;
+pub mod hashmap_authorization_code_store;
+pub mod hashmap_client_store;
+pub mod sqlite_client_store;
+pub mod hashmap_device_code_store;
+pub mod hashmap_session_store;
+pub mod hashmap_recovery_code_store;
+pub mod hashmap_trusted_device_store;
+pub mod hashmap_passkey_store;
+pub mod http_sms_client;
+pub mod mock_sms_client;
+pub mod webhook_client;
+pub mod vec_audit_sink;
+pub mod sqlite_audit_sink;
+pub mod json_lines_audit_sink;
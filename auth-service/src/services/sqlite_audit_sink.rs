@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use chrono::DateTime;
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use crate::domain::{AuditEvent, AuditEventKind, AuditQuery, AuditSink, AuditSinkError, Email};
+use crate::DatabasePool;
+
+// Audit log in the `audit_events` table, which triggers keep append-only
+pub struct SqliteAuditSink {
+	pool: DatabasePool,
+}
+
+impl SqliteAuditSink {
+	pub fn new(pool: DatabasePool) -> Self {
+		Self { pool }
+	}
+}
+
+#[async_trait::async_trait]
+impl AuditSink for SqliteAuditSink {
+	async fn record(&mut self, event: AuditEvent) -> Result<(), AuditSinkError> {
+		sqlx::query("INSERT INTO audit_events (kind, email, actor, ip, details, at) VALUES (?, ?, ?, ?, ?, ?)")
+			.bind(event.kind.as_str())
+			.bind(event.email.as_ref())
+			.bind(event.actor.as_ref().map(AsRef::<str>::as_ref))
+			.bind(&event.ip)
+			.bind(&event.details)
+			.bind(event.at.timestamp_micros())
+			.execute(&self.pool)
+			.await
+			.map_err(|_| AuditSinkError::UnexpectedError)?;
+
+		Ok(())
+	}
+
+	async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>, AuditSinkError> {
+		let email = query.email.as_ref().map(AsRef::<str>::as_ref);
+		let kind = query.kind.map(|kind| kind.as_str());
+		let since = query.since.map(|since| since.timestamp_micros());
+		let until = query.until.map(|until| until.timestamp_micros());
+		let limit = i64::try_from(query.limit).unwrap_or(i64::MAX);
+
+		let rows = sqlx::query(
+			"SELECT kind, email, actor, ip, details, at FROM audit_events \
+			WHERE (?1 IS NULL OR email = ?1) AND (?2 IS NULL OR kind = ?2) \
+			AND (?3 IS NULL OR at >= ?3) AND (?4 IS NULL OR at < ?4) \
+			ORDER BY at DESC, id DESC LIMIT ?5"
+		)
+			.bind(email)
+			.bind(kind)
+			.bind(since)
+			.bind(until)
+			.bind(limit)
+			.fetch_all(&self.pool)
+			.await
+			.map_err(|_| AuditSinkError::UnexpectedError)?;
+
+		rows.iter().map(event_from_row).collect()
+	}
+
+	async fn health_check(&self) -> Result<(), AuditSinkError> {
+		sqlx::query("SELECT 1")
+			.execute(&self.pool)
+			.await
+			.map_err(|_| AuditSinkError::UnexpectedError)?;
+
+		Ok(())
+	}
+}
+
+fn event_from_row(row: &SqliteRow) -> Result<AuditEvent, AuditSinkError> {
+	let kind: String = row.get("kind");
+	let email: String = row.get("email");
+	let actor: Option<String> = row.get("actor");
+
+	Ok(AuditEvent {
+		kind: AuditEventKind::from_str(&kind).map_err(|_| AuditSinkError::UnexpectedError)?,
+		email: Email::from_str(&email).map_err(|_| AuditSinkError::UnexpectedError)?,
+		actor: actor
+			.map(|actor| Email::from_str(&actor))
+			.transpose()
+			.map_err(|_| AuditSinkError::UnexpectedError)?,
+		ip: row.get("ip"),
+		details: row.get("details"),
+		at: DateTime::from_timestamp_micros(row.get("at")).ok_or(AuditSinkError::UnexpectedError)?,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use sqlx::sqlite::SqlitePoolOptions;
+
+	use super::*;
+
+	async fn sink() -> SqliteAuditSink {
+		// A single connection, otherwise every connection gets its own in-memory database
+		let pool = SqlitePoolOptions::new()
+			.max_connections(1)
+			.connect("sqlite::memory:")
+			.await
+			.unwrap();
+		sqlx::migrate!().run(&pool).await.unwrap();
+		SqliteAuditSink::new(pool)
+	}
+
+	#[tokio::test]
+	async fn test_record_and_query_events() {
+		let mut sink = sink().await;
+		let email = Email::from_str("test@example.com").unwrap();
+		let admin = Email::from_str("admin@example.com").unwrap();
+		let mut login = AuditEvent::new(AuditEventKind::LoginSucceeded, email.clone(), Some("127.0.0.1".to_string()))
+			.with_details("password");
+		login.at -= chrono::Duration::minutes(5);
+		let unlock = AuditEvent::new(AuditEventKind::AccountUnlocked, email.clone(), None).by(admin);
+		sink.record(login.clone()).await.unwrap();
+		sink.record(unlock.clone()).await.unwrap();
+
+		let query = AuditQuery { email: Some(email), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![unlock.clone(), login.clone()]);
+
+		let query = AuditQuery { kind: Some(AuditEventKind::LoginSucceeded), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![login.clone()]);
+
+		let query = AuditQuery { since: Some(unlock.at), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![unlock]);
+		let query = AuditQuery { until: Some(login.at + chrono::Duration::seconds(1)), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![login]);
+	}
+
+	#[tokio::test]
+	async fn test_events_cannot_be_deleted() {
+		let mut sink = sink().await;
+		let email = Email::from_str("test@example.com").unwrap();
+		sink.record(AuditEvent::new(AuditEventKind::LoggedOut, email, None)).await.unwrap();
+
+		assert!(sqlx::query("DELETE FROM audit_events").execute(&sink.pool).await.is_err());
+		assert!(sqlx::query("UPDATE audit_events SET email = 'x@example.com'").execute(&sink.pool).await.is_err());
+	}
+}
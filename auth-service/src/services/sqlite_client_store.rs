@@ -0,0 +1,107 @@
+use sqlx::Row;
+
+use crate::domain::{ClientStore, ClientStoreError, OAuthClient};
+use crate::DatabasePool;
+
+pub struct SqliteClientStore {
+	pool: DatabasePool,
+}
+
+impl SqliteClientStore {
+	pub fn new(pool: DatabasePool) -> Self {
+		Self { pool }
+	}
+}
+
+#[async_trait::async_trait]
+impl ClientStore for SqliteClientStore {
+	async fn add_client(&mut self, client: OAuthClient) -> Result<(), ClientStoreError> {
+		let redirect_uris = serde_json::to_string(&client.redirect_uris)
+			.map_err(|_| ClientStoreError::UnexpectedError)?;
+
+		sqlx::query("INSERT INTO oauth_clients (client_id, secret_hash, redirect_uris, allowed_scopes) VALUES (?, ?, ?, ?)")
+			.bind(&client.client_id)
+			.bind(&client.secret_hash)
+			.bind(redirect_uris)
+			.bind(client.allowed_scopes.join(" "))
+			.execute(&self.pool)
+			.await
+			.map_err(|e| match e {
+				sqlx::Error::Database(e) if e.is_unique_violation() => ClientStoreError::ClientAlreadyExists,
+				_ => ClientStoreError::UnexpectedError,
+			})?;
+
+		Ok(())
+	}
+
+	async fn get_client(&self, client_id: &str) -> Result<OAuthClient, ClientStoreError> {
+		let row = sqlx::query("SELECT client_id, secret_hash, redirect_uris, allowed_scopes FROM oauth_clients WHERE client_id = ?")
+			.bind(client_id)
+			.fetch_optional(&self.pool)
+			.await
+			.map_err(|_| ClientStoreError::UnexpectedError)?
+			.ok_or(ClientStoreError::ClientNotFound)?;
+
+		let redirect_uris: String = row.get("redirect_uris");
+		let allowed_scopes: String = row.get("allowed_scopes");
+
+		Ok(OAuthClient {
+			client_id: row.get("client_id"),
+			secret_hash: row.get("secret_hash"),
+			redirect_uris: serde_json::from_str(&redirect_uris).map_err(|_| ClientStoreError::UnexpectedError)?,
+			allowed_scopes: allowed_scopes.split_whitespace().map(str::to_owned).collect(),
+		})
+	}
+
+	async fn health_check(&self) -> Result<(), ClientStoreError> {
+		sqlx::query("SELECT 1")
+			.execute(&self.pool)
+			.await
+			.map_err(|_| ClientStoreError::UnexpectedError)?;
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use sqlx::sqlite::SqlitePoolOptions;
+
+	use super::*;
+
+	async fn store() -> SqliteClientStore {
+		// A single connection, otherwise every connection gets its own in-memory database
+		let pool = SqlitePoolOptions::new()
+			.max_connections(1)
+			.connect("sqlite::memory:")
+			.await
+			.unwrap();
+		sqlx::migrate!().run(&pool).await.unwrap();
+		SqliteClientStore::new(pool)
+	}
+
+	#[tokio::test]
+	async fn test_add_and_get_client() {
+		let mut store = store().await;
+		let client = OAuthClient {
+			client_id: "client".to_string(),
+			secret_hash: Some("hash".to_string()),
+			redirect_uris: vec!["http://localhost/callback".to_string(), "http://localhost/other".to_string()],
+			allowed_scopes: vec!["openid".to_string(), "email".to_string()],
+		};
+
+		store.add_client(client.clone()).await.unwrap();
+		assert_eq!(store.get_client("client").await.unwrap(), client);
+		assert_eq!(store.add_client(client).await, Err(ClientStoreError::ClientAlreadyExists));
+		assert_eq!(store.get_client("other").await, Err(ClientStoreError::ClientNotFound));
+	}
+
+	#[tokio::test]
+	async fn test_health_check_fails_once_the_pool_is_closed() {
+		let store = store().await;
+		assert_eq!(store.health_check().await, Ok(()));
+
+		store.pool.close().await;
+		assert_eq!(store.health_check().await, Err(ClientStoreError::UnexpectedError));
+	}
+}
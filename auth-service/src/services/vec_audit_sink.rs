@@ -0,0 +1,50 @@
+use crate::domain::{AuditEvent, AuditQuery, AuditSink, AuditSinkError};
+
+// Keeps the audit log in memory, for tests and local development
+#[derive(Default)]
+pub struct VecAuditSink {
+	events: Vec<AuditEvent>,
+}
+
+#[async_trait::async_trait]
+impl AuditSink for VecAuditSink {
+	async fn record(&mut self, event: AuditEvent) -> Result<(), AuditSinkError> {
+		self.events.push(event);
+		Ok(())
+	}
+
+	async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>, AuditSinkError> {
+		Ok(self.events
+			.iter()
+			.rev()
+			.filter(|event| query.matches(event))
+			.take(query.limit)
+			.cloned()
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+	use crate::domain::{AuditEventKind, Email};
+
+	#[tokio::test]
+	async fn should_return_newest_matching_events() {
+		let mut sink = VecAuditSink::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let other = Email::from_str("other@example.com").unwrap();
+		let first = AuditEvent::new(AuditEventKind::LoginSucceeded, email.clone(), None);
+		let second = AuditEvent::new(AuditEventKind::LoggedOut, email.clone(), None);
+		sink.record(first.clone()).await.unwrap();
+		sink.record(AuditEvent::new(AuditEventKind::LoginSucceeded, other, None)).await.unwrap();
+		sink.record(second.clone()).await.unwrap();
+
+		let query = AuditQuery { email: Some(email), limit: 10, ..Default::default() };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![second.clone(), first]);
+		let query = AuditQuery { limit: 1, ..query };
+		assert_eq!(sink.query(&query).await.unwrap(), vec![second]);
+	}
+}
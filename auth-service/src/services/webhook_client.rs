@@ -0,0 +1,169 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Serialize;
+use url::{Host, Url};
+
+use crate::domain::WebhookUrl;
+
+// How long the user's webhook gets to accept a code
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Posts to the webhooks users have their 2FA codes delivered to. Users pick those URLs, so they're
+// kept from reaching into the network the service runs in: only https, only public addresses,
+// checked again after every DNS lookup, and no following redirects.
+#[derive(Clone)]
+pub struct WebhookClient {
+	http_client: reqwest::Client,
+	allow_local: bool,
+}
+
+impl Default for WebhookClient {
+	fn default() -> Self {
+		Self::build(false)
+	}
+}
+
+impl WebhookClient {
+	// For tests and local development, whose webhooks run on the same machine over plain http
+	pub fn allowing_local_webhooks() -> Self {
+		Self::build(true)
+	}
+
+	fn build(allow_local: bool) -> Self {
+		let mut http_client = reqwest::Client::builder()
+			.redirect(reqwest::redirect::Policy::none())
+			.timeout(WEBHOOK_TIMEOUT);
+		if !allow_local {
+			http_client = http_client.dns_resolver(Arc::new(PublicResolver));
+		}
+
+		Self {
+			http_client: http_client.build().expect("Failed to build webhook client"),
+			allow_local,
+		}
+	}
+
+	// What can be told from the URL alone, i.e. without looking up the host
+	pub fn check_url(&self, url: &WebhookUrl) -> Result<(), String> {
+		if self.allow_local {
+			return Ok(());
+		}
+
+		let url = Url::parse(url.as_ref()).map_err(|e| e.to_string())?;
+		if url.scheme() != "https" {
+			return Err("Webhook URL must be an https URL".to_string());
+		}
+		let is_public = match url.host() {
+			Some(Host::Domain(domain)) => domain != "localhost" && !domain.ends_with(".localhost"),
+			Some(Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+			Some(Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+			None => false,
+		};
+		if !is_public {
+			return Err("Webhook URL must point to a public host".to_string());
+		}
+
+		Ok(())
+	}
+
+	pub async fn post<T: Serialize>(&self, url: &WebhookUrl, body: &T) -> Result<(), String> {
+		// Checked again, as the URL may have been picked under an older policy
+		self.check_url(url)?;
+
+		self.http_client
+			.post(url.as_ref())
+			.json(body)
+			.send().await
+			.and_then(|response| response.error_for_status())
+			.map(|_| ())
+			.map_err(|e| e.to_string())
+	}
+}
+
+// Resolves hosts like the system does, but fails for those with any address that isn't public.
+// The connection is made to the addresses checked here, so the host can't be changed to point
+// somewhere else after the check.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+	fn resolve(&self, name: Name) -> Resolving {
+		Box::pin(async move {
+			let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+			if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+				return Err(format!("{} resolves to an address that isn't public", name.as_str()).into());
+			}
+
+			Ok(Box::new(addrs.into_iter()) as Addrs)
+		})
+	}
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+	match ip {
+		IpAddr::V4(ip) => is_public_ipv4(ip),
+		IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+			Some(ip) => is_public_ipv4(ip),
+			None => is_public_ipv6(ip),
+		},
+	}
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+	// 100.64.0.0/10, the shared address space of carrier-grade NAT
+	let is_shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64;
+
+	!(ip.is_unspecified()
+		|| ip.is_loopback()
+		|| ip.is_private()
+		|| ip.is_link_local()
+		|| ip.is_broadcast()
+		|| ip.is_documentation()
+		|| ip.is_multicast()
+		|| is_shared)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+	!(ip.is_unspecified()
+		|| ip.is_loopback()
+		|| ip.is_unique_local()
+		|| ip.is_unicast_link_local()
+		|| ip.is_multicast())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+
+	fn check(url: &str) -> Result<(), String> {
+		WebhookClient::default().check_url(&WebhookUrl::from_str(url).unwrap())
+	}
+
+	#[test]
+	fn test_check_url() {
+		assert!(check("https://example.com/2fa").is_ok());
+		assert!(check("https://93.184.216.34/2fa").is_ok());
+		assert!(check("http://example.com/2fa").is_err());
+		assert!(check("https://localhost/2fa").is_err());
+		assert!(check("https://api.localhost/2fa").is_err());
+		assert!(check("https://127.0.0.1/2fa").is_err());
+		assert!(check("https://10.0.0.1/2fa").is_err());
+		assert!(check("https://169.254.169.254/latest/meta-data").is_err());
+		assert!(check("https://[::1]/2fa").is_err());
+		assert!(check("https://[::ffff:192.168.0.1]/2fa").is_err());
+		assert!(check("https://[fd00::1]/2fa").is_err());
+
+		let url = WebhookUrl::from_str("http://127.0.0.1:8080/2fa").unwrap();
+		assert!(WebhookClient::allowing_local_webhooks().check_url(&url).is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_resolver_rejects_local_hosts() {
+		let name = Name::from_str("localhost").unwrap();
+		assert!(PublicResolver.resolve(name).await.is_err());
+	}
+}
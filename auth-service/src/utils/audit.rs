@@ -0,0 +1,40 @@
+use crate::domain::{AuditEvent, AuditEventKind, AuthAPIError, Email, User};
+use crate::AppState;
+
+use super::auth::{ensure_active, Claims};
+
+// Add an event to the audit log. The action it describes already happened, so a sink that's
+// down is reported but doesn't fail the request.
+pub async fn record_event(state: &AppState, event: AuditEvent) {
+	let kind = event.kind;
+	if let Err(e) = state.audit_sink.write().await.record(event).await {
+		tracing::error!(%kind, error = ?e, "failed to record audit event");
+	}
+}
+
+// An event about the owner of the token, naming the administrator when they're impersonating the user
+pub fn token_event(kind: AuditEventKind, claims: &Claims, ip: Option<String>) -> Result<AuditEvent, AuthAPIError> {
+	let email: Email = claims.sub.parse().map_err(|_| AuthAPIError::InvalidToken)?;
+	let event = AuditEvent::new(kind, email, ip);
+
+	match &claims.act {
+		Some(actor) => Ok(event.by(actor.sub.parse().map_err(|_| AuthAPIError::InvalidToken)?)),
+		None => Ok(event),
+	}
+}
+
+// `ensure_active` for logins, auditing the ones refused because of the account's status
+pub async fn ensure_active_login(state: &AppState, user: &User, ip: Option<String>) -> Result<(), AuthAPIError> {
+	if let Err(error) = ensure_active(user) {
+		let details = format!("account is {}", user.status.as_str());
+		record_event(state, AuditEvent::new(AuditEventKind::LoginFailed, user.email(), ip).with_details(details)).await;
+		return Err(error);
+	}
+
+	Ok(())
+}
+
+// A completed login, with how the user logged in
+pub async fn record_login(state: &AppState, email: &Email, ip: Option<String>, method: &str) {
+	record_event(state, AuditEvent::new(AuditEventKind::LoginSucceeded, email.clone(), ip).with_details(method)).await;
+}
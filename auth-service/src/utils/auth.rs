@@ -1,29 +1,64 @@
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap};
 use axum_extra::extract::cookie::{Cookie, SameSite};
+use axum_extra::extract::CookieJar;
 use chrono::Utc;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Validation};
 use serde::{Deserialize, Serialize};
 
-use crate::{domain::Email, BannedTokenStoreType};
-
-use super::constants::{JWT_COOKIE_NAME, JWT_SECRET};
+use crate::domain::{
+	AccountStatus, AuditEvent, AuditEventKind, AuthAPIError, Email, Password, Permission, Role, Session, SessionId, User,
+	UserStoreError,
+};
+use crate::{AppState, Config};
 
+use super::audit::record_event;
 // Create cookie with a new JWT auth token
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>, GenerateTokenError> {
-	let token = generate_auth_token(email)?;
-	Ok(create_auth_cookie(token))
+pub fn generate_auth_cookie(
+	config: &Config,
+	email: &Email,
+	session_id: &SessionId,
+	permissions: &[Permission],
+) -> Result<Cookie<'static>, GenerateTokenError> {
+	let token = generate_auth_token(config, email, session_id, permissions)?;
+	Ok(create_auth_cookie(config, token))
 }
 
 // Create cookie and set the value to the passed-in token string 
-fn create_auth_cookie(token: String) -> Cookie<'static> {
-	let cookie = Cookie::build((JWT_COOKIE_NAME, token))
+fn create_auth_cookie(config: &Config, token: String) -> Cookie<'static> {
+	let mut cookie = session_cookie(config, config.auth_cookie_name(), token);
+	cookie.set_http_only(true); // prevent JavaScript from accessing the cookie
+	cookie
+}
+
+// Cookie that lives as long as the auth token, with the attributes the cookie policy asks for
+pub(crate) fn session_cookie(config: &Config, name: String, value: String) -> Cookie<'static> {
+	let mut cookie = Cookie::build((name, value))
 		.path("/") // apply cookie to all URLs on the server
-		.http_only(true) // prevent JavaScript from accessing the cookie
-		.same_site(SameSite::Lax) // send cookie with "same-site" requests, and with "cross-site" top-level navigations.
+		.same_site(SameSite::from(config.cookies.same_site)) // which cross-site requests the cookie is sent with
+		.secure(config.secure_cookies()) // only send cookie over HTTPS
+		.max_age(time::Duration::seconds(config.auth.token_ttl_seconds)) // expire with the token in it
 		.build();
+	if let Some(domain) = &config.cookies.domain {
+		cookie.set_domain(domain.clone()); // also send cookie to subdomains
+	}
 
 	cookie
 }
 
+// Cookie that clears the auth cookie. Browsers only replace a cookie with matching attributes,
+// so it's built the same way.
+pub fn auth_cookie_removal(config: &Config) -> Cookie<'static> {
+	let mut cookie = create_auth_cookie(config, String::new());
+	cookie.make_removal();
+	cookie
+}
+
 #[derive(Debug)]
 pub enum GenerateTokenError {
 	#[allow(dead_code)]
@@ -31,73 +66,439 @@ pub enum GenerateTokenError {
 	UnexpectedError,
 }
 
-// This value determines how long the JWT auth token is valid for
-pub const TOKEN_TTL_SECONDS: i64 = 600; // 10 minutes
+// Create JWT auth token carrying the permissions of the user's roles
+pub fn generate_auth_token(
+	config: &Config,
+	email: &Email,
+	session_id: &SessionId,
+	permissions: &[Permission],
+) -> Result<String, GenerateTokenError> {
+	generate_scoped_auth_token(config, email, session_id, None, permissions)
+}
+
+// Create JWT auth token limited to `scope`, as handed out to OAuth clients.
+// Tokens without a scope belong to our own UI.
+pub fn generate_scoped_auth_token(
+	config: &Config,
+	email: &Email,
+	session_id: &SessionId,
+	scope: Option<&str>,
+	permissions: &[Permission],
+) -> Result<String, GenerateTokenError> {
+	let exp = expires_in(config.auth.token_ttl_seconds)?;
+
+	let sub = email.as_ref().to_owned();
+
+	let scope = scope.map(str::to_owned);
+
+	let sid = session_id.as_ref().to_owned();
+
+	let permissions = (!permissions.is_empty()).then(|| {
+		permissions.iter().map(Permission::as_str).collect::<Vec<_>>().join(" ")
+	});
+
+	let claims = Claims { sub, exp, scope, sid, permissions, act: None };
+
+	create_token(config, &claims).map_err(GenerateTokenError::TokenError)
+}
+
+// Impersonation tokens can't be refreshed, so this is how long an administrator gets to act as the user
+pub const IMPERSONATION_TOKEN_TTL_SECONDS: i64 = 900; // 15 minutes
 
-// Create JWT auth token
-fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError> {
-	let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
+// Create JWT auth token letting `actor` act as the user. The token names the actor in an
+// `act` claim (RFC 8693 section 4.1), and carries none of the permissions of either of them.
+pub fn generate_impersonation_token(
+	config: &Config,
+	email: &Email,
+	session_id: &SessionId,
+	actor: &Email,
+) -> Result<String, GenerateTokenError> {
+	let claims = Claims {
+		sub: email.as_ref().to_owned(),
+		exp: expires_in(IMPERSONATION_TOKEN_TTL_SECONDS)?,
+		scope: None,
+		sid: session_id.as_ref().to_owned(),
+		permissions: None,
+		act: Some(Actor { sub: actor.as_ref().to_owned() }),
+	};
+
+	create_token(config, &claims).map_err(GenerateTokenError::TokenError)
+}
+
+// JWT expiration time, `ttl_seconds` from now
+fn expires_in(ttl_seconds: i64) -> Result<usize, GenerateTokenError> {
+	let delta = chrono::Duration::try_seconds(ttl_seconds)
 		.ok_or(GenerateTokenError::UnexpectedError)?;
 
-	// Create JWT expiration time
 	let exp = Utc::now()
 		.checked_add_signed(delta)
 		.ok_or(GenerateTokenError::UnexpectedError)?
 		.timestamp();
 
 	// Cast exp to a usize, which is what Claims expects
-	let exp: usize = exp
-		.try_into()
-		.map_err(|_| GenerateTokenError::UnexpectedError)?;
+	exp.try_into().map_err(|_| GenerateTokenError::UnexpectedError)
+}
 
-	let sub = email.as_ref().to_owned();
+// Register a new login session, which every auth token issued for the login is bound to
+pub async fn start_session(state: &AppState, email: &Email, client: ClientInfo) -> Result<SessionId, AuthAPIError> {
+	let session = Session::new(email.clone(), client.user_agent, client.ip);
+	let session_id = session.id.clone();
+
+	state.session_store
+		.write().await
+		.add_session(session).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(session_id)
+}
+
+// Record that the user proved they own their mailbox. Accounts with one of the configured admin emails get
+// the admin role at this point, as anyone could sign up with the address before its owner does.
+pub fn verify_email(config: &Config, user: &mut User) {
+	user.email_verified = true;
+	if config.auth.admin_emails.iter().any(|email| email == user.email_str()) && !user.roles.contains(&Role::Admin) {
+		user.roles.push(Role::Admin);
+	}
+}
+
+// Check the user's password, keeping count of wrong ones. Reaching the policy's limit locks
+// password logins until the lockout is over or an administrator unlocks the account.
+// Failed attempts are audited, whether or not the account exists.
+pub async fn verify_password(
+	state: &AppState,
+	email: &Email,
+	password: Password,
+	ip: Option<String>,
+) -> Result<User, AuthAPIError> {
+	let failed = |details: &str| AuditEvent::new(AuditEventKind::LoginFailed, email.clone(), ip.clone()).with_details(details);
+
+	let mut user_store = state.user_store.write().await;
+	let timer = state.metrics.password_check_duration.start_timer();
+	let password_matches = user_store.validate_user(email.clone(), password).await.is_ok();
+	timer.observe_duration();
+	// Unknown users get the same answer as a wrong password
+	let Ok(mut user) = user_store.get_user(email.clone()).await else {
+		drop(user_store);
+		record_event(state, failed("unknown user")).await;
+		return Err(AuthAPIError::IncorrectPassword);
+	};
+	if user.is_locked() {
+		drop(user_store);
+		record_event(state, failed("account locked")).await;
+		return Err(AuthAPIError::AccountLocked);
+	}
+
+	if password_matches {
+		if user.failed_login_attempts > 0 || user.locked_until.is_some() {
+			user.failed_login_attempts = 0;
+			user.locked_until = None;
+			user_store.update_user(user.clone()).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+		}
+		return Ok(user);
+	}
+
+	// Counting starts over once an earlier lockout has run out
+	if user.locked_until.take().is_some() {
+		user.failed_login_attempts = 0;
+	}
+	user.failed_login_attempts += 1;
+	let policy = &state.login_lockout_policy;
+	let locked = user.failed_login_attempts >= policy.max_failed_attempts;
+	if locked {
+		user.locked_until = Some(Utc::now() + policy.lockout);
+	}
+	user_store.update_user(user).await.map_err(|_| AuthAPIError::UnexpectedError)?;
+	drop(user_store);
+
+	record_event(state, failed("wrong password")).await;
+	if locked {
+		record_event(state, AuditEvent::new(AuditEventKind::AccountLocked, email.clone(), ip)).await;
+	}
+
+	Err(AuthAPIError::IncorrectPassword)
+}
+
+// Every way of logging in, and of using a token, refuses accounts that aren't active
+pub fn ensure_active(user: &User) -> Result<(), AuthAPIError> {
+	match user.status {
+		AccountStatus::Active => Ok(()),
+		AccountStatus::Disabled => Err(AuthAPIError::AccountDisabled),
+		AccountStatus::PendingVerification => Err(AuthAPIError::AccountPendingVerification),
+	}
+}
+
+// `ensure_active` for the user a token was issued to. Tokens of users that no longer exist are invalid.
+async fn ensure_active_subject(state: &AppState, claims: &Claims) -> Result<(), AuthAPIError> {
+	let user = state.user_store
+		.read().await
+		.get_user_str(&claims.sub).await
+		.map_err(|e| match e {
+			UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+			_ => AuthAPIError::UnexpectedError,
+		})?;
+
+	ensure_active(&user)
+}
+
+// Check if JWT auth token is valid by decoding it using the JWT secret,
+// and that the session it belongs to has not been revoked
+pub async fn validate_token(token: &str, state: &AppState) -> Result<Claims, jsonwebtoken::errors::Error> {
+	let result = check_token(token, state).await;
 
-	let claims = Claims { sub, exp };
+	let outcome = if result.is_ok() { "valid" } else { "invalid" };
+	state.metrics.token_validations.with_label_values(&[outcome]).inc();
 
-	create_token(&claims).map_err(GenerateTokenError::TokenError)
+	result
 }
 
-// Check if JWT auth token is valid by decoding it using the JWT secret
-pub async fn validate_token(token: &str, banned_token_store: &BannedTokenStoreType) -> Result<Claims, jsonwebtoken::errors::Error> {
-	banned_token_store.read().await.check(token).await.map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?;
-	decode::<Claims>(
+// How stale a session's last use may get before it's recorded again
+pub const SESSION_TOUCH_INTERVAL_SECONDS: i64 = 60;
+
+async fn check_token(token: &str, state: &AppState) -> Result<Claims, jsonwebtoken::errors::Error> {
+	state.banned_token_store.read().await.check(token).await.map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+	let claims = decode::<Claims>(
 		token,
-		&DecodingKey::from_secret(JWT_SECRET.as_bytes()),
+		&DecodingKey::from_secret(state.config.auth.jwt_secret.as_bytes()),
 		&Validation::default(),
 	)
-		.map(|data| data.claims)
+		.map(|data| data.claims)?;
+
+	let session_id = SessionId::parse(claims.sid.clone()).map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+	let session = state.session_store.read().await
+		.get_session(&session_id).await
+		.map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+	if session.email.as_ref() != claims.sub || session.is_expired(state.config.auth.session_idle_ttl_seconds) {
+		return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+	}
+	// Recording every request would have them all wait for the store's write lock in turn
+	if Utc::now() - session.last_seen_at >= chrono::Duration::seconds(SESSION_TOUCH_INTERVAL_SECONDS) {
+		state.session_store.write().await
+			.touch_session(&session_id).await
+			.map_err(|_| jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+	}
+
+	Ok(claims)
 }
 
 // Create JWT auth token by encoding claims using the JWT secret
-fn create_token(claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
+pub(crate) fn create_token<T: Serialize>(config: &Config, claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
 	encode(
 		&jsonwebtoken::Header::default(),
 		&claims,
-		&EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+		&EncodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
 	)
 }
 
+// Credentials can only be changed by the user, not by an administrator impersonating them
+pub fn ensure_not_impersonated(claims: &Claims) -> Result<(), AuthAPIError> {
+	if claims.act.is_some() {
+		return Err(AuthAPIError::ImpersonationNotAllowed);
+	}
+	Ok(())
+}
+
+// Extract the token from an `Authorization: Bearer` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+	headers.get(header::AUTHORIZATION)?
+		.to_str().ok()?
+		.strip_prefix("Bearer ")
+		.map(str::trim)
+		.filter(|token| !token.is_empty())
+}
+
+// Route extractor for the user a request is authenticated as. The token comes from an `Authorization: Bearer`
+// header if there is one, and from the auth cookie otherwise. A bearer token is never swapped for the cookie,
+// so a request with an invalid one is rejected even if its cookie is valid.
+// Only first-party tokens are accepted. Tokens issued to OAuth clients carry a scope, and would otherwise
+// let those clients manage the user's account.
+// Rejects requests without a token with 400, those with an invalid one with 401, and those of accounts that
+// aren't active with 403.
+pub struct AuthenticatedUser {
+	pub claims: Claims,
+	pub token: String,
+	pub source: TokenSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenSource {
+	Bearer,
+	Cookie,
+}
+
+impl AuthenticatedUser {
+	pub fn email(&self) -> Result<Email, AuthAPIError> {
+		self.claims.sub.parse().map_err(|_| AuthAPIError::InvalidToken)
+	}
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for AuthenticatedUser {
+	type Rejection = AuthAPIError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+		let jar = CookieJar::from_headers(&parts.headers);
+		let (token, source) = match bearer_token(&parts.headers) {
+			Some(token) => (token.to_owned(), TokenSource::Bearer),
+			None => {
+				let cookie = jar.get(&state.config.auth_cookie_name()).ok_or(AuthAPIError::MissingToken)?;
+				(cookie.value().to_owned(), TokenSource::Cookie)
+			}
+		};
+
+		let claims = validate_token(&token, state).await.map_err(|_| AuthAPIError::InvalidToken)?;
+		if claims.scope.is_some() {
+			return Err(AuthAPIError::InvalidToken);
+		}
+		ensure_active_subject(state, &claims).await?;
+
+		Ok(Self { claims, token, source })
+	}
+}
+
+// Route extractor for the claims of an OAuth access token, for the endpoints clients call on the user's behalf,
+// e.g. /userinfo. The token only comes from an `Authorization: Bearer` header, and its scope is for the route
+// to check. Rejects requests without a token with 400, those with an invalid one with 401, and those of
+// accounts that aren't active with 403.
+pub struct AccessToken {
+	pub claims: Claims,
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for AccessToken {
+	type Rejection = AuthAPIError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+		let token = bearer_token(&parts.headers).ok_or(AuthAPIError::MissingToken)?;
+		let claims = validate_token(token, state).await.map_err(|_| AuthAPIError::InvalidToken)?;
+		ensure_active_subject(state, &claims).await?;
+
+		Ok(Self { claims })
+	}
+}
+
+// Route extractor for the claims of a token that carries the permission `P`, e.g.
+// `Authorized<require::ManageUsers>`. The token is found like for `AuthenticatedUser`.
+pub struct Authorized<P> {
+	pub claims: Claims,
+	permission: PhantomData<P>,
+}
+
+pub trait RequiredPermission: Send + Sync {
+	const PERMISSION: Permission;
+}
+
+// Marker types naming the permission an `Authorized` extractor requires
+pub mod require {
+	use super::RequiredPermission;
+	use crate::domain::Permission;
+
+	pub struct ReadUsers;
+	pub struct ManageUsers;
+	pub struct ImpersonateUsers;
+	pub struct ReadAuditLog;
+
+	impl RequiredPermission for ReadUsers {
+		const PERMISSION: Permission = Permission::ReadUsers;
+	}
+
+	impl RequiredPermission for ManageUsers {
+		const PERMISSION: Permission = Permission::ManageUsers;
+	}
+
+	impl RequiredPermission for ImpersonateUsers {
+		const PERMISSION: Permission = Permission::ImpersonateUsers;
+	}
+
+	impl RequiredPermission for ReadAuditLog {
+		const PERMISSION: Permission = Permission::ReadAuditLog;
+	}
+}
+
+#[axum::async_trait]
+impl<P: RequiredPermission> FromRequestParts<AppState> for Authorized<P> {
+	type Rejection = AuthAPIError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+		let AuthenticatedUser { claims, .. } = AuthenticatedUser::from_request_parts(parts, state).await?;
+		if !claims.has_permission(P::PERMISSION) {
+			return Err(AuthAPIError::MissingPermission);
+		}
+
+		Ok(Self { claims, permission: PhantomData })
+	}
+}
+
+// Details about the client a session was started from, shown in the session list
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+	pub user_agent: Option<String>,
+	pub ip: Option<String>,
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		let user_agent = parts.headers
+			.get(header::USER_AGENT)
+			.and_then(|value| value.to_str().ok())
+			.map(str::to_owned);
+		let ip = parts.extensions
+			.get::<ConnectInfo<SocketAddr>>()
+			.map(|ConnectInfo(address)| address.ip().to_string());
+
+		Ok(Self { user_agent, ip })
+	}
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Claims {
 	pub sub: String,
 	pub exp: usize,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub scope: Option<String>,
+	// ID of the session the token was issued for
+	pub sid: String,
+	// Space-separated permission scopes granted by the user's roles, e.g. `users:read users:write`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub permissions: Option<String>,
+	// Set when an administrator acts as the user
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub act: Option<Actor>,
 }
 
-#[cfg(test)]
-mod tests {
-	use tokio::sync::RwLock;
+// The party acting on behalf of the token's subject, see RFC 8693 section 4.1
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Actor {
+	pub sub: String,
+}
 
-	use crate::HashsetBannedTokenStore;
+impl Claims {
+	// Only tokens issued to OAuth clients have scopes. Our own session tokens have none, so they
+	// don't pass for an access token.
+	pub fn has_scope(&self, wanted: &str) -> bool {
+		self.scope.as_deref().is_some_and(|scope| super::oidc::has_scope(scope, wanted))
+	}
+
+	pub fn has_permission(&self, permission: Permission) -> bool {
+		self.permissions.as_deref().is_some_and(|permissions| super::oidc::has_scope(permissions, permission.as_str()))
+	}
+}
 
+#[cfg(test)]
+mod tests {
 	use super::*;
 
-	use std::sync::Arc;
 	use std::str::FromStr as _;
 
+	use crate::utils::constants::JWT_COOKIE_NAME;
+	use crate::{CookiePrefix, CookieSameSite, SecureCookies};
+
 	#[tokio::test]
 	async fn test_generate_auth_cookie() {
 		let email = Email::from_str("test@example.com").unwrap();
-		let cookie = generate_auth_cookie(&email).unwrap();
+		let cookie = generate_auth_cookie(&Config::default(), &email, &SessionId::default(), &[]).unwrap();
 		assert_eq!(cookie.name(), JWT_COOKIE_NAME);
 		assert_eq!(cookie.value().split('.').count(), 3);
 		assert_eq!(cookie.path(), Some("/"));
@@ -108,28 +509,65 @@ mod tests {
 	#[tokio::test]
 	async fn test_create_auth_cookie() {
 		let token = "test_token".to_owned();
-		let cookie = create_auth_cookie(token.clone());
+		let cookie = create_auth_cookie(&Config::default(), token.clone());
 		assert_eq!(cookie.name(), JWT_COOKIE_NAME);
 		assert_eq!(cookie.value(), token);
 		assert_eq!(cookie.path(), Some("/"));
 		assert_eq!(cookie.http_only(), Some(true));
 		assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+		assert_eq!(cookie.secure(), Some(false));
+	}
+
+	#[tokio::test]
+	async fn test_create_auth_cookie_secure_with_tls() {
+		let mut config = Config::default();
+		config.server.tls = Some(crate::TlsConfig {
+			cert_path: "cert.pem".into(),
+			key_path: "key.pem".into(),
+			reload_interval_seconds: 60,
+		});
+		let cookie = create_auth_cookie(&config, "test_token".to_owned());
+		assert_eq!(cookie.secure(), Some(true));
+	}
+
+	#[tokio::test]
+	async fn test_create_auth_cookie_follows_cookie_policy() {
+		let mut config = Config::default();
+		config.cookies.prefix = CookiePrefix::Secure;
+		config.cookies.domain = Some("example.com".to_string());
+		config.cookies.same_site = CookieSameSite::Strict;
+		config.cookies.secure = SecureCookies::Always;
+
+		let cookie = create_auth_cookie(&config, "test_token".to_owned());
+		assert_eq!(cookie.name(), "__Secure-jwt");
+		assert_eq!(cookie.domain(), Some("example.com"));
+		assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+		assert_eq!(cookie.secure(), Some(true));
+		assert_eq!(cookie.max_age(), Some(time::Duration::seconds(config.auth.token_ttl_seconds)));
+
+		let removal = auth_cookie_removal(&config);
+		assert_eq!(removal.name(), "__Secure-jwt");
+		assert_eq!(removal.value(), "");
+		assert_eq!(removal.domain(), Some("example.com"));
+		assert_eq!(removal.path(), Some("/"));
+		assert_eq!(removal.secure(), Some(true));
+		assert_eq!(removal.max_age(), Some(time::Duration::ZERO));
 	}
 
 	#[tokio::test]
 	async fn test_generate_auth_token() {
 		let email = Email::from_str("test@example.com").unwrap();
-		let result = generate_auth_token(&email).unwrap();
+		let result = generate_auth_token(&Config::default(), &email, &SessionId::default(), &[]).unwrap();
 		assert_eq!(result.split('.').count(), 3);
 	}
 
 	#[tokio::test]
 	async fn test_validate_token_with_valid_token() {
-		let banned_token_store = HashsetBannedTokenStore::default();
-		let banned_token_store_box: BannedTokenStoreType = Arc::new(RwLock::new(Box::new(banned_token_store)));
+		let state = AppState::default();
 		let email = Email::from_str("test@example.com").unwrap();
-		let token = generate_auth_token(&email).unwrap();
-		let result = validate_token(&token, &banned_token_store_box).await.unwrap();
+		let session_id = start_session(&state, &email, ClientInfo::default()).await.unwrap();
+		let token = generate_auth_token(&state.config, &email, &session_id, &[]).unwrap();
+		let result = validate_token(&token, &state).await.unwrap();
 		assert_eq!(result.sub, "test@example.com");
 
 		let exp = Utc::now()
@@ -140,12 +578,99 @@ mod tests {
 		assert!(result.exp > exp as usize);
 	}
 
+	#[tokio::test]
+	async fn test_token_carries_permissions() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let session_id = start_session(&state, &email, ClientInfo::default()).await.unwrap();
+
+		let token = generate_auth_token(&state.config, &email, &session_id, &[Permission::ReadUsers]).unwrap();
+		let claims = validate_token(&token, &state).await.unwrap();
+		assert!(claims.has_permission(Permission::ReadUsers));
+		assert!(!claims.has_permission(Permission::ManageUsers));
+
+		let token = generate_auth_token(&state.config, &email, &session_id, &[]).unwrap();
+		let claims = validate_token(&token, &state).await.unwrap();
+		assert!(claims.permissions.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_only_scoped_tokens_have_scopes() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let session_id = start_session(&state, &email, ClientInfo::default()).await.unwrap();
+
+		let token = generate_scoped_auth_token(&state.config, &email, &session_id, Some("openid email"), &[]).unwrap();
+		let claims = validate_token(&token, &state).await.unwrap();
+		assert!(claims.has_scope("openid"));
+		assert!(!claims.has_scope("profile"));
+
+		let token = generate_auth_token(&state.config, &email, &session_id, &[]).unwrap();
+		let claims = validate_token(&token, &state).await.unwrap();
+		assert!(!claims.has_scope("openid"));
+	}
+
+	#[tokio::test]
+	async fn test_impersonation_token_names_actor() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let actor = Email::from_str("admin@example.com").unwrap();
+		let session_id = start_session(&state, &email, ClientInfo::default()).await.unwrap();
+
+		let token = generate_impersonation_token(&state.config, &email, &session_id, &actor).unwrap();
+		let claims = validate_token(&token, &state).await.unwrap();
+		assert_eq!(claims.sub, "test@example.com");
+		assert_eq!(claims.act.map(|act| act.sub).as_deref(), Some("admin@example.com"));
+		assert!(claims.permissions.is_none());
+	}
+
 	#[tokio::test]
 	async fn test_validate_token_with_invalid_token() {
 		let token = "invalid_token".to_owned();
-		let banned_token_store = HashsetBannedTokenStore::default();
-		let banned_token_store_box: BannedTokenStoreType = Arc::new(RwLock::new(Box::new(banned_token_store)));
-		let result = validate_token(&token, &banned_token_store_box).await;
+		let result = validate_token(&token, &AppState::default()).await;
 		assert!(result.is_err());
 	}
+
+	#[tokio::test]
+	async fn test_validate_token_with_revoked_session() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let session_id = start_session(&state, &email, ClientInfo::default()).await.unwrap();
+		let token = generate_auth_token(&state.config, &email, &session_id, &[]).unwrap();
+
+		state.session_store.write().await.remove_session(&session_id).await.unwrap();
+		assert!(validate_token(&token, &state).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_validate_token_with_idle_session() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let mut session = Session::new(email.clone(), None, None);
+		session.last_seen_at -= chrono::Duration::seconds(state.config.auth.session_idle_ttl_seconds + 1);
+		let session_id = session.id.clone();
+		state.session_store.write().await.add_session(session).await.unwrap();
+
+		let token = generate_auth_token(&state.config, &email, &session_id, &[]).unwrap();
+		assert!(validate_token(&token, &state).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_validate_token_records_session_use() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let mut session = Session::new(email.clone(), None, None);
+		session.last_seen_at -= chrono::Duration::seconds(SESSION_TOUCH_INTERVAL_SECONDS);
+		let session_id = session.id.clone();
+		state.session_store.write().await.add_session(session.clone()).await.unwrap();
+		let token = generate_auth_token(&state.config, &email, &session_id, &[]).unwrap();
+
+		validate_token(&token, &state).await.unwrap();
+		let last_seen_at = state.session_store.read().await.get_session(&session_id).await.unwrap().last_seen_at;
+		assert!(last_seen_at > session.last_seen_at);
+
+		// Until the interval passed again, the use isn't recorded
+		validate_token(&token, &state).await.unwrap();
+		assert_eq!(state.session_store.read().await.get_session(&session_id).await.unwrap().last_seen_at, last_seen_at);
+	}
 }
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use crate::utils::shutdown::ShutdownHandle;
+use crate::AppState;
+
+// How often expired entries are dropped from the stores
+pub const PRUNING_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Keeps the banned list, sessions, trusted devices, passkey challenges, device authorizations and rate limits
+// from growing forever.
+// Returns once the app shuts down, never in the middle of a pass.
+pub async fn prune_expired(state: AppState, shutdown: ShutdownHandle) {
+	let mut interval = tokio::time::interval(PRUNING_INTERVAL);
+
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {},
+			_ = shutdown.wait() => return,
+		}
+
+		match state.banned_token_store.write().await.remove_expired().await {
+			Ok(removed) => tracing::debug!(removed, "pruned banned tokens"),
+			Err(e) => tracing::error!(error = ?e, "failed to prune banned tokens"),
+		}
+		match state.session_store.write().await.remove_expired(state.config.auth.session_idle_ttl_seconds).await {
+			Ok(removed) => tracing::debug!(removed, "pruned idle sessions"),
+			Err(e) => tracing::error!(error = ?e, "failed to prune idle sessions"),
+		}
+		match state.trusted_device_store.write().await.remove_expired().await {
+			Ok(removed) => tracing::debug!(removed, "pruned trusted devices"),
+			Err(e) => tracing::error!(error = ?e, "failed to prune trusted devices"),
+		}
+		match state.passkey_store.write().await.remove_expired_challenges().await {
+			Ok(removed) => tracing::debug!(removed, "pruned passkey challenges"),
+			Err(e) => tracing::error!(error = ?e, "failed to prune passkey challenges"),
+		}
+		match state.device_code_store.write().await.remove_expired().await {
+			Ok(removed) => tracing::debug!(removed, "pruned device authorizations"),
+			Err(e) => tracing::error!(error = ?e, "failed to prune device authorizations"),
+		}
+		let removed = state.device_authorization_limiter.remove_expired();
+		tracing::debug!(removed, "pruned device authorization rate limits");
+	}
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::domain::{ClientStore, ClientStoreError, OAuthClient};
+
+use super::crypto::{generate_secret, hash_secret};
+
+pub const REGISTER_CLIENT_USAGE: &str = "\
+Usage: auth-service register-client <client-id> --scope <scopes> [--redirect-uri <uri>]... [--confidential]
+
+  --scope          Space-separated scopes the client may ask for, e.g. \"openid email\"
+  --redirect-uri   Where users are sent back to after consenting, repeat for several
+  --confidential   Give the client a secret, which the client credentials grant requires.
+                   Public clients, e.g. single page apps, need a redirect URI instead.";
+
+// An OAuth client to register, as given to `auth-service register-client` by the operator
+#[derive(Debug, PartialEq)]
+pub struct ClientRegistration {
+	pub client_id: String,
+	pub redirect_uris: Vec<String>,
+	pub allowed_scopes: Vec<String>,
+	pub confidential: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClientRegistrationError {
+	InvalidArguments(String),
+	Store(ClientStoreError),
+}
+
+impl fmt::Display for ClientRegistrationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ClientRegistrationError::InvalidArguments(reason) => write!(f, "{reason}\n\n{REGISTER_CLIENT_USAGE}"),
+			ClientRegistrationError::Store(ClientStoreError::ClientAlreadyExists) => f.write_str("a client with this ID already exists"),
+			ClientRegistrationError::Store(e) => write!(f, "failed to store the client: {e:?}"),
+		}
+	}
+}
+
+impl ClientRegistration {
+	// The arguments after `register-client`
+	pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ClientRegistrationError> {
+		let invalid = |reason: &str| ClientRegistrationError::InvalidArguments(reason.to_string());
+
+		let mut client_id = None;
+		let mut redirect_uris = Vec::new();
+		let mut allowed_scopes = Vec::new();
+		let mut confidential = false;
+
+		let mut args = args.into_iter();
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--redirect-uri" => redirect_uris.push(args.next().ok_or_else(|| invalid("--redirect-uri needs a value"))?),
+				"--scope" => {
+					let scope = args.next().ok_or_else(|| invalid("--scope needs a value"))?;
+					allowed_scopes.extend(scope.split_whitespace().map(str::to_owned));
+				}
+				"--confidential" => confidential = true,
+				_ if arg.starts_with('-') => return Err(invalid(&format!("unknown option {arg}"))),
+				_ if client_id.is_none() => client_id = Some(arg),
+				_ => return Err(invalid(&format!("unexpected argument {arg}"))),
+			}
+		}
+
+		let client_id = client_id.ok_or_else(|| invalid("the client ID is missing"))?;
+		if !client_id.chars().all(|c| c.is_ascii_alphanumeric() || "-._".contains(c)) {
+			return Err(invalid("the client ID may only contain letters, digits, '-', '.' and '_'"));
+		}
+		if allowed_scopes.is_empty() {
+			return Err(invalid("at least one scope is required"));
+		}
+		if let Some(uri) = redirect_uris.iter().find(|uri| !is_redirect_uri(uri)) {
+			return Err(invalid(&format!("{uri} isn't an http(s) URL without a fragment")));
+		}
+		// Without a secret, the redirect URI is all that keeps others from posing as the client
+		if !confidential && redirect_uris.is_empty() {
+			return Err(invalid("public clients need a redirect URI"));
+		}
+
+		Ok(Self { client_id, redirect_uris, allowed_scopes, confidential })
+	}
+
+	// Adds the client to the store. Returns the secret of a confidential client, which isn't
+	// stored and can't be shown again.
+	pub async fn register(self, client_store: &mut (dyn ClientStore + Send + Sync)) -> Result<Option<String>, ClientRegistrationError> {
+		let secret = self.confidential.then(generate_secret);
+		let client = OAuthClient {
+			client_id: self.client_id,
+			secret_hash: secret.as_deref().map(hash_secret),
+			redirect_uris: self.redirect_uris,
+			allowed_scopes: self.allowed_scopes,
+		};
+
+		client_store.add_client(client).await.map_err(ClientRegistrationError::Store)?;
+
+		Ok(secret)
+	}
+}
+
+fn is_redirect_uri(value: &str) -> bool {
+	url::Url::parse(value).is_ok_and(|url| {
+		matches!(url.scheme(), "http" | "https") && url.host().is_some() && url.fragment().is_none()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::HashmapClientStore;
+
+	fn parse(args: &[&str]) -> Result<ClientRegistration, ClientRegistrationError> {
+		ClientRegistration::parse(args.iter().map(|arg| arg.to_string()))
+	}
+
+	#[test]
+	fn test_parse() {
+		let registration = parse(&["app", "--scope", "openid email", "--redirect-uri", "https://app.example/callback", "--scope", "profile"]).unwrap();
+		assert_eq!(registration, ClientRegistration {
+			client_id: "app".to_string(),
+			redirect_uris: vec!["https://app.example/callback".to_string()],
+			allowed_scopes: vec!["openid".to_string(), "email".to_string(), "profile".to_string()],
+			confidential: false,
+		});
+
+		assert!(parse(&["reports", "--scope", "reports:read", "--confidential"]).unwrap().confidential);
+	}
+
+	#[test]
+	fn test_parse_rejects_invalid_arguments() {
+		assert!(parse(&["--scope", "openid"]).is_err());
+		assert!(parse(&["app", "--redirect-uri", "https://app.example/callback"]).is_err());
+		assert!(parse(&["app", "--scope", "openid"]).is_err());
+		assert!(parse(&["app", "--scope", "openid", "--redirect-uri", "javascript:alert(1)"]).is_err());
+		assert!(parse(&["app", "--scope", "openid", "--redirect-uri", "https://app.example/#token"]).is_err());
+		assert!(parse(&["app", "other", "--scope", "openid", "--confidential"]).is_err());
+		assert!(parse(&["app id", "--scope", "openid", "--confidential"]).is_err());
+		assert!(parse(&["app", "--scope", "openid", "--confidential", "--secret"]).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_register() {
+		let mut store = HashmapClientStore::default();
+
+		let secret = parse(&["reports", "--scope", "reports:read", "--confidential"]).unwrap()
+			.register(&mut store).await
+			.unwrap()
+			.expect("Missing secret");
+		assert!(store.get_client("reports").await.unwrap().verify_secret(&secret));
+
+		let secret = parse(&["app", "--scope", "openid", "--redirect-uri", "https://app.example/callback"]).unwrap()
+			.register(&mut store).await
+			.unwrap();
+		assert!(secret.is_none());
+		assert!(!store.get_client("app").await.unwrap().is_confidential());
+
+		let result = parse(&["app", "--scope", "openid", "--confidential"]).unwrap().register(&mut store).await;
+		assert_eq!(result, Err(ClientRegistrationError::Store(ClientStoreError::ClientAlreadyExists)));
+	}
+}
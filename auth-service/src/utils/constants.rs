@@ -1,29 +1,33 @@
-use dotenvy::dotenv;
-use lazy_static::lazy_static;
-use std::env as std_env;
-
-// Define a lazily evaluated static. lazy_static is needed because std_env::var is not a const function.
-lazy_static! {
-	pub static ref JWT_SECRET: String = set_token();
-}
-
-fn set_token() -> String {
-	dotenv().ok(); // Load environment variables
-	let secret = std_env::var(env::JWT_SECRET_ENV_VAR).expect("JWT_SECRET must be set.");
-	if secret.is_empty() {
-		panic!("JWT_SECRET must not be empty.");
-	}
-	secret
-}
-
 pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const TRUSTED_DEVICE_COOKIE_NAME: &str = "trusted_device";
+pub const CSRF_COOKIE_NAME: &str = "csrf_token";
+// Where scripts echo the CSRF cookie's value back
+pub const CSRF_HEADER_NAME: &str = "x-csrf-token";
 
+// Environment variables overriding the config file, and the few settings that only come from the environment
 pub mod env {
+	pub const CONFIG_PATH_ENV_VAR: &str = "CONFIG_PATH";
+	pub const APP_ADDRESS_ENV_VAR: &str = "APP_ADDRESS";
+	pub const ALLOWED_ORIGINS_ENV_VAR: &str = "ALLOWED_ORIGINS";
+	pub const DRAIN_TIMEOUT_SECONDS_ENV_VAR: &str = "DRAIN_TIMEOUT_SECONDS";
+	pub const TLS_CERT_PATH_ENV_VAR: &str = "TLS_CERT_PATH";
+	pub const TLS_KEY_PATH_ENV_VAR: &str = "TLS_KEY_PATH";
+	pub const COOKIE_PREFIX_ENV_VAR: &str = "COOKIE_PREFIX";
+	pub const COOKIE_DOMAIN_ENV_VAR: &str = "COOKIE_DOMAIN";
+	pub const COOKIE_SAME_SITE_ENV_VAR: &str = "COOKIE_SAME_SITE";
+	pub const SECURE_COOKIES_ENV_VAR: &str = "SECURE_COOKIES";
 	pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
-}
-
-pub mod prod {
-    pub const APP_ADDRESS: &str = "0.0.0.0:3000";
+	pub const OIDC_ISSUER_ENV_VAR: &str = "OIDC_ISSUER";
+	pub const TOKEN_TTL_SECONDS_ENV_VAR: &str = "TOKEN_TTL_SECONDS";
+	pub const SESSION_IDLE_TTL_SECONDS_ENV_VAR: &str = "SESSION_IDLE_TTL_SECONDS";
+	pub const TRUSTED_DEVICE_TTL_DAYS_ENV_VAR: &str = "TRUSTED_DEVICE_TTL_DAYS";
+	pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
+	pub const SMS_GATEWAY_URL_ENV_VAR: &str = "SMS_GATEWAY_URL";
+	pub const SMS_GATEWAY_API_KEY_ENV_VAR: &str = "SMS_GATEWAY_API_KEY";
+	pub const ADMIN_EMAILS_ENV_VAR: &str = "ADMIN_EMAILS";
+	pub const ID_TOKEN_KEY_PATH_ENV_VAR: &str = "ID_TOKEN_KEY_PATH";
+	pub const AUDIT_LOG_PATH_ENV_VAR: &str = "AUDIT_LOG_PATH";
+	pub const LOG_FORMAT_ENV_VAR: &str = "LOG_FORMAT";
 }
 
 pub mod test {
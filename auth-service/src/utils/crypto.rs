@@ -0,0 +1,38 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use rand::{distr::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+// Generate a random high-entropy secret, e.g. for OAuth clients
+pub fn generate_secret() -> String {
+	let mut rng = rand::rng();
+	(&mut rng).sample_iter(&Alphanumeric).take(40).map(char::from).collect()
+}
+
+// Secrets we generate ourselves have enough entropy that a plain SHA-256 is enough to store them
+pub fn hash_secret(secret: &str) -> String {
+	URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+pub fn verify_secret(secret: &str, hash: &str) -> bool {
+	constant_time_eq(&hash_secret(secret), hash)
+}
+
+// Compare every byte so the time taken doesn't leak how much of a secret matched
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+	a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_verify_secret() {
+		let secret = generate_secret();
+		let hash = hash_secret(&secret);
+		assert_ne!(secret, hash);
+		assert!(verify_secret(&secret, &hash));
+		assert!(!verify_secret("wrong", &hash));
+	}
+}
@@ -0,0 +1,157 @@
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::cookie::{Cookie, CookieJar};
+use url::Url;
+
+use crate::domain::AuthAPIError;
+use crate::utils::auth::{bearer_token, session_cookie};
+use crate::utils::constants::CSRF_HEADER_NAME;
+use crate::utils::crypto::{constant_time_eq, generate_secret};
+use crate::{AppState, Config};
+
+// Routes that don't act on the account in the auth cookie, so a forged request can't do anything
+// the attacker couldn't do themselves. Logging in and signing up come before there is a CSRF token.
+const EXEMPT_PATHS: &[&str] = &[
+	"/signup",
+	"/login",
+	"/login/magic-link",
+	"/verify-2fa",
+	"/resend-2fa",
+	"/verify-token",
+	"/oauth/token",
+	"/oauth/device_authorization",
+	"/userinfo",
+	"/passkeys/login/start",
+	"/passkeys/login/finish",
+];
+
+// Middleware protecting cookie-authenticated requests that change state against cross-site request forgery.
+// They have to come from an allowed origin, and repeat the CSRF cookie's value in the `X-CSRF-Token` header,
+// which only scripts running on an allowed origin can read (double-submit cookie).
+// The CSRF cookie is handed out, and cleared, along with the auth cookie.
+pub async fn protect(State(state): State<AppState>, request: Request, next: Next) -> Response {
+	if let Err(e) = check_request(&state.config, &request) {
+		return e.into_response();
+	}
+
+	let mut response = next.run(request).await;
+	issue_token(&state.config, response.headers_mut());
+
+	response
+}
+
+fn check_request(config: &Config, request: &Request) -> Result<(), AuthAPIError> {
+	let headers = request.headers();
+	if is_safe(request.method()) || EXEMPT_PATHS.contains(&request.uri().path()) {
+		return Ok(());
+	}
+	// Browsers don't attach an `Authorization` header to cross-site requests without CORS allowing it,
+	// and without a cookie there's no ambient authority to abuse
+	let jar = CookieJar::from_headers(headers);
+	if bearer_token(headers).is_some() || jar.get(&config.auth_cookie_name()).is_none() {
+		return Ok(());
+	}
+
+	if !has_allowed_origin(config, headers) {
+		return Err(AuthAPIError::CrossSiteRequest);
+	}
+
+	let cookie = jar.get(&config.csrf_cookie_name()).ok_or(AuthAPIError::CrossSiteRequest)?;
+	let header = headers
+		.get(CSRF_HEADER_NAME)
+		.and_then(|header| header.to_str().ok())
+		.ok_or(AuthAPIError::CrossSiteRequest)?;
+	if cookie.value().is_empty() || !constant_time_eq(cookie.value(), header) {
+		return Err(AuthAPIError::CrossSiteRequest);
+	}
+
+	Ok(())
+}
+
+fn is_safe(method: &Method) -> bool {
+	matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+// Browsers send `Origin` with state-changing requests, older ones at least a `Referer`. Requests with
+// neither don't come from a browser, or the user opted out of sending them, and rely on the token alone.
+fn has_allowed_origin(config: &Config, headers: &HeaderMap) -> bool {
+	let origin = match (headers.get(header::ORIGIN), headers.get(header::REFERER)) {
+		(Some(origin), _) => origin.to_str().ok().map(str::to_owned),
+		(None, Some(referer)) => referer.to_str().ok()
+			.and_then(|referer| Url::parse(referer).ok())
+			.map(|referer| referer.origin().ascii_serialization()),
+		(None, None) => return true,
+	};
+	let Some(origin) = origin else {
+		return false;
+	};
+
+	let own_origin = Url::parse(&config.auth.issuer).map(|issuer| issuer.origin().ascii_serialization());
+	own_origin.is_ok_and(|own_origin| own_origin == origin) || config.server.allowed_origins.contains(&origin)
+}
+
+// A fresh token whenever the auth cookie is set, so every session gets its own. Cleared along with the auth cookie.
+fn issue_token(config: &Config, headers: &mut HeaderMap) {
+	let auth_cookie_name = config.auth_cookie_name();
+	let auth_cookie = headers
+		.get_all(header::SET_COOKIE)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.filter_map(|value| Cookie::parse(value).ok())
+		.find(|cookie| cookie.name() == auth_cookie_name);
+	let Some(auth_cookie) = auth_cookie else {
+		return;
+	};
+
+	// Readable by scripts, unlike the auth cookie, as they have to send it back in the header
+	let mut cookie = session_cookie(config, config.csrf_cookie_name(), generate_secret());
+	if auth_cookie.value().is_empty() {
+		cookie.make_removal();
+	}
+	if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+		headers.append(header::SET_COOKIE, value);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request(method: Method, path: &str, headers: &[(&str, &str)]) -> Request {
+		let mut request = Request::builder().method(method).uri(path);
+		for (name, value) in headers {
+			request = request.header(*name, *value);
+		}
+		request.body(axum::body::Body::empty()).unwrap()
+	}
+
+	#[test]
+	fn test_checks_cookie_authenticated_requests_only() {
+		let config = Config::default();
+		let cookies = "jwt=token; csrf_token=secret";
+
+		assert!(check_request(&config, &request(Method::POST, "/logout", &[("cookie", cookies), ("x-csrf-token", "secret")])).is_ok());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &[("cookie", cookies), ("x-csrf-token", "wrong")])).is_err());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &[("cookie", cookies)])).is_err());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &[("cookie", "jwt=token"), ("x-csrf-token", "")])).is_err());
+
+		assert!(check_request(&config, &request(Method::GET, "/sessions", &[("cookie", cookies)])).is_ok());
+		assert!(check_request(&config, &request(Method::POST, "/login", &[("cookie", cookies)])).is_ok());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &[])).is_ok());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &[("cookie", cookies), ("authorization", "Bearer token")])).is_ok());
+	}
+
+	#[test]
+	fn test_checks_origin() {
+		let config = Config::default();
+		let headers = |origin: (&'static str, &'static str)| [("cookie", "jwt=token; csrf_token=secret"), ("x-csrf-token", "secret"), origin];
+
+		assert!(check_request(&config, &request(Method::POST, "/logout", &headers(("origin", "http://localhost:3000")))).is_ok());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &headers(("origin", "https://evil.example")))).is_err());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &headers(("origin", "null")))).is_err());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &headers(("referer", "http://127.0.0.1:3000/?next=/")))).is_ok());
+		assert!(check_request(&config, &request(Method::POST, "/logout", &headers(("referer", "https://evil.example/page")))).is_err());
+	}
+}
@@ -0,0 +1,66 @@
+use chrono::Utc;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::Email;
+use crate::Config;
+
+use super::auth::{create_token, GenerateTokenError};
+
+// How long a magic login link stays usable
+pub const MAGIC_LINK_TTL_SECONDS: i64 = 900; // 15 minutes
+
+// Keeps magic link tokens from being accepted as auth tokens and vice versa
+const MAGIC_LINK_AUDIENCE: &str = "magic-link";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MagicLinkClaims {
+	pub sub: String,
+	pub aud: String,
+	pub exp: usize,
+	// Random ID, so two links requested within the same second are still different tokens
+	pub jti: String,
+}
+
+pub fn generate_magic_link_token(config: &Config, email: &Email) -> Result<String, GenerateTokenError> {
+	let exp = (Utc::now() + chrono::Duration::seconds(MAGIC_LINK_TTL_SECONDS))
+		.timestamp()
+		.try_into()
+		.map_err(|_| GenerateTokenError::UnexpectedError)?;
+
+	let claims = MagicLinkClaims {
+		sub: email.as_ref().to_owned(),
+		aud: MAGIC_LINK_AUDIENCE.to_owned(),
+		exp,
+		jti: uuid::Uuid::new_v4().to_string(),
+	};
+
+	create_token(config, &claims).map_err(GenerateTokenError::TokenError)
+}
+
+pub fn decode_magic_link_token(config: &Config, token: &str) -> Result<MagicLinkClaims, jsonwebtoken::errors::Error> {
+	let mut validation = Validation::default();
+	validation.set_audience(&[MAGIC_LINK_AUDIENCE]);
+
+	decode::<MagicLinkClaims>(token, &DecodingKey::from_secret(config.auth.jwt_secret.as_bytes()), &validation)
+		.map(|data| data.claims)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr as _;
+
+	use super::*;
+	use crate::utils::auth::Claims;
+
+	#[test]
+	fn test_magic_link_token_is_not_an_auth_token() {
+		let config = Config::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let token = generate_magic_link_token(&config, &email).unwrap();
+
+		assert_eq!(decode_magic_link_token(&config, &token).unwrap().sub, "test@example.com");
+		let key = DecodingKey::from_secret(config.auth.jwt_secret.as_bytes());
+		assert!(decode::<Claims>(&token, &key, &Validation::default()).is_err());
+	}
+}
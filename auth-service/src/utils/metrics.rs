@@ -0,0 +1,126 @@
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+	Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use crate::AppState;
+
+// Prometheus metrics of one app instance. Every instance has its own registry, so tests
+// running several apps side by side don't see each other's numbers.
+#[derive(Clone)]
+pub struct Metrics {
+	registry: Registry,
+	pub signups: IntCounter,
+	// Labelled with `outcome`: `success`, `2fa_required` or `failure`
+	pub logins: IntCounterVec,
+	// Labelled with `outcome`: `success` or `failure`
+	pub two_fa_verifications: IntCounterVec,
+	// Labelled with `outcome`: `valid` or `invalid`
+	pub token_validations: IntCounterVec,
+	pub tokens_banned: IntCounter,
+	// Labelled with `method`, the route's `path` pattern and response `status`
+	pub request_duration: HistogramVec,
+	pub password_check_duration: Histogram,
+	pub users: IntGauge,
+	pub banned_tokens: IntGauge,
+	pub pending_two_fa_codes: IntGauge,
+}
+
+impl Metrics {
+	pub fn new() -> Self {
+		let registry = Registry::new_custom(Some("auth".to_string()), None).expect("Invalid metrics prefix");
+
+		let metrics = Self {
+			signups: IntCounter::new("signups_total", "Accounts created").unwrap(),
+			logins: IntCounterVec::new(Opts::new("logins_total", "Password logins by outcome"), &["outcome"]).unwrap(),
+			two_fa_verifications: IntCounterVec::new(
+				Opts::new("two_fa_verifications_total", "Second factor checks of 2FA logins by outcome"),
+				&["outcome"],
+			).unwrap(),
+			token_validations: IntCounterVec::new(
+				Opts::new("token_validations_total", "Auth token validations by outcome"),
+				&["outcome"],
+			).unwrap(),
+			tokens_banned: IntCounter::new("tokens_banned_total", "Tokens added to the banned list").unwrap(),
+			request_duration: HistogramVec::new(
+				HistogramOpts::new("http_request_duration_seconds", "Time taken to handle a request"),
+				&["method", "path", "status"],
+			).unwrap(),
+			// Checking a password is where the hashing happens, so this is mostly hashing time
+			password_check_duration: Histogram::with_opts(HistogramOpts::new(
+				"password_check_duration_seconds",
+				"Time taken to check a password against the user store",
+			)).unwrap(),
+			users: IntGauge::new("users", "Accounts in the user store").unwrap(),
+			banned_tokens: IntGauge::new("banned_tokens", "Tokens in the banned token store").unwrap(),
+			pending_two_fa_codes: IntGauge::new("pending_two_fa_codes", "2FA logins waiting for their code").unwrap(),
+			registry,
+		};
+
+		metrics.registry.register(Box::new(metrics.signups.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.logins.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.two_fa_verifications.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.token_validations.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.tokens_banned.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.request_duration.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.password_check_duration.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.users.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.banned_tokens.clone())).unwrap();
+		metrics.registry.register(Box::new(metrics.pending_two_fa_codes.clone())).unwrap();
+
+		metrics
+	}
+
+	// Everything in the Prometheus text exposition format
+	pub fn render(&self) -> Result<String, prometheus::Error> {
+		let mut buffer = Vec::new();
+		TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+		String::from_utf8(buffer).map_err(|e| prometheus::Error::Msg(e.to_string()))
+	}
+}
+
+impl Default for Metrics {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// Middleware timing every routed request
+pub async fn track_request_duration(State(state): State<AppState>, request: Request, next: Next) -> Response {
+	let method = request.method().to_string();
+	// The route pattern rather than the actual path, so emails and IDs don't each get their own series
+	let path = request
+		.extensions()
+		.get::<MatchedPath>()
+		.map(|path| path.as_str().to_owned())
+		.unwrap_or_default();
+
+	let started_at = Instant::now();
+	let response = next.run(request).await;
+
+	state.metrics.request_duration
+		.with_label_values(&[method.as_str(), path.as_str(), response.status().as_str()])
+		.observe(started_at.elapsed().as_secs_f64());
+
+	response
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_render_prefixes_metrics() {
+		let metrics = Metrics::new();
+		metrics.signups.inc();
+		metrics.logins.with_label_values(&["failure"]).inc();
+
+		let rendered = metrics.render().unwrap();
+		assert!(rendered.contains("auth_signups_total 1"));
+		assert!(rendered.contains(r#"auth_logins_total{outcome="failure"} 1"#));
+	}
+}
@@ -1,2 +1,17 @@
+pub mod audit;
 pub mod auth;
+pub mod background;
+pub mod client_registration;
 pub mod constants;
+pub mod crypto;
+pub mod csrf;
+pub mod oidc;
+pub mod rate_limit;
+pub mod shutdown;
+pub mod telemetry;
+pub mod tls;
+pub mod trusted_device;
+pub mod two_fa;
+pub mod magic_link;
+pub mod metrics;
+pub mod webauthn;
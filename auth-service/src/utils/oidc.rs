@@ -0,0 +1,240 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use jsonwebtoken::jwk::{Jwk, JwkSet, ThumbprintHash};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::domain::Email;
+use crate::Config;
+
+use super::auth::{create_token, GenerateTokenError};
+
+// How long an authorization code can be redeemed for after the user consents
+pub const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60;
+
+// How long the user has to enter a device flow user code, and how often the device may poll
+pub const DEVICE_CODE_TTL_SECONDS: i64 = 600;
+pub const DEVICE_CODE_POLL_INTERVAL_SECONDS: i64 = 5;
+
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+	pub iss: String,
+	pub sub: String,
+	pub aud: String,
+	pub exp: usize,
+	pub iat: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub nonce: Option<String>,
+	pub email: String,
+}
+
+// Access token issued to a client for itself through the client credentials grant.
+// It deliberately has no `sub`, so it can never pass as a user's auth token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceTokenClaims {
+	pub iss: String,
+	pub client_id: String,
+	pub scope: String,
+	pub exp: usize,
+	pub iat: usize,
+}
+
+// Key pair signing ID tokens. Relying parties check them against the public half published at the
+// JWKS endpoint, so they never hold a secret that could also sign our own auth tokens.
+pub struct IdTokenKey {
+	encoding_key: EncodingKey,
+	jwk: Jwk,
+}
+
+impl IdTokenKey {
+	// A fresh P-256 key, for tests and services that weren't given one. ID tokens it signed can't be
+	// checked anymore once the service restarts.
+	pub fn generate() -> Self {
+		loop {
+			// Almost every 32 byte string is a valid scalar, the rest is retried
+			if let Ok(secret) = p256::SecretKey::from_slice(&rand::random::<[u8; 32]>()) {
+				return Self::from_secret(&secret).expect("Generated key can't be encoded");
+			}
+		}
+	}
+
+	// P-256 private key in PKCS#8 PEM, e.g. made with `openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256`
+	pub fn from_pem(pem: &str) -> Result<Self, String> {
+		let secret = p256::SecretKey::from_pkcs8_pem(pem).map_err(|e| e.to_string())?;
+		Self::from_secret(&secret)
+	}
+
+	fn from_secret(secret: &p256::SecretKey) -> Result<Self, String> {
+		let der = secret.to_pkcs8_der().map_err(|e| e.to_string())?;
+		let encoding_key = EncodingKey::from_ec_der(der.as_bytes());
+		let mut jwk = Jwk::from_encoding_key(&encoding_key, Algorithm::ES256).map_err(|e| e.to_string())?;
+		jwk.common.key_id = Some(jwk.thumbprint(ThumbprintHash::SHA256));
+
+		Ok(Self { encoding_key, jwk })
+	}
+
+	// The public key, as served at the `jwks_uri` of the discovery document
+	pub fn jwks(&self) -> JwkSet {
+		JwkSet { keys: vec![self.jwk.clone()] }
+	}
+
+	fn sign<T: Serialize>(&self, claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+		let mut header = Header::new(Algorithm::ES256);
+		header.kid = self.jwk.common.key_id.clone();
+		encode(&header, claims, &self.encoding_key)
+	}
+}
+
+// Returns the (iat, exp) pair for a token issued now
+fn issued_at_and_expiry(config: &Config) -> Result<(usize, usize), GenerateTokenError> {
+	let now = Utc::now();
+	let delta = chrono::Duration::try_seconds(config.auth.token_ttl_seconds)
+		.ok_or(GenerateTokenError::UnexpectedError)?;
+	let exp = now
+		.checked_add_signed(delta)
+		.ok_or(GenerateTokenError::UnexpectedError)?
+		.timestamp()
+		.try_into()
+		.map_err(|_| GenerateTokenError::UnexpectedError)?;
+	let iat = now.timestamp()
+		.try_into()
+		.map_err(|_| GenerateTokenError::UnexpectedError)?;
+
+	Ok((iat, exp))
+}
+
+pub fn generate_service_token(config: &Config, client_id: &str, scope: &str) -> Result<String, GenerateTokenError> {
+	let (iat, exp) = issued_at_and_expiry(config)?;
+
+	let claims = ServiceTokenClaims {
+		iss: config.auth.issuer.clone(),
+		client_id: client_id.to_owned(),
+		scope: scope.to_owned(),
+		exp,
+		iat,
+	};
+
+	create_token(config, &claims).map_err(GenerateTokenError::TokenError)
+}
+
+// Create an OpenID Connect ID token for `client_id`, signed with the ID token key
+pub fn generate_id_token(
+	config: &Config,
+	key: &IdTokenKey,
+	email: &Email,
+	client_id: &str,
+	nonce: Option<&str>,
+) -> Result<String, GenerateTokenError> {
+	let (iat, exp) = issued_at_and_expiry(config)?;
+
+	let claims = IdTokenClaims {
+		iss: config.auth.issuer.clone(),
+		sub: email.as_ref().to_owned(),
+		aud: client_id.to_owned(),
+		exp,
+		iat,
+		nonce: nonce.map(str::to_owned),
+		email: email.as_ref().to_owned(),
+	};
+
+	key.sign(&claims).map_err(GenerateTokenError::TokenError)
+}
+
+// Check a PKCE code verifier against the S256 challenge sent to the authorization endpoint (RFC 7636)
+pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool {
+	let valid_verifier = (43..=128).contains(&code_verifier.len())
+		&& code_verifier.chars().all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c));
+	if !valid_verifier {
+		return false;
+	}
+
+	let digest = Sha256::digest(code_verifier.as_bytes());
+	URL_SAFE_NO_PAD.encode(digest) == code_challenge
+}
+
+// Check whether a space-delimited scope string contains `wanted`
+pub fn has_scope(scope: &str, wanted: &str) -> bool {
+	scope.split_whitespace().any(|s| s == wanted)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr as _;
+
+	use jsonwebtoken::{decode, DecodingKey, Validation};
+
+	use super::*;
+
+	// Example values from RFC 7636 appendix B
+	const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+	const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+	#[test]
+	fn test_verify_pkce() {
+		assert!(verify_pkce(VERIFIER, CHALLENGE));
+		assert!(!verify_pkce(VERIFIER, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cN"));
+		assert!(!verify_pkce("short", CHALLENGE));
+	}
+
+	#[test]
+	fn test_has_scope() {
+		assert!(has_scope("openid email", "email"));
+		assert!(!has_scope("openid emails", "email"));
+	}
+
+	#[test]
+	fn test_generate_id_token() {
+		let config = Config::default();
+		let key = IdTokenKey::generate();
+		let email = Email::from_str("test@example.com").unwrap();
+		let token = generate_id_token(&config, &key, &email, "client", Some("nonce")).unwrap();
+
+		// Verifiable with nothing but the published key
+		let jwks = key.jwks();
+		let kid = jsonwebtoken::decode_header(&token).unwrap().kid.unwrap();
+		let jwk = jwks.find(&kid).unwrap();
+		let mut validation = Validation::new(Algorithm::ES256);
+		validation.set_audience(&["client"]);
+		validation.set_issuer(&[config.auth.issuer.as_str()]);
+		let claims = decode::<IdTokenClaims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation)
+			.unwrap()
+			.claims;
+
+		assert_eq!(claims.sub, "test@example.com");
+		assert_eq!(claims.nonce.as_deref(), Some("nonce"));
+
+		// And not with the secret signing auth tokens
+		let secret = DecodingKey::from_secret(config.auth.jwt_secret.as_bytes());
+		assert!(decode::<IdTokenClaims>(&token, &secret, &validation).is_err());
+	}
+
+	#[test]
+	fn test_id_token_key_from_pem() {
+		let pem = p256::SecretKey::from_slice(&[7; 32]).unwrap().to_pkcs8_pem(Default::default()).unwrap();
+		let key = IdTokenKey::from_pem(&pem).unwrap();
+		assert_eq!(key.jwks().keys.len(), 1);
+
+		assert!(IdTokenKey::from_pem("not a key").is_err());
+	}
+
+	#[test]
+	fn test_generate_service_token() {
+		let config = Config::default();
+		let token = generate_service_token(&config, "client", "reports:read").unwrap();
+		let key = DecodingKey::from_secret(config.auth.jwt_secret.as_bytes());
+
+		let claims = decode::<ServiceTokenClaims>(&token, &key, &Validation::default())
+			.unwrap()
+			.claims;
+		assert_eq!(claims.client_id, "client");
+		assert_eq!(claims.scope, "reports:read");
+
+		// Without a subject the token must not be usable as a user's auth token
+		assert!(decode::<crate::utils::auth::Claims>(&token, &key, &Validation::default()).is_err());
+	}
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Allows each key, e.g. a client's IP address, `limit` hits per `window`. Windows start with the
+// first hit, and keys whose window is over are dropped by `remove_expired`.
+#[derive(Clone)]
+pub struct RateLimiter {
+	limit: u32,
+	window: Duration,
+	hits: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+struct Window {
+	started_at: Instant,
+	hits: u32,
+}
+
+impl RateLimiter {
+	pub fn new(limit: u32, window: Duration) -> Self {
+		Self { limit, window, hits: Arc::default() }
+	}
+
+	// Counts a hit, and whether it's still within the limit
+	pub fn check(&self, key: &str) -> bool {
+		let now = Instant::now();
+		let mut hits = self.hits.lock().expect("Rate limiter lock poisoned");
+		let window = hits.entry(key.to_owned()).or_insert(Window { started_at: now, hits: 0 });
+		if now.duration_since(window.started_at) >= self.window {
+			*window = Window { started_at: now, hits: 0 };
+		}
+
+		window.hits += 1;
+		window.hits <= self.limit
+	}
+
+	pub fn remove_expired(&self) -> usize {
+		let mut hits = self.hits.lock().expect("Rate limiter lock poisoned");
+		let before = hits.len();
+		hits.retain(|_, window| window.started_at.elapsed() < self.window);
+		before - hits.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_limits_each_key() {
+		let limiter = RateLimiter::new(2, Duration::from_secs(60));
+
+		assert!(limiter.check("a"));
+		assert!(limiter.check("a"));
+		assert!(!limiter.check("a"));
+		assert!(limiter.check("b"));
+	}
+
+	#[test]
+	fn test_starts_over_after_the_window() {
+		let limiter = RateLimiter::new(1, Duration::ZERO);
+
+		assert!(limiter.check("a"));
+		assert!(limiter.check("a"));
+		assert_eq!(limiter.remove_expired(), 1);
+	}
+}
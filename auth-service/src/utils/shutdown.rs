@@ -0,0 +1,40 @@
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+
+// Stops a running `Application`: it stops accepting connections, lets the requests in flight
+// finish and waits for its background tasks, all within the drain timeout
+#[derive(Clone, Default)]
+pub struct ShutdownHandle(CancellationToken);
+
+impl ShutdownHandle {
+	pub fn shutdown(&self) {
+		self.0.cancel();
+	}
+
+	// Resolves once shutdown has been requested
+	pub async fn wait(&self) {
+		self.0.cancelled().await;
+	}
+}
+
+// Resolves on Ctrl+C, or on SIGTERM as sent by `docker stop` and other orchestrators
+pub async fn shutdown_signal() {
+	let ctrl_c = async {
+		signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
+	};
+
+	#[cfg(unix)]
+	let terminate = async {
+		signal::unix::signal(signal::unix::SignalKind::terminate())
+			.expect("Failed to listen for SIGTERM")
+			.recv()
+			.await;
+	};
+	#[cfg(not(unix))]
+	let terminate = std::future::pending::<()>();
+
+	tokio::select! {
+		_ = ctrl_c => {},
+		_ = terminate => {},
+	}
+}
@@ -0,0 +1,39 @@
+use axum::body::Body;
+use axum::http::Request;
+use tower_http::request_id::RequestId;
+use tracing::Span;
+use tracing_subscriber::EnvFilter;
+
+use super::constants::env;
+
+// What gets logged unless RUST_LOG says otherwise
+const DEFAULT_LOG_FILTER: &str = "auth_service=info,tower_http=info";
+
+// Log to stdout, as JSON objects when LOG_FORMAT is `json` and human-readable lines otherwise
+pub fn init_tracing() {
+	let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+	let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+	if std::env::var(env::LOG_FORMAT_ENV_VAR).is_ok_and(|format| format == "json") {
+		subscriber.json().flatten_event(true).with_current_span(true).init();
+	} else {
+		subscriber.init();
+	}
+}
+
+// Span every request is handled in. Only the path is recorded, since query strings
+// carry secrets like magic link tokens and authorization codes.
+pub fn request_span(request: &Request<Body>) -> Span {
+	let request_id = request
+		.extensions()
+		.get::<RequestId>()
+		.and_then(|id| id.header_value().to_str().ok())
+		.unwrap_or_default();
+
+	tracing::info_span!(
+		"request",
+		method = %request.method(),
+		path = %request.uri().path(),
+		request_id = %request_id,
+	)
+}
@@ -0,0 +1,53 @@
+use std::io;
+use std::time::SystemTime;
+
+use axum_server::tls_rustls::RustlsConfig;
+
+use crate::utils::shutdown::ShutdownHandle;
+use crate::TlsConfig;
+
+pub async fn load_certificate(config: &TlsConfig) -> io::Result<RustlsConfig> {
+	RustlsConfig::from_pem_file(&config.cert_path, &config.key_path).await
+}
+
+// Swaps in the certificate and key once either file changes, so renewed certificates are picked up
+// without a restart. New connections get the new certificate, open ones keep theirs.
+// Returns once the app shuts down.
+pub async fn reload_certificate(rustls_config: RustlsConfig, config: TlsConfig, shutdown: ShutdownHandle) {
+	let mut loaded = modified_times(&config).await.ok();
+	let mut interval = tokio::time::interval(config.reload_interval());
+
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {},
+			_ = shutdown.wait() => return,
+		}
+
+		let modified = match modified_times(&config).await {
+			Ok(modified) => modified,
+			Err(e) => {
+				tracing::warn!(error = %e, "failed to check the TLS certificate for changes");
+				continue;
+			}
+		};
+		if loaded == Some(modified) {
+			continue;
+		}
+
+		// A certificate that doesn't load, e.g. because only one of the files was replaced so far,
+		// is retried on the next tick while the current one stays in use
+		match rustls_config.reload_from_pem_file(&config.cert_path, &config.key_path).await {
+			Ok(()) => {
+				tracing::info!(cert_path = %config.cert_path.display(), "reloaded TLS certificate");
+				loaded = Some(modified);
+			}
+			Err(e) => tracing::warn!(error = %e, "failed to reload TLS certificate, keeping the current one"),
+		}
+	}
+}
+
+async fn modified_times(config: &TlsConfig) -> io::Result<(SystemTime, SystemTime)> {
+	let cert = tokio::fs::metadata(&config.cert_path).await?.modified()?;
+	let key = tokio::fs::metadata(&config.key_path).await?.modified()?;
+	Ok((cert, key))
+}
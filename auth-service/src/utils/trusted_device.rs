@@ -0,0 +1,143 @@
+use axum_extra::extract::cookie::Cookie;
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{AuthAPIError, Email, TrustedDevice, TrustedDeviceId};
+use crate::{AppState, Config};
+
+use super::auth::{create_token, session_cookie, ClientInfo};
+
+// Keeps trusted device cookies from being accepted as auth tokens and vice versa
+const TRUSTED_DEVICE_AUDIENCE: &str = "trusted-device";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TrustedDeviceClaims {
+	sub: String,
+	// ID of the trusted device record, so the cookie stops working once it's revoked
+	did: String,
+	aud: String,
+	exp: usize,
+}
+
+// Remember the browser after a successful 2FA login and create the cookie that identifies it
+pub async fn remember_device(state: &AppState, email: &Email, client: ClientInfo) -> Result<Cookie<'static>, AuthAPIError> {
+	let ttl_days = state.config.auth.trusted_device_ttl_days;
+	let device = TrustedDevice::new(email.clone(), client.user_agent, ttl_days);
+
+	let exp = device.expires_at
+		.timestamp()
+		.try_into()
+		.map_err(|_| AuthAPIError::TokenCreationError)?;
+	let claims = TrustedDeviceClaims {
+		sub: email.as_ref().to_owned(),
+		did: device.id.as_ref().to_owned(),
+		aud: TRUSTED_DEVICE_AUDIENCE.to_owned(),
+		exp,
+	};
+	let token = create_token(&state.config, &claims).map_err(|_| AuthAPIError::TokenCreationError)?;
+
+	state.trusted_device_store
+		.write().await
+		.add_device(device).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(trusted_device_cookie(&state.config, token))
+}
+
+// Built like the auth cookie, so it follows the same cookie policy
+fn trusted_device_cookie(config: &Config, token: String) -> Cookie<'static> {
+	let mut cookie = session_cookie(config, config.trusted_device_cookie_name(), token);
+	cookie.set_http_only(true);
+	// Unlike the auth cookie it has to survive browser restarts
+	cookie.set_max_age(time::Duration::days(config.auth.trusted_device_ttl_days));
+	cookie
+}
+
+// Cookie that makes the browser forget it's trusted. Browsers only replace a cookie with matching
+// attributes, so it's built the same way.
+pub fn trusted_device_cookie_removal(config: &Config) -> Cookie<'static> {
+	let mut cookie = trusted_device_cookie(config, String::new());
+	cookie.make_removal();
+	cookie
+}
+
+// The ID of the trusted device in the cookie, if it's valid for `email` and hasn't been revoked
+pub async fn trusted_device_id(state: &AppState, jar: &CookieJar, email: &Email) -> Option<TrustedDeviceId> {
+	let cookie = jar.get(&state.config.trusted_device_cookie_name())?;
+
+	let mut validation = Validation::default();
+	validation.set_audience(&[TRUSTED_DEVICE_AUDIENCE]);
+	let claims = decode::<TrustedDeviceClaims>(
+		cookie.value(),
+		&DecodingKey::from_secret(state.config.auth.jwt_secret.as_bytes()),
+		&validation,
+	)
+		.ok()?
+		.claims;
+
+	if claims.sub != email.as_ref() {
+		return None;
+	}
+
+	let device_id = TrustedDeviceId::parse(claims.did).ok()?;
+	let device = state.trusted_device_store
+		.read().await
+		.get_device(&device_id).await
+		.ok()?;
+
+	(device.email == *email && device.expires_at > Utc::now()).then_some(device_id)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr as _;
+
+	use axum_extra::extract::cookie::SameSite;
+
+	use super::*;
+	use crate::{CookiePrefix, CookieSameSite, SecureCookies};
+
+	#[tokio::test]
+	async fn test_trusted_device_is_bound_to_user() {
+		let state = AppState::default();
+		let email = Email::from_str("test@example.com").unwrap();
+		let cookie = remember_device(&state, &email, ClientInfo::default()).await.unwrap();
+		let jar = CookieJar::new().add(cookie);
+
+		let device_id = trusted_device_id(&state, &jar, &email).await.unwrap();
+		let other = Email::from_str("other@example.com").unwrap();
+		assert!(trusted_device_id(&state, &jar, &other).await.is_none());
+
+		state.trusted_device_store.write().await.remove_device(&device_id).await.unwrap();
+		assert!(trusted_device_id(&state, &jar, &email).await.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_trusted_device_cookie_follows_cookie_policy() {
+		let mut config = Config::default();
+		config.cookies.prefix = CookiePrefix::Secure;
+		config.cookies.domain = Some("example.com".to_string());
+		config.cookies.same_site = CookieSameSite::Strict;
+		config.cookies.secure = SecureCookies::Always;
+		let state = AppState::default().with_config(config.clone());
+		let email = Email::from_str("test@example.com").unwrap();
+
+		let cookie = remember_device(&state, &email, ClientInfo::default()).await.unwrap();
+		assert_eq!(cookie.name(), "__Secure-trusted_device");
+		assert_eq!(cookie.domain(), Some("example.com"));
+		assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+		assert_eq!(cookie.secure(), Some(true));
+		assert_eq!(cookie.http_only(), Some(true));
+		assert_eq!(cookie.max_age(), Some(time::Duration::days(config.auth.trusted_device_ttl_days)));
+
+		let jar = CookieJar::new().add(cookie);
+		assert!(trusted_device_id(&state, &jar, &email).await.is_some());
+
+		let removal = trusted_device_cookie_removal(&config);
+		assert_eq!(removal.name(), "__Secure-trusted_device");
+		assert_eq!(removal.domain(), Some("example.com"));
+		assert_eq!(removal.max_age(), Some(time::Duration::ZERO));
+	}
+}
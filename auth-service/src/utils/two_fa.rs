@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+use crate::domain::{AuthAPIError, TwoFACode, TwoFAChannel, User};
+use crate::AppState;
+
+#[derive(Serialize)]
+struct TwoFAWebhookPayload<'a> {
+	email: &'a str,
+	code: &'a str,
+}
+
+// Deliver a 2FA code through the channel the user picked
+pub async fn send_2fa_code(state: &AppState, user: &User, code: &TwoFACode) -> Result<(), AuthAPIError> {
+	let content = format!("Your 2FA code is: {}", code.as_ref());
+
+	match &user.two_fa_channel {
+		TwoFAChannel::Email => state.email_client
+			.write().await
+			.send_email(&user.email(), "Let's Get Rusty Bootcamp: 2FA Login", &content).await,
+		TwoFAChannel::Sms(phone_number) => state.sms_client
+			.write().await
+			.send_sms(phone_number, &content).await,
+		TwoFAChannel::Webhook(url) => state.webhook_client
+			.post(url, &TwoFAWebhookPayload { email: user.email_str(), code: code.as_ref() }).await,
+	}
+	.map_err(|_| AuthAPIError::TwoFADeliveryFailed)
+}
@@ -0,0 +1,273 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use ciborium::Value;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::domain::{AuthAPIError, Email, Passkey, PasskeyCeremony, PasskeyId};
+use crate::{AppState, Config};
+
+// COSE identifier of ES256 (ECDSA with P-256 and SHA-256), the only algorithm we accept
+pub const COSE_ALG_ES256: i64 = -7;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+#[derive(Debug, PartialEq)]
+pub enum WebAuthnError {
+	InvalidClientData,
+	InvalidAuthenticatorData,
+	UnsupportedAttestation,
+	UnsupportedKey,
+	UserNotPresent,
+	UserNotVerified,
+	InvalidSignature,
+	// The authenticator's counter went backwards, so the credential may have been cloned
+	SignCountRegression,
+}
+
+// Passkeys are bound to the host the service is reachable on
+pub fn relying_party_id(config: &Config) -> String {
+	url::Url::parse(&config.auth.issuer)
+		.ok()
+		.and_then(|url| url.host_str().map(str::to_owned))
+		.unwrap_or_else(|| "localhost".to_string())
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+	#[serde(rename = "type")]
+	ceremony_type: String,
+	challenge: String,
+	origin: String,
+}
+
+// Check the browser ran the expected ceremony on our origin, and return the challenge it signed
+pub fn verify_client_data(config: &Config, client_data_json: &[u8], expected_type: &str) -> Result<String, WebAuthnError> {
+	let client_data: ClientData = serde_json::from_slice(client_data_json).map_err(|_| WebAuthnError::InvalidClientData)?;
+	if client_data.ceremony_type != expected_type || client_data.origin != config.auth.issuer {
+		return Err(WebAuthnError::InvalidClientData);
+	}
+	Ok(client_data.challenge)
+}
+
+pub struct AuthenticatorData {
+	pub flags: u8,
+	pub sign_count: u32,
+	// Only present when a credential is registered
+	pub credential: Option<AttestedCredential>,
+}
+
+pub struct AttestedCredential {
+	pub id: Vec<u8>,
+	// Uncompressed SEC1 encoding of the P-256 public key
+	pub public_key: Vec<u8>,
+}
+
+impl AuthenticatorData {
+	// Layout described in https://www.w3.org/TR/webauthn-2/#sctn-authenticator-data
+	pub fn parse(config: &Config, data: &[u8]) -> Result<Self, WebAuthnError> {
+		if data.len() < 37 {
+			return Err(WebAuthnError::InvalidAuthenticatorData);
+		}
+		let (rp_id_hash, rest) = data.split_at(32);
+		if rp_id_hash[..] != Sha256::digest(relying_party_id(config).as_bytes())[..] {
+			return Err(WebAuthnError::InvalidAuthenticatorData);
+		}
+
+		let flags = rest[0];
+		let sign_count = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]);
+		if flags & FLAG_USER_PRESENT == 0 {
+			return Err(WebAuthnError::UserNotPresent);
+		}
+
+		let credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+			Some(parse_attested_credential(&rest[5..])?)
+		} else {
+			None
+		};
+
+		Ok(Self { flags, sign_count, credential })
+	}
+
+	pub fn user_verified(&self) -> bool {
+		self.flags & FLAG_USER_VERIFIED != 0
+	}
+}
+
+// AAGUID (16 bytes), credential ID length (2 bytes), credential ID, then the COSE public key
+fn parse_attested_credential(data: &[u8]) -> Result<AttestedCredential, WebAuthnError> {
+	if data.len() < 18 {
+		return Err(WebAuthnError::InvalidAuthenticatorData);
+	}
+	let id_length = u16::from_be_bytes([data[16], data[17]]) as usize;
+	let rest = &data[18..];
+	if rest.len() < id_length {
+		return Err(WebAuthnError::InvalidAuthenticatorData);
+	}
+
+	let (id, mut public_key) = rest.split_at(id_length);
+	let public_key: Value = ciborium::from_reader(&mut public_key).map_err(|_| WebAuthnError::InvalidAuthenticatorData)?;
+
+	Ok(AttestedCredential {
+		id: id.to_vec(),
+		public_key: cose_key_to_sec1(&public_key)?,
+	})
+}
+
+fn cose_key_to_sec1(key: &Value) -> Result<Vec<u8>, WebAuthnError> {
+	let entries = key.as_map().ok_or(WebAuthnError::UnsupportedKey)?;
+	let get = |label: i64| {
+		entries
+			.iter()
+			.find(|(key, _)| key.as_integer() == Some(label.into()))
+			.map(|(_, value)| value)
+	};
+	let integer = |label: i64| get(label).and_then(Value::as_integer).map(i128::from);
+	let coordinate = |label: i64| get(label).and_then(Value::as_bytes).filter(|bytes| bytes.len() == 32);
+
+	// Key type (1) EC2, algorithm (3) ES256 and curve (-1) P-256
+	if integer(1) != Some(2) || integer(3) != Some(COSE_ALG_ES256.into()) || integer(-1) != Some(1) {
+		return Err(WebAuthnError::UnsupportedKey);
+	}
+	let (Some(x), Some(y)) = (coordinate(-2), coordinate(-3)) else {
+		return Err(WebAuthnError::UnsupportedKey);
+	};
+
+	let mut sec1 = vec![0x04];
+	sec1.extend_from_slice(x);
+	sec1.extend_from_slice(y);
+	VerifyingKey::from_sec1_bytes(&sec1).map_err(|_| WebAuthnError::UnsupportedKey)?;
+	Ok(sec1)
+}
+
+// We ask for "none" attestation, since we don't restrict which authenticators can be used
+pub fn parse_attestation_object(config: &Config, data: &[u8]) -> Result<AuthenticatorData, WebAuthnError> {
+	let value: Value = ciborium::from_reader(data).map_err(|_| WebAuthnError::InvalidAuthenticatorData)?;
+	let entries = value.as_map().ok_or(WebAuthnError::InvalidAuthenticatorData)?;
+	let get = |name: &str| {
+		entries
+			.iter()
+			.find(|(key, _)| key.as_text() == Some(name))
+			.map(|(_, value)| value)
+	};
+
+	if get("fmt").and_then(Value::as_text) != Some("none")
+		|| !get("attStmt").and_then(Value::as_map).is_some_and(Vec::is_empty)
+	{
+		return Err(WebAuthnError::UnsupportedAttestation);
+	}
+
+	let auth_data = get("authData").and_then(Value::as_bytes).ok_or(WebAuthnError::InvalidAuthenticatorData)?;
+	AuthenticatorData::parse(config, auth_data)
+}
+
+// Check an assertion was signed by the passkey, and return the authenticator's new signature counter
+pub fn verify_assertion(
+	config: &Config,
+	passkey: &Passkey,
+	client_data_json: &[u8],
+	authenticator_data: &[u8],
+	signature: &[u8],
+	require_user_verification: bool,
+) -> Result<u32, WebAuthnError> {
+	let auth_data = AuthenticatorData::parse(config, authenticator_data)?;
+	if require_user_verification && !auth_data.user_verified() {
+		return Err(WebAuthnError::UserNotVerified);
+	}
+
+	let key = VerifyingKey::from_sec1_bytes(&passkey.public_key).map_err(|_| WebAuthnError::UnsupportedKey)?;
+	let signature = Signature::from_der(signature).map_err(|_| WebAuthnError::InvalidSignature)?;
+	let mut signed = authenticator_data.to_vec();
+	signed.extend_from_slice(&Sha256::digest(client_data_json));
+	key.verify(&signed, &signature).map_err(|_| WebAuthnError::InvalidSignature)?;
+
+	check_sign_count(passkey.sign_count, auth_data.sign_count)?;
+	Ok(auth_data.sign_count)
+}
+
+// Authenticators without a counter always report 0, otherwise it has to grow with every use
+fn check_sign_count(stored: u32, received: u32) -> Result<(), WebAuthnError> {
+	if (stored != 0 || received != 0) && received <= stored {
+		return Err(WebAuthnError::SignCountRegression);
+	}
+	Ok(())
+}
+
+// JSON form of the credential returned by `navigator.credentials.get()`, binary fields base64url-encoded
+#[derive(Clone, Debug, Deserialize)]
+pub struct PasskeyAssertion {
+	pub id: String,
+	#[serde(rename = "clientDataJSON")]
+	pub client_data_json: String,
+	#[serde(rename = "authenticatorData")]
+	pub authenticator_data: String,
+	pub signature: String,
+}
+
+// Verify an assertion answers a challenge we handed out, and record the use of the passkey.
+// When `email` is set, the passkey has to belong to that user.
+pub async fn authenticate_passkey(
+	state: &AppState,
+	assertion: &PasskeyAssertion,
+	email: Option<&Email>,
+	require_user_verification: bool,
+) -> Result<Passkey, AuthAPIError> {
+	let decode = |value: &str| URL_SAFE_NO_PAD.decode(value).map_err(|_| AuthAPIError::InvalidPasskey);
+	let client_data_json = decode(&assertion.client_data_json)?;
+	let authenticator_data = decode(&assertion.authenticator_data)?;
+	let signature = decode(&assertion.signature)?;
+	let id = PasskeyId::parse(&assertion.id).map_err(|_| AuthAPIError::InvalidPasskey)?;
+
+	let challenge = verify_client_data(&state.config, &client_data_json, "webauthn.get").map_err(|_| AuthAPIError::InvalidPasskey)?;
+
+	let mut passkey_store = state.passkey_store.write().await;
+	let challenge = passkey_store.take_challenge(&challenge).await.map_err(|_| AuthAPIError::InvalidPasskey)?;
+	let passkey = passkey_store.get_passkey(&id).await.map_err(|_| AuthAPIError::InvalidPasskey)?;
+
+	// Challenges handed out for a specific user only work with that user's passkeys
+	let challenge_matches = match &challenge.ceremony {
+		PasskeyCeremony::Authentication(None) => true,
+		PasskeyCeremony::Authentication(Some(challenge_email)) => challenge_email == &passkey.email,
+		PasskeyCeremony::Registration(_) => false,
+	};
+	if !challenge_matches || email.is_some_and(|email| email != &passkey.email) {
+		return Err(AuthAPIError::InvalidPasskey);
+	}
+
+	let sign_count = verify_assertion(&state.config, &passkey, &client_data_json, &authenticator_data, &signature, require_user_verification)
+		.map_err(|_| AuthAPIError::InvalidPasskey)?;
+	passkey_store
+		.update_sign_count(&passkey.id, sign_count).await
+		.map_err(|_| AuthAPIError::UnexpectedError)?;
+
+	Ok(passkey)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_check_sign_count() {
+		assert_eq!(check_sign_count(0, 0), Ok(()));
+		assert_eq!(check_sign_count(0, 1), Ok(()));
+		assert_eq!(check_sign_count(5, 6), Ok(()));
+		assert_eq!(check_sign_count(5, 5), Err(WebAuthnError::SignCountRegression));
+		assert_eq!(check_sign_count(5, 0), Err(WebAuthnError::SignCountRegression));
+	}
+
+	#[test]
+	fn test_reject_foreign_origin() {
+		let client_data = serde_json::json!({
+			"type": "webauthn.get",
+			"challenge": "abc",
+			"origin": "https://evil.example.com",
+		});
+		let client_data = serde_json::to_vec(&client_data).unwrap();
+		assert_eq!(verify_client_data(&Config::default(), &client_data, "webauthn.get"), Err(WebAuthnError::InvalidClientData));
+	}
+}
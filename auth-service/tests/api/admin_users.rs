@@ -0,0 +1,293 @@
+use std::str::FromStr;
+
+use auth_service::{
+	AccountStatus, AdminUserResponse, AppState, Email, LoginLockoutPolicy, Role, SignupResponse, TwoFactorAuthResponse, UserListResponse,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn signup(app: &TestApp, email: &str) {
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+}
+
+// Sign up an admin and log in as them
+async fn login_as_admin(app: &TestApp) -> String {
+	let email = get_random_email();
+	signup(app, &email).await;
+	app.grant_role(&email, Role::Admin).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+	email
+}
+
+// Log the user in with their own client, so the admin's cookie stays in place
+async fn login_as_user(app: &TestApp, email: &str, password: &str) -> (u16, reqwest::Client) {
+	let client = reqwest::Client::builder().cookie_store(true).build().unwrap();
+	let response = client
+		.post(format!("{}/login", app.address))
+		.json(&serde_json::json!({"email": email, "password": password}))
+		.send()
+		.await
+		.unwrap();
+	(response.status().as_u16(), client)
+}
+
+async fn user_details(app: &TestApp, email: &str) -> AdminUserResponse {
+	let response = app.get_admin_user(email).await;
+	assert_eq!(response.status().as_u16(), 200);
+	response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let response = app.get_admin_users(&[("page", "1")]).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_403_without_permission() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+
+	let response = app.get_admin_users(&[("page", "1")]).await;
+	assert_eq!(response.status().as_u16(), 403);
+	let response = app.post_admin_user_action(&email, "unlock").await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_let_support_look_but_not_change() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	app.grant_role(&email, Role::Support).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+
+	assert_eq!(user_details(&app, &email).await.roles, vec![Role::Support]);
+	let response = app.post_admin_user_action(&email, "disable").await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_list_users_with_pagination_and_search() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let domain = format!("{}.example.org", uuid::Uuid::new_v4());
+	for name in ["carol", "alice", "bob"] {
+		signup(&app, &format!("{name}@{domain}")).await;
+	}
+
+	let response = app.get_admin_users(&[("search", domain.to_uppercase().as_str()), ("page", "1"), ("perPage", "2")]).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let page = response.json::<UserListResponse>().await.unwrap();
+	assert_eq!(page.total, 3);
+	assert_eq!((page.page, page.per_page), (1, 2));
+	let emails: Vec<String> = page.users.into_iter().map(|user| user.email).collect();
+	assert_eq!(emails, vec![format!("alice@{domain}"), format!("bob@{domain}")]);
+
+	let response = app.get_admin_users(&[("search", domain.as_str()), ("page", "2"), ("perPage", "2")]).await;
+	let page = response.json::<UserListResponse>().await.unwrap();
+	let emails: Vec<String> = page.users.into_iter().map(|user| user.email).collect();
+	assert_eq!(emails, vec![format!("carol@{domain}")]);
+}
+
+#[tokio::test]
+async fn should_cap_page_size() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+
+	let response = app.get_admin_users(&[("perPage", "1000")]).await;
+	assert_eq!(response.json::<UserListResponse>().await.unwrap().per_page, 100);
+}
+
+#[tokio::test]
+async fn should_return_user_details() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let user = user_details(&app, &email).await;
+	assert_eq!(user.email, email);
+	assert!(user.has_password);
+	assert!(!user.requires_2fa);
+	assert_eq!(user.two_fa_channel, "email");
+	assert_eq!(user.status, AccountStatus::Active);
+	assert!(user.locked_until.is_none());
+
+	let response = app.get_admin_user(&get_random_email()).await;
+	assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_disable_and_enable_user() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	let (status, user_client) = login_as_user(&app, &email, "password123").await;
+	assert_eq!(status, 204);
+
+	let response = app.post_admin_user_action(&email, "disable").await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<AdminUserResponse>().await.unwrap().status, AccountStatus::Disabled);
+
+	// Disabling logs the user out everywhere and keeps them from logging in again
+	let response = user_client.get(format!("{}/sessions", app.address)).send().await.unwrap();
+	assert_eq!(response.status().as_u16(), 401);
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 403);
+
+	let response = app.post_admin_user_action(&email, "enable").await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 204);
+}
+
+#[tokio::test]
+async fn should_update_status() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.put_admin_user_status(&email, &serde_json::json!({"status": "pendingVerification"})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<AdminUserResponse>().await.unwrap().status, AccountStatus::PendingVerification);
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 403);
+
+	let response = app.put_admin_user_status(&email, &serde_json::json!({"status": "active"})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 204);
+
+	let response = app.put_admin_user_status(&email, &serde_json::json!({"status": "suspended"})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_not_let_admin_disable_themselves() {
+	let app = TestApp::new().await;
+	let admin = login_as_admin(&app).await;
+
+	let response = app.post_admin_user_action(&admin, "disable").await;
+	assert_eq!(response.status().as_u16(), 400);
+	let response = app.put_admin_user_status(&admin, &serde_json::json!({"status": "pendingVerification"})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_lock_and_unlock_user() {
+	let policy = LoginLockoutPolicy { max_failed_attempts: 3, ..Default::default() };
+	let app = TestApp::with_state(AppState::default().with_login_lockout_policy(policy)).await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	for _ in 0..3 {
+		assert_eq!(login_as_user(&app, &email, "wrong-password").await.0, 401);
+	}
+	// Even the right password is refused while the account is locked
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 423);
+	let user = user_details(&app, &email).await;
+	assert_eq!(user.failed_login_attempts, 3);
+	assert!(user.locked_until.is_some());
+
+	let response = app.post_admin_user_action(&email, "unlock").await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert!(response.json::<AdminUserResponse>().await.unwrap().locked_until.is_none());
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 204);
+	assert_eq!(user_details(&app, &email).await.failed_login_attempts, 0);
+}
+
+#[tokio::test]
+async fn should_reset_failed_attempts_after_successful_login() {
+	let policy = LoginLockoutPolicy { max_failed_attempts: 3, ..Default::default() };
+	let app = TestApp::with_state(AppState::default().with_login_lockout_policy(policy)).await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	for _ in 0..2 {
+		assert_eq!(login_as_user(&app, &email, "wrong-password").await.0, 401);
+	}
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 204);
+	assert_eq!(login_as_user(&app, &email, "wrong-password").await.0, 401);
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 204);
+}
+
+#[tokio::test]
+async fn should_force_password_reset() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.post_admin_user_action(&email, "force-password-reset").await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert!(response.json::<AdminUserResponse>().await.unwrap().password_reset_required);
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 403);
+
+	let payload = serde_json::json!({"email": email, "currentPassword": "wrong-password", "newPassword": "new-password"});
+	assert_eq!(app.post_password(&payload).await.status().as_u16(), 401);
+	let payload = serde_json::json!({"email": email, "currentPassword": "password123", "newPassword": "new-password"});
+	assert_eq!(app.post_password(&payload).await.status().as_u16(), 204);
+
+	assert_eq!(login_as_user(&app, &email, "password123").await.0, 401);
+	assert_eq!(login_as_user(&app, &email, "new-password").await.0, 204);
+	assert!(!user_details(&app, &email).await.password_reset_required);
+}
+
+#[tokio::test]
+async fn should_not_force_password_reset_for_passwordless_user() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": null, "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201);
+
+	let response = app.post_admin_user_action(&email, "force-password-reset").await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_reset_2fa() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	let recovery_codes = response.json::<SignupResponse>().await.unwrap().recovery_codes.unwrap();
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 206);
+
+	let response = app.post_admin_user_action(&email, "reset-2fa").await;
+	assert_eq!(response.status().as_u16(), 200);
+	let user = response.json::<AdminUserResponse>().await.unwrap();
+	assert!(user.requires_2fa);
+	assert_eq!(user.two_fa_channel, "email");
+
+	// The pending code and the old recovery codes no longer work
+	let email = Email::from_str(&email).unwrap();
+	assert!(app.two_fa_code_store.read().await.get_code(&email).await.is_err());
+	let response = app.post_login(&serde_json::json!({"email": email.as_ref(), "password": "password123"})).await;
+	let login = response.json::<TwoFactorAuthResponse>().await.unwrap();
+	let payload = serde_json::json!({
+		"email": email.as_ref(),
+		"loginAttemptId": login.login_attempt_id,
+		"2FACode": recovery_codes[0],
+	});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_not_reset_2fa_for_user_without_2fa() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.post_admin_user_action(&email, "reset-2fa").await;
+	assert_eq!(response.status().as_u16(), 400);
+}
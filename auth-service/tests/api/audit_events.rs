@@ -0,0 +1,162 @@
+use auth_service::{AppState, AuditEventListResponse, AuditEventResponse, LoginLockoutPolicy, Role};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn signup(app: &TestApp, email: &str) {
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+}
+
+// Sign up a user with the role and log in as them
+async fn login_with_role(app: &TestApp, role: Role) -> String {
+	let email = get_random_email();
+	signup(app, &email).await;
+	app.grant_role(&email, role).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+	email
+}
+
+// Log the user in with their own client, so the operator's cookie stays in place
+async fn login_as_user(app: &TestApp, email: &str, password: &str) -> u16 {
+	let client = reqwest::Client::builder().cookie_store(true).build().unwrap();
+	let response = client
+		.post(format!("{}/login", app.address))
+		.json(&serde_json::json!({"email": email, "password": password}))
+		.send()
+		.await
+		.unwrap();
+	response.status().as_u16()
+}
+
+async fn audit_events(app: &TestApp, query: &[(&str, &str)]) -> Vec<AuditEventResponse> {
+	let response = app.get_admin_audit_events(query).await;
+	assert_eq!(response.status().as_u16(), 200);
+	response.json::<AuditEventListResponse>().await.unwrap().events
+}
+
+fn kinds(events: &[AuditEventResponse]) -> Vec<&str> {
+	events.iter().map(|event| event.kind.as_str()).collect()
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let response = app.get_admin_audit_events(&[("limit", "10")]).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_403_without_permission() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+
+	let response = app.get_admin_audit_events(&[("email", email.as_str())]).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_record_logins_newest_first() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	assert_eq!(login_as_user(&app, &email, "wrongpassword").await, 401);
+	assert_eq!(login_as_user(&app, &email, "password123").await, 204);
+	login_with_role(&app, Role::Support).await;
+
+	let events = audit_events(&app, &[("email", email.as_str())]).await;
+	assert_eq!(kinds(&events), vec!["login_succeeded", "login_failed", "signed_up"]);
+	assert_eq!(events[0].details.as_deref(), Some("password"));
+	assert_eq!(events[1].details.as_deref(), Some("wrong password"));
+	assert!(events.iter().all(|event| event.email == email && event.actor.is_none()));
+	assert!(events.iter().all(|event| event.ip.as_deref() == Some("127.0.0.1")));
+}
+
+#[tokio::test]
+async fn should_filter_by_type_and_time_range() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	let before = chrono::Utc::now().to_rfc3339();
+	assert_eq!(login_as_user(&app, &email, "password123").await, 204);
+	let after = chrono::Utc::now().to_rfc3339();
+	login_with_role(&app, Role::Admin).await;
+
+	let events = audit_events(&app, &[("email", email.as_str()), ("type", "login_succeeded")]).await;
+	assert_eq!(kinds(&events), vec!["login_succeeded"]);
+
+	let events = audit_events(&app, &[("email", email.as_str()), ("since", before.as_str())]).await;
+	assert_eq!(kinds(&events), vec!["login_succeeded"]);
+	let events = audit_events(&app, &[("email", email.as_str()), ("until", before.as_str())]).await;
+	assert_eq!(kinds(&events), vec!["signed_up"]);
+	let events = audit_events(&app, &[("email", email.as_str()), ("since", after.as_str())]).await;
+	assert!(events.is_empty());
+
+	let events = audit_events(&app, &[("type", "signed_up"), ("limit", "1")]).await;
+	assert_eq!(events.len(), 1);
+}
+
+#[tokio::test]
+async fn should_return_400_for_invalid_query() {
+	let app = TestApp::new().await;
+	login_with_role(&app, Role::Admin).await;
+
+	for query in [[("type", "unknown")], [("since", "yesterday")], [("email", "not-an-email")]] {
+		let response = app.get_admin_audit_events(&query).await;
+		assert_eq!(response.status().as_u16(), 400, "Failed for query {query:?}");
+	}
+}
+
+#[tokio::test]
+async fn should_record_the_administrator_behind_account_changes() {
+	let app = TestApp::new().await;
+	let admin = login_with_role(&app, Role::Admin).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.post_admin_user_action(&email, "disable").await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(login_as_user(&app, &email, "password123").await, 403);
+
+	let events = audit_events(&app, &[("email", email.as_str())]).await;
+	assert_eq!(kinds(&events), vec!["login_failed", "account_status_changed", "signed_up"]);
+	assert_eq!(events[0].details.as_deref(), Some("account is disabled"));
+	assert_eq!(events[1].actor.as_deref(), Some(admin.as_str()));
+	assert_eq!(events[1].details.as_deref(), Some("disabled"));
+}
+
+#[tokio::test]
+async fn should_record_lockouts() {
+	let policy = LoginLockoutPolicy { max_failed_attempts: 2, ..Default::default() };
+	let app = TestApp::with_state(AppState::default().with_login_lockout_policy(policy)).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	for _ in 0..2 {
+		assert_eq!(login_as_user(&app, &email, "wrongpassword").await, 401);
+	}
+	assert_eq!(login_as_user(&app, &email, "password123").await, 423);
+	login_with_role(&app, Role::Support).await;
+
+	let events = audit_events(&app, &[("email", email.as_str())]).await;
+	assert_eq!(
+		kinds(&events),
+		vec!["login_failed", "account_locked", "login_failed", "login_failed", "signed_up"]
+	);
+	assert_eq!(events[0].details.as_deref(), Some("account locked"));
+}
+
+#[tokio::test]
+async fn should_record_logouts() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(app.post_logout().await.status().as_u16(), 200);
+	login_with_role(&app, Role::Admin).await;
+
+	let events = audit_events(&app, &[("email", email.as_str()), ("type", "logged_out")]).await;
+	assert_eq!(events.len(), 1);
+}
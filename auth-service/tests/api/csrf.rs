@@ -0,0 +1,102 @@
+use auth_service::{Role, CSRF_COOKIE_NAME, CSRF_HEADER_NAME};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn log_in(app: &TestApp) -> String {
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+	email
+}
+
+async fn post_logout_with(app: &TestApp, headers: &[(&str, &str)]) -> reqwest::Response {
+	let mut request = app.http_client.post(format!("{}/logout", app.address));
+	for (name, value) in headers {
+		request = request.header(*name, *value);
+	}
+	request.send().await.expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn should_hand_out_a_csrf_token_readable_by_scripts_with_the_auth_cookie() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	let csrf_cookie = response.headers()
+		.get_all("set-cookie")
+		.iter()
+		.map(|value| value.to_str().unwrap())
+		.find(|value| value.starts_with(&format!("{CSRF_COOKIE_NAME}=")))
+		.expect("No CSRF cookie");
+	assert!(!csrf_cookie.contains("HttpOnly"), "Scripts can't read the CSRF cookie: {csrf_cookie}");
+	let token = app.csrf_token();
+	assert!(!token.is_empty());
+
+	// A new login gets a new token
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_ne!(app.csrf_token(), token);
+}
+
+#[tokio::test]
+async fn should_reject_cookie_authenticated_requests_without_the_token() {
+	let app = TestApp::new().await;
+	log_in(&app).await;
+
+	let response = post_logout_with(&app, &[]).await;
+	assert_eq!(response.status().as_u16(), 403);
+
+	let response = post_logout_with(&app, &[(CSRF_HEADER_NAME, "wrong")]).await;
+	assert_eq!(response.status().as_u16(), 403);
+
+	// Still logged in
+	assert_eq!(app.get_profile().await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_reject_requests_from_other_origins() {
+	let app = TestApp::new().await;
+	log_in(&app).await;
+	let token = app.csrf_token();
+
+	let response = post_logout_with(&app, &[(CSRF_HEADER_NAME, &token), ("origin", "https://evil.example")]).await;
+	assert_eq!(response.status().as_u16(), 403);
+
+	let response = post_logout_with(&app, &[(CSRF_HEADER_NAME, &token), ("referer", "https://evil.example/page")]).await;
+	assert_eq!(response.status().as_u16(), 403);
+
+	let response = post_logout_with(&app, &[(CSRF_HEADER_NAME, &token), ("origin", "http://localhost:3000")]).await;
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_clear_the_token_on_logout() {
+	let app = TestApp::new().await;
+	log_in(&app).await;
+
+	let response = app.post_logout().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	assert_eq!(app.csrf_token(), "");
+}
+
+#[tokio::test]
+async fn should_not_require_the_token_with_a_bearer_token() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	app.grant_role(&email, Role::Admin).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+
+	// The client still sends the auth cookie, but authenticates with the header
+	let response = app.http_client
+		.put(format!("{}/admin/users/{}/roles", app.address, email))
+		.bearer_auth(app.auth_token())
+		.json(&serde_json::json!({"roles": ["admin"]}))
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use auth_service::{get_sql_pool, AppState, HealthResponse, SqliteAuditSink};
+use tokio::sync::RwLock;
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn should_return_200_when_alive() {
+	let app = TestApp::new().await;
+
+	let response = app.get_health_live().await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<HealthResponse>().await.unwrap().status, "ok");
+}
+
+#[tokio::test]
+async fn should_return_200_when_every_dependency_is_ready() {
+	let app = TestApp::new().await;
+
+	let response = app.get_health_ready().await;
+	assert_eq!(response.status().as_u16(), 200);
+	let health = response.json::<HealthResponse>().await.unwrap();
+	assert_eq!(health.status, "ok");
+	for dependency in ["user_store", "client_store", "audit_sink", "email_client", "sms_client"] {
+		assert_eq!(health.checks[dependency], "ok", "Failed for {dependency}");
+	}
+}
+
+#[tokio::test]
+async fn should_return_503_when_the_database_is_unavailable() {
+	let pool = get_sql_pool("sqlite::memory:").await;
+	pool.close().await;
+	let audit_sink = Arc::new(RwLock::new(Box::new(SqliteAuditSink::new(pool)) as _));
+	let app = TestApp::with_state(AppState::default().with_audit_sink(audit_sink)).await;
+
+	let response = app.get_health_ready().await;
+	assert_eq!(response.status().as_u16(), 503);
+	let health = response.json::<HealthResponse>().await.unwrap();
+	assert_eq!(health.status, "unavailable");
+	assert_eq!(health.checks["audit_sink"], "unavailable");
+	assert_eq!(health.checks["user_store"], "ok");
+
+	// The process itself is still fine
+	assert_eq!(app.get_health_live().await.status().as_u16(), 200);
+}
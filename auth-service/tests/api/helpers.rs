@@ -1,48 +1,160 @@
 use std::sync::Arc;
 
-use auth_service::{test, AppState, Application};
-use reqwest::cookie::Jar;
+use auth_service::{
+	test, AccountStatus, AppState, Application, ConsentResponse, Email, MockEmailClient, MockSmsClient, OAuthClient, Role,
+	ServerConfig, ShutdownHandle, TokenResponse, CSRF_COOKIE_NAME, CSRF_HEADER_NAME, JWT_COOKIE_NAME,
+};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use reqwest::cookie::{CookieStore, Jar};
 use uuid::Uuid;
 
 pub struct TestApp {
 	pub address: String,
 	pub cookie_jar: Arc<Jar>,
 	pub http_client: reqwest::Client,
+	pub user_store: auth_service::UserStoreType,
 	pub banned_token_store: auth_service::BannedTokenStoreType,
 	pub two_fa_code_store: auth_service::TwoFACodeStoreType,
+	pub client_store: auth_service::ClientStoreType,
+	pub audit_sink: auth_service::AuditSinkType,
+	pub email_client: MockEmailClient,
+	pub sms_client: MockSmsClient,
+	pub shutdown: ShutdownHandle,
+	server: JoinHandle<Result<(), std::io::Error>>,
 }
 
 impl TestApp {
 	pub async fn new() -> Self {
-		let state = AppState::default();
+		Self::with_state(AppState::default()).await
+	}
+
+	// Run the app with a customized state. The email and SMS clients are always replaced
+	// with mocks the test can inspect.
+	pub async fn with_state(state: AppState) -> Self {
+		let email_client = MockEmailClient::default();
+		let sms_client = MockSmsClient::default();
+		let state = AppState {
+			email_client: Arc::new(RwLock::new(Box::new(email_client.clone()))),
+			sms_client: Arc::new(RwLock::new(Box::new(sms_client.clone()))),
+			..state
+		};
+		let user_store = state.user_store.clone();
 		let banned_token_store = state.banned_token_store.clone();
 		let two_fa_code_store = state.two_fa_code_store.clone();
-		let app = Application::build(state, test::APP_ADDRESS)
+		let client_store = state.client_store.clone();
+		let audit_sink = state.audit_sink.clone();
+
+		// Public client used by the authorization code flow tests
+		client_store.write().await
+			.add_client(OAuthClient {
+				client_id: TEST_CLIENT_ID.to_string(),
+				secret_hash: None,
+				redirect_uris: vec![REDIRECT_URI.to_string()],
+				allowed_scopes: vec!["openid".to_string(), "email".to_string(), "profile".to_string()],
+			}).await
+			.expect("Failed to register test client");
+
+		// Same settings on a random port, so tests can run side by side
+		let server_config = ServerConfig {
+			address: test::APP_ADDRESS.to_string(),
+			..state.config.server.clone()
+		};
+		let app = Application::build(state, &server_config)
 			.await
 			.expect("Failed to build app");
 
-		let address = format!("http://{}", app.address.clone());
+		let scheme = if server_config.tls.is_some() { "https" } else { "http" };
+		let address = format!("{scheme}://{}", app.address.clone());
 
 		// Run the auth service in a separate async task
 		// to avoid blocking the main test thread.
-		#[allow(clippy::let_underscore_future)]
-		let _ = tokio::spawn(app.run());
+		let shutdown = app.shutdown_handle();
+		let server = tokio::spawn(app.run());
 
 		let cookie_jar = Arc::new(Jar::default());
-		let http_client = reqwest::Client::builder()
+		let mut http_client = reqwest::Client::builder()
 			.cookie_provider(Arc::clone(&cookie_jar))
-			.build()
-			.unwrap();
+			.redirect(reqwest::redirect::Policy::none());
+		// Tests serving HTTPS use a self-signed certificate
+		if let Some(tls) = &server_config.tls {
+			let cert = std::fs::read(&tls.cert_path).expect("Failed to read certificate");
+			http_client = http_client.add_root_certificate(reqwest::Certificate::from_pem(&cert).unwrap());
+		}
+		let http_client = http_client.build().unwrap();
 
 		Self {
 			address,
 			cookie_jar,
 			http_client,
+			user_store,
 			banned_token_store,
 			two_fa_code_store,
+			client_store,
+			audit_sink,
+			email_client,
+			sms_client,
+			shutdown,
+			server,
 		}
 	}
 
+	// Wait for the app to stop after `shutdown` was triggered, and for however it went
+	pub async fn stopped(self) -> Result<(), std::io::Error> {
+		self.server.await.expect("Server task panicked")
+	}
+
+	// Roles can only be handed out by someone who already has one, so tests set them directly
+	pub async fn grant_role(&self, email: &str, role: Role) {
+		let mut user_store = self.user_store.write().await;
+		let mut user = user_store.get_user(email.parse::<Email>().unwrap()).await.expect("User not found");
+		user.roles.push(role);
+		user_store.update_user(user).await.unwrap();
+	}
+
+	// Change the status in the store only, leaving the user's sessions alone
+	pub async fn set_account_status(&self, email: &str, status: AccountStatus) {
+		let mut user_store = self.user_store.write().await;
+		let mut user = user_store.get_user(email.parse::<Email>().unwrap()).await.expect("User not found");
+		user.status = status;
+		user_store.update_user(user).await.unwrap();
+	}
+
+	// Value of a cookie the app set on the test client
+	pub fn cookie(&self, name: &str) -> Option<String> {
+		let cookies = self.cookie_jar.cookies(&self.address.parse().unwrap())?;
+		cookies.to_str().unwrap()
+			.split("; ")
+			.find_map(|cookie| cookie.strip_prefix(&format!("{name}=")))
+			.map(str::to_owned)
+	}
+
+	pub fn auth_token(&self) -> String {
+		self.cookie(JWT_COOKIE_NAME).expect("Not logged in")
+	}
+
+	// The CSRF cookie handed out with the auth cookie, which requests authenticated by the cookie repeat
+	// in a header like the page scripts do
+	pub fn csrf_token(&self) -> String {
+		self.cookie(CSRF_COOKIE_NAME).unwrap_or_default()
+	}
+
+	// Logs in as a client without a cookie jar would, opening a new session, and returns its token to use as a bearer token
+	pub async fn auth_token_for<Body: serde::Serialize>(&self, login: &Body) -> String {
+		let response = reqwest::Client::new()
+			.post(format!("{}/login", self.address))
+			.json(login)
+			.send()
+			.await
+			.expect("Failed to execute request.");
+
+		let token = response.cookies()
+			.find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+			.map(|cookie| cookie.value().to_owned());
+
+		token.expect("Failed to log in")
+	}
+
 	pub async fn get_root(&self) -> reqwest::Response {
 		self.http_client
 			.get(format!("{}/", self.address))
@@ -51,6 +163,30 @@ impl TestApp {
 			.expect("Failed to execute request.")
 	}
 
+	pub async fn get_health_live(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/health/live", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_health_ready(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/health/ready", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_metrics(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/metrics", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
 	pub async fn post_signup<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
 		self.http_client
 			.post(format!("{}/signup", self.address))
@@ -69,6 +205,24 @@ impl TestApp {
 			.expect("Failed to execute request.")
 	}
 
+	pub async fn post_magic_link<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/login/magic-link", self.address))
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_magic_link_callback(&self, token: &str) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/login/magic-link/callback", self.address))
+			.query(&[("token", token)])
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
 	pub async fn post_verify_2fa<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
 		self.http_client
 			.post(format!("{}/verify-2fa", self.address))
@@ -78,9 +232,19 @@ impl TestApp {
 			.expect("Failed to execute request.")
 	}
 
+	pub async fn post_resend_2fa<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/resend-2fa", self.address))
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
 	pub async fn post_logout(&self) -> reqwest::Response {
 		self.http_client
 			.post(format!("{}/logout", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
 			.send()
 			.await
 			.expect("Failed to execute request.")
@@ -94,8 +258,361 @@ impl TestApp {
 			.await
 			.expect("Failed to execute request.")
 	}
+
+	pub async fn get_oauth_authorize<Query: serde::Serialize + ?Sized>(&self, query: &Query) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/oauth/authorize", self.address))
+			.query(query)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_oauth_authorize<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/oauth/authorize", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_oauth_token<Form: serde::Serialize + ?Sized>(&self, form: &Form) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/oauth/token", self.address))
+			.form(form)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_oauth_device_authorization<Form: serde::Serialize + ?Sized>(&self, form: &Form) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/oauth/device_authorization", self.address))
+			.form(form)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_oauth_device_verify<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/oauth/device/verify", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_userinfo(&self, token: &str) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/userinfo", self.address))
+			.bearer_auth(token)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_profile(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/profile", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn patch_profile<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.patch(format!("{}/profile", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	// Run the authorization code flow for the logged in user and exchange the code for tokens
+	pub async fn get_oauth_tokens(&self, scope: &str) -> TokenResponse {
+		let consent = serde_json::json!({
+			"response_type": "code",
+			"client_id": TEST_CLIENT_ID,
+			"redirect_uri": REDIRECT_URI,
+			"scope": scope,
+			"code_challenge": CODE_CHALLENGE,
+			"code_challenge_method": "S256",
+			"approve": true,
+		});
+		let response = self.post_oauth_authorize(&consent).await;
+		assert_eq!(response.status().as_u16(), 200, "Failed to consent");
+
+		let redirect_to = response.json::<ConsentResponse>().await.unwrap().redirect_to;
+		let code = reqwest::Url::parse(&redirect_to).unwrap()
+			.query_pairs()
+			.find(|(key, _)| key == "code")
+			.map(|(_, value)| value.into_owned())
+			.expect("Missing code");
+
+		let form = [
+			("grant_type", "authorization_code"),
+			("code", &code),
+			("redirect_uri", REDIRECT_URI),
+			("client_id", TEST_CLIENT_ID),
+			("code_verifier", CODE_VERIFIER),
+		];
+		let response = self.post_oauth_token(&form).await;
+		assert_eq!(response.status().as_u16(), 200, "Failed to exchange code");
+		response.json().await.unwrap()
+	}
+
+	pub async fn get_recovery_codes(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/recovery-codes", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_recovery_codes(&self) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/recovery-codes", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_two_fa_channel(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/2fa/channel", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn put_two_fa_channel<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.put(format!("{}/2fa/channel", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_sessions(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/sessions", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn delete_session(&self, id: &str) -> reqwest::Response {
+		self.http_client
+			.delete(format!("{}/sessions/{}", self.address, id))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn delete_sessions(&self) -> reqwest::Response {
+		self.http_client
+			.delete(format!("{}/sessions", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_trusted_devices(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/trusted-devices", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn delete_trusted_device(&self, id: &str) -> reqwest::Response {
+		self.http_client
+			.delete(format!("{}/trusted-devices/{}", self.address, id))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn delete_trusted_devices(&self) -> reqwest::Response {
+		self.http_client
+			.delete(format!("{}/trusted-devices", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_passkeys(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/passkeys", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn delete_passkey(&self, id: &str) -> reqwest::Response {
+		self.http_client
+			.delete(format!("{}/passkeys/{}", self.address, id))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_passkey_register_start(&self) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/passkeys/register/start", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_passkey_register_finish<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/passkeys/register/finish", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_passkey_login_start<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/passkeys/login/start", self.address))
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_passkey_login_finish<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/passkeys/login/finish", self.address))
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_user_roles(&self, email: &str) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/admin/users/{}/roles", self.address, email))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn put_user_roles<Body: serde::Serialize>(&self, email: &str, body: &Body) -> reqwest::Response {
+		self.http_client
+			.put(format!("{}/admin/users/{}/roles", self.address, email))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_admin_audit_events<Query: serde::Serialize + ?Sized>(&self, query: &Query) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/admin/audit-events", self.address))
+			.query(query)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_admin_users<Query: serde::Serialize + ?Sized>(&self, query: &Query) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/admin/users", self.address))
+			.query(query)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_admin_user(&self, email: &str) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/admin/users/{}", self.address, email))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn put_admin_user_status<Body: serde::Serialize>(&self, email: &str, body: &Body) -> reqwest::Response {
+		self.http_client
+			.put(format!("{}/admin/users/{}/status", self.address, email))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	// `action` is one of `disable`, `enable`, `force-password-reset`, `reset-2fa` and `unlock`
+	pub async fn post_admin_user_action(&self, email: &str, action: &str) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/admin/users/{}/{}", self.address, email, action))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_impersonate(&self, email: &str) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/admin/users/{}/impersonate", self.address, email))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn post_password<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+		self.http_client
+			.post(format!("{}/password", self.address))
+			.header(CSRF_HEADER_NAME, self.csrf_token())
+			.json(body)
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_openid_configuration(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/.well-known/openid-configuration", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
+
+	pub async fn get_jwks(&self) -> reqwest::Response {
+		self.http_client
+			.get(format!("{}/.well-known/jwks.json", self.address))
+			.send()
+			.await
+			.expect("Failed to execute request.")
+	}
 }
 
+// Example PKCE pair from RFC 7636 appendix B
+pub const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+pub const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+pub const REDIRECT_URI: &str = "http://localhost:8000/callback";
+pub const TEST_CLIENT_ID: &str = "test-client";
+
 pub fn get_random_email() -> String {
 	format!("{}@example.com", Uuid::new_v4())
 }
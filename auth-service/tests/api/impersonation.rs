@@ -0,0 +1,200 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use auth_service::{
+	AccountStatus, AppState, AuditEvent, AuditEventKind, AuditQuery, AuditSink, AuditSinkError, Email, ImpersonationResponse,
+	Role, JWT_COOKIE_NAME,
+};
+use reqwest::cookie::Jar;
+use tokio::sync::RwLock;
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn signup(app: &TestApp, email: &str) {
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+}
+
+// Sign up a user with the role and log in as them
+async fn login_with_role(app: &TestApp, role: Role) -> String {
+	let email = get_random_email();
+	signup(app, &email).await;
+	app.grant_role(&email, role).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+	email
+}
+
+async fn impersonate(app: &TestApp, email: &str) -> String {
+	let response = app.post_impersonate(email).await;
+	assert_eq!(response.status().as_u16(), 200);
+	response.json::<ImpersonationResponse>().await.unwrap().token
+}
+
+// Browser of the support agent, logged in with the impersonation token
+fn impersonating_client(app: &TestApp, token: &str) -> reqwest::Client {
+	let jar = Jar::default();
+	jar.add_cookie_str(&format!("{JWT_COOKIE_NAME}={token}"), &app.address.parse().unwrap());
+	reqwest::Client::builder().cookie_provider(Arc::new(jar)).build().unwrap()
+}
+
+// An audit log that can't be written to
+struct FailingAuditSink;
+
+#[async_trait::async_trait]
+impl AuditSink for FailingAuditSink {
+	async fn record(&mut self, _event: AuditEvent) -> Result<(), AuditSinkError> {
+		Err(AuditSinkError::UnexpectedError)
+	}
+
+	async fn query(&self, _query: &AuditQuery) -> Result<Vec<AuditEvent>, AuditSinkError> {
+		Err(AuditSinkError::UnexpectedError)
+	}
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let response = app.post_impersonate(&get_random_email()).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_403_without_permission() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	let target = get_random_email();
+	signup(&app, &target).await;
+
+	let response = app.post_impersonate(&target).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_mark_token_as_impersonated() {
+	let app = TestApp::new().await;
+	let agent = login_with_role(&app, Role::Support).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let token = impersonate(&app, &email).await;
+	let response = app.post_verify_token(&serde_json::json!({"token": token})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let body = response.json::<serde_json::Value>().await.unwrap();
+	assert_eq!(body["email"], email.as_str());
+	assert_eq!(body["impersonated"], true);
+	assert_eq!(body["impersonatedBy"], agent.as_str());
+}
+
+#[tokio::test]
+async fn should_not_mark_regular_token_as_impersonated() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	let token = response.cookies().find(|cookie| cookie.name() == JWT_COOKIE_NAME).unwrap().value().to_string();
+	let response = app.post_verify_token(&serde_json::json!({"token": token})).await;
+	let body = response.json::<serde_json::Value>().await.unwrap();
+	assert_eq!(body["impersonated"], false);
+	assert!(body.get("impersonatedBy").is_none());
+}
+
+#[tokio::test]
+async fn should_record_impersonation_in_audit_log() {
+	let app = TestApp::new().await;
+	let agent = login_with_role(&app, Role::Support).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	impersonate(&app, &email).await;
+	let query = AuditQuery {
+		email: Some(Email::from_str(&email).unwrap()),
+		kind: Some(AuditEventKind::ImpersonationStarted),
+		limit: 10,
+		..Default::default()
+	};
+	let events = app.audit_sink.read().await.query(&query).await.unwrap();
+	assert_eq!(events.len(), 1);
+	assert_eq!(events[0].actor.as_ref().map(|actor| actor.as_ref()), Some(agent.as_str()));
+}
+
+#[tokio::test]
+async fn should_not_change_credentials_while_impersonating() {
+	let app = TestApp::new().await;
+	login_with_role(&app, Role::Support).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	let client = impersonating_client(&app, &impersonate(&app, &email).await);
+
+	// Looking around is fine
+	let response = client.get(format!("{}/profile", app.address)).send().await.unwrap();
+	assert_eq!(response.status().as_u16(), 200);
+
+	let response = client
+		.put(format!("{}/2fa/channel", app.address))
+		.json(&serde_json::json!({"channel": "email"}))
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(response.status().as_u16(), 403);
+	let response = client.post(format!("{}/recovery-codes", app.address)).send().await.unwrap();
+	assert_eq!(response.status().as_u16(), 403);
+	let response = client.post(format!("{}/passkeys/register/start", app.address)).send().await.unwrap();
+	assert_eq!(response.status().as_u16(), 403);
+	let response = client.delete(format!("{}/passkeys/AAAA", app.address)).send().await.unwrap();
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_not_carry_permissions_of_impersonated_admin() {
+	let app = TestApp::new().await;
+	login_with_role(&app, Role::Support).await;
+	let admin = get_random_email();
+	signup(&app, &admin).await;
+	app.grant_role(&admin, Role::Admin).await;
+
+	let token = impersonate(&app, &admin).await;
+	let response = reqwest::Client::new()
+		.get(format!("{}/admin/users", app.address))
+		.bearer_auth(token)
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_reject_invalid_targets() {
+	let app = TestApp::new().await;
+	let agent = login_with_role(&app, Role::Support).await;
+
+	let response = app.post_impersonate(&agent).await;
+	assert_eq!(response.status().as_u16(), 400);
+	let response = app.post_impersonate(&get_random_email()).await;
+	assert_eq!(response.status().as_u16(), 404);
+
+	let email = get_random_email();
+	signup(&app, &email).await;
+	app.set_account_status(&email, AccountStatus::Disabled).await;
+	let response = app.post_impersonate(&email).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_not_start_session_if_audit_fails() {
+	let state = AppState::default().with_audit_sink(Arc::new(RwLock::new(Box::new(FailingAuditSink))));
+	let session_store = state.session_store.clone();
+	let app = TestApp::with_state(state).await;
+	login_with_role(&app, Role::Support).await;
+	let target = get_random_email();
+	signup(&app, &target).await;
+
+	let response = app.post_impersonate(&target).await;
+	assert_eq!(response.status().as_u16(), 500);
+	let sessions = session_store.read().await.get_sessions(&Email::from_str(&target).unwrap()).await.unwrap();
+	assert!(sessions.is_empty());
+}
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use crate::helpers::{get_random_email, TestApp};
-use auth_service::{Email, TwoFactorAuthResponse};
+use auth_service::{AccountStatus, Email, ErrorResponse, TwoFactorAuthResponse};
 
 #[tokio::test]
 async fn should_return_422_if_malformed_input() {
@@ -85,3 +85,23 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
 
 	let _ = app.two_fa_code_store.read().await.get_code(&Email::from_str(&random_email).unwrap()).await.unwrap();
 }
+
+#[tokio::test]
+async fn should_return_403_if_account_not_active() {
+	let app = TestApp::new().await;
+	let random_email = get_random_email();
+	let user_payload = serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+
+	let login_payload = serde_json::json!({"email": random_email, "password": "password123"});
+	for (status, error) in [
+		(AccountStatus::Disabled, "This account has been disabled"),
+		(AccountStatus::PendingVerification, "This account has not been verified yet"),
+	] {
+		app.set_account_status(&random_email, status).await;
+		let response = app.post_login(&login_payload).await;
+		assert_eq!(response.status().as_u16(), 403);
+		assert_eq!(response.json::<ErrorResponse>().await.unwrap().error, error);
+	}
+}
@@ -1,7 +1,7 @@
-use reqwest::{cookie::CookieStore, Url};
+use reqwest::Url;
 
 use crate::helpers::TestApp;
-use auth_service::JWT_COOKIE_NAME;
+use auth_service::{AppState, Config, CookieSameSite, CSRF_COOKIE_NAME, JWT_COOKIE_NAME};
 
 #[tokio::test]
 async fn should_return_400_if_jwt_cookie_missing() {
@@ -15,10 +15,9 @@ async fn should_return_400_if_jwt_cookie_missing() {
 async fn should_return_401_if_invalid_token() {
 	let app = TestApp::new().await;
 
-	app.cookie_jar.add_cookie_str(
-		&format!("{JWT_COOKIE_NAME}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/"),
-		&Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
-	);
+	let url = Url::parse("http://127.0.0.1").expect("Failed to parse URL");
+	app.cookie_jar.add_cookie_str(&format!("{JWT_COOKIE_NAME}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/"), &url);
+	app.cookie_jar.add_cookie_str(&format!("{CSRF_COOKIE_NAME}=csrf; SameSite=Lax; Path=/"), &url);
 
 	let response = app.post_logout().await;
 	assert_eq!(response.status().as_u16(), 401);
@@ -35,14 +34,13 @@ async fn should_return_200_if_valid_jwt_cookie() {
 	let response = app.post_login(&login_payload).await;
 	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
 
-	let header = app.cookie_jar.cookies(&"http://127.0.0.1".parse().unwrap()).unwrap();
-	let token = header.to_str().unwrap().split('=').collect::<Vec<&str>>()[1];
+	let token = app.auth_token();
 
 	let response = app.post_logout().await;
 	assert_eq!(response.status().as_u16(), 200);
 
 	let banned_store = app.banned_token_store.read().await;
-	assert!(banned_store.check(token).await.is_err());
+	assert!(banned_store.check(&token).await.is_err());
 }
 
 #[tokio::test]
@@ -62,3 +60,76 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
 	let response = app.post_logout().await;
 	assert_eq!(response.status().as_u16(), 400);
 }
+
+#[tokio::test]
+async fn should_clear_cookie_with_the_attributes_it_was_set_with() {
+	let mut config = Config::default();
+	config.cookies.domain = Some("example.com".to_string());
+	config.cookies.same_site = CookieSameSite::Strict;
+	let app = TestApp::with_state(AppState::default().with_config(config)).await;
+
+	let user_payload = serde_json::json!({"email": "hello@world.com", "password": "password123", "requires2FA": false});
+	app.post_signup(&user_payload).await;
+	let login_payload = serde_json::json!({"email": "hello@world.com", "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	let cookie = response.headers().get("set-cookie").unwrap().to_str().unwrap().to_owned();
+	assert!(cookie.contains("Domain=example.com"), "Unexpected cookie: {cookie}");
+	assert!(cookie.contains("SameSite=Strict"), "Unexpected cookie: {cookie}");
+	assert!(cookie.contains("Max-Age=600"), "Unexpected cookie: {cookie}");
+
+	// The client's cookie store won't keep cookies for another domain, so they're handed over directly
+	let url = Url::parse("http://127.0.0.1").expect("Failed to parse URL");
+	app.cookie_jar.add_cookie_str(cookie.split(';').next().unwrap(), &url);
+	app.cookie_jar.add_cookie_str(&format!("{CSRF_COOKIE_NAME}=csrf"), &url);
+
+	let response = app.post_logout().await;
+	assert_eq!(response.status().as_u16(), 200);
+	let removal = response.headers().get("set-cookie").unwrap().to_str().unwrap();
+	for attribute in [&format!("{JWT_COOKIE_NAME}=;"), "Domain=example.com", "Path=/", "SameSite=Strict", "HttpOnly", "Max-Age=0"] {
+		assert!(removal.contains(attribute), "Missing {attribute}: {removal}");
+	}
+}
+
+#[tokio::test]
+async fn should_ban_bearer_token_and_keep_cookie() {
+	let app = TestApp::new().await;
+
+	let user_payload = serde_json::json!({"email": "hello@world.com", "password": "password123", "requires2FA": false});
+	app.post_signup(&user_payload).await;
+	let login_payload = serde_json::json!({"email": "hello@world.com", "password": "password123"});
+	app.post_login(&login_payload).await;
+	let cookie_token = app.auth_token();
+	// A second session, used as a bearer token by a client without cookies
+	let bearer_token = app.auth_token_for(&login_payload).await;
+
+	let response = reqwest::Client::new()
+		.post(format!("{}/logout", app.address))
+		.bearer_auth(&bearer_token)
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+	assert!(response.headers().get("set-cookie").is_none());
+
+	let banned_store = app.banned_token_store.read().await;
+	assert!(banned_store.check(&bearer_token).await.is_err());
+	assert!(banned_store.check(&cookie_token).await.is_ok());
+}
+
+#[tokio::test]
+async fn should_not_fall_back_to_cookie_if_bearer_token_invalid() {
+	let app = TestApp::new().await;
+
+	let user_payload = serde_json::json!({"email": "hello@world.com", "password": "password123", "requires2FA": false});
+	app.post_signup(&user_payload).await;
+	let login_payload = serde_json::json!({"email": "hello@world.com", "password": "password123"});
+	app.post_login(&login_payload).await;
+
+	let response = app.http_client
+		.post(format!("{}/logout", app.address))
+		.bearer_auth("invalid")
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 401);
+}
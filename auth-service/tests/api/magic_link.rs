@@ -0,0 +1,117 @@
+use auth_service::{MagicLinkResponse, JWT_COOKIE_NAME};
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Pull the token out of the most recently emailed login link
+fn magic_link_token(app: &TestApp) -> String {
+	let email = app.email_client.sent_emails().pop().expect("No email sent");
+	let link = email.content
+		.split_whitespace()
+		.find(|word| word.contains("/login/magic-link/callback"))
+		.expect("No link in email");
+
+	reqwest::Url::parse(link).unwrap()
+		.query_pairs()
+		.find(|(key, _)| key == "token")
+		.map(|(_, value)| value.into_owned())
+		.expect("Missing token")
+}
+
+#[tokio::test]
+async fn should_create_passwordless_account() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": null, "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201);
+
+	// There's no password that could log this account in
+	let response = app.post_login(&serde_json::json!({"email": email, "password": ""})).await;
+	assert_eq!(response.status().as_u16(), 400);
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_400_for_passwordless_account_with_2fa() {
+	let app = TestApp::new().await;
+
+	let response = app.post_signup(&serde_json::json!({"email": get_random_email(), "password": null, "requires2FA": true})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_log_in_through_magic_link() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": null, "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201);
+
+	let response = app.post_magic_link(&serde_json::json!({"email": email})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let sent = app.email_client.sent_emails();
+	assert_eq!(sent.len(), 1);
+	assert_eq!(sent[0].recipient, email);
+
+	let response = app.get_magic_link_callback(&magic_link_token(&app)).await;
+	assert_eq!(response.status().as_u16(), 303);
+	assert_eq!(response.headers().get("location").unwrap(), "/");
+	let auth_cookie = response.cookies()
+		.find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+		.expect("No auth cookie found");
+	assert!(!auth_cookie.value().is_empty());
+
+	// The cookie is a regular session cookie
+	let response = app.get_sessions().await;
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_only_accept_magic_link_once() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201);
+	app.post_magic_link(&serde_json::json!({"email": email})).await;
+	let token = magic_link_token(&app);
+
+	let response = app.get_magic_link_callback(&token).await;
+	assert_eq!(response.status().as_u16(), 303);
+
+	let response = app.get_magic_link_callback(&token).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_for_invalid_token() {
+	let app = TestApp::new().await;
+
+	let response = app.get_magic_link_callback("invalid").await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_not_reveal_unknown_or_2fa_accounts() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	assert_eq!(response.status().as_u16(), 201);
+
+	for email in [email, get_random_email()] {
+		let response = app.post_magic_link(&serde_json::json!({"email": email})).await;
+		assert_eq!(response.status().as_u16(), 200);
+		response.json::<MagicLinkResponse>().await.expect("Could not deserialize response body");
+	}
+	assert!(app.email_client.sent_emails().is_empty());
+}
+
+#[tokio::test]
+async fn should_return_400_for_malformed_email() {
+	let app = TestApp::new().await;
+
+	let response = app.post_magic_link(&serde_json::json!({"email": "not-an-email"})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
@@ -1,7 +1,29 @@
+mod admin_users;
+mod audit_events;
+mod csrf;
+mod health;
 mod helpers;
+mod impersonation;
 mod login;
 mod logout;
+mod magic_link;
+mod metrics;
+mod oauth_authorize;
+mod oauth_device_authorization;
+mod oauth_token;
+mod openid_configuration;
+mod passkeys;
+mod profile;
+mod recovery_codes;
+mod resend_2fa;
+mod roles;
 mod root;
+mod sessions;
+mod shutdown;
 mod signup;
+mod tls;
+mod trusted_devices;
+mod two_fa_channel;
+mod userinfo;
 mod verify_2fa;
 mod verify_token;
@@ -0,0 +1,83 @@
+use std::str::FromStr;
+
+use auth_service::Email;
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn scrape(app: &TestApp) -> String {
+	let response = app.get_metrics().await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+	response.text().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_count_signups_and_logins() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "wrongpassword"})).await;
+	assert_eq!(response.status().as_u16(), 401);
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204);
+
+	let metrics = scrape(&app).await;
+	assert!(metrics.contains("auth_signups_total 1"));
+	assert!(metrics.contains(r#"auth_logins_total{outcome="success"} 1"#));
+	assert!(metrics.contains(r#"auth_logins_total{outcome="failure"} 1"#));
+	assert!(metrics.contains("auth_password_check_duration_seconds_count 2"));
+	assert!(metrics.contains("auth_users 1"));
+}
+
+#[tokio::test]
+async fn should_count_2fa_verifications() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 206);
+
+	let metrics = scrape(&app).await;
+	assert!(metrics.contains(r#"auth_logins_total{outcome="2fa_required"} 1"#));
+	assert!(metrics.contains("auth_pending_two_fa_codes 1"));
+
+	let (login_attempt_id, code) = app.two_fa_code_store
+		.read().await
+		.get_code(&Email::from_str(&email).unwrap()).await
+		.unwrap();
+	let response = app.post_verify_2fa(&serde_json::json!({"email": email, "loginAttemptId": login_attempt_id.as_ref(), "2FACode": "000000"})).await;
+	assert_eq!(response.status().as_u16(), 401);
+	let response = app.post_verify_2fa(&serde_json::json!({"email": email, "loginAttemptId": login_attempt_id.as_ref(), "2FACode": code.as_ref()})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let metrics = scrape(&app).await;
+	assert!(metrics.contains(r#"auth_two_fa_verifications_total{outcome="failure"} 1"#));
+	assert!(metrics.contains(r#"auth_two_fa_verifications_total{outcome="success"} 1"#));
+	assert!(metrics.contains("auth_pending_two_fa_codes 0"));
+}
+
+#[tokio::test]
+async fn should_count_token_validations_and_bans() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	let response = app.post_logout().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let metrics = scrape(&app).await;
+	assert!(metrics.contains(r#"auth_token_validations_total{outcome="valid"} 1"#));
+	assert!(metrics.contains("auth_tokens_banned_total 1"));
+	assert!(metrics.contains("auth_banned_tokens 1"));
+}
+
+#[tokio::test]
+async fn should_time_requests_by_route() {
+	let app = TestApp::new().await;
+	app.post_login(&serde_json::json!({"email": get_random_email(), "password": "password123"})).await;
+
+	let metrics = scrape(&app).await;
+	assert!(metrics.contains(
+		r#"auth_http_request_duration_seconds_count{method="POST",path="/login",status="401"} 1"#
+	));
+}
@@ -0,0 +1,145 @@
+use auth_service::ConsentResponse;
+
+use crate::helpers::{get_random_email, TestApp, CODE_CHALLENGE, REDIRECT_URI, TEST_CLIENT_ID};
+
+fn authorize_query() -> serde_json::Value {
+	serde_json::json!({
+		"response_type": "code",
+		"client_id": TEST_CLIENT_ID,
+		"redirect_uri": REDIRECT_URI,
+		"scope": "openid email",
+		"state": "xyz",
+		"code_challenge": CODE_CHALLENGE,
+		"code_challenge_method": "S256",
+		"nonce": "n-0S6_WzA2Mj",
+	})
+}
+
+async fn log_in(app: &TestApp) {
+	let random_email = get_random_email();
+	let user_payload = serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": random_email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+}
+
+fn location(response: &reqwest::Response) -> String {
+	response.headers().get("location").expect("Missing location header").to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn should_redirect_to_login_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let response = app.get_oauth_authorize(&authorize_query()).await;
+	assert_eq!(response.status().as_u16(), 303);
+	assert!(location(&response).starts_with("/?next=%2Foauth%2Fauthorize%3F"));
+}
+
+#[tokio::test]
+async fn should_redirect_to_consent_if_logged_in() {
+	let app = TestApp::new().await;
+	log_in(&app).await;
+
+	let response = app.get_oauth_authorize(&authorize_query()).await;
+	assert_eq!(response.status().as_u16(), 303);
+	assert!(location(&response).starts_with("/consent.html?response_type=code&client_id=test-client"));
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_redirect_uri() {
+	let app = TestApp::new().await;
+
+	let test_cases = ["", "not a url", "http://localhost:8000/other", "http://localhost:8000/callback#fragment"];
+
+	for redirect_uri in test_cases {
+		let mut query = authorize_query();
+		query["redirect_uri"] = serde_json::json!(redirect_uri);
+		let response = app.get_oauth_authorize(&query).await;
+		assert_eq!(response.status().as_u16(), 400, "Failed for redirect_uri: {redirect_uri:?}");
+	}
+}
+
+#[tokio::test]
+async fn should_return_400_if_unknown_client() {
+	let app = TestApp::new().await;
+
+	let mut query = authorize_query();
+	query["client_id"] = serde_json::json!("unknown-client");
+	let response = app.get_oauth_authorize(&query).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_redirect_with_error_if_invalid_request() {
+	let app = TestApp::new().await;
+
+	let test_cases = [
+		("response_type", serde_json::json!("token"), "unsupported_response_type"),
+		("scope", serde_json::json!("email"), "invalid_scope"),
+		("scope", serde_json::json!("openid admin"), "invalid_scope"),
+		("code_challenge", serde_json::Value::Null, "invalid_request"),
+		("code_challenge_method", serde_json::json!("plain"), "invalid_request"),
+	];
+
+	for (field, value, error) in test_cases {
+		let mut query = authorize_query();
+		if value.is_null() {
+			query.as_object_mut().unwrap().remove(field);
+		} else {
+			query[field] = value;
+		}
+		let response = app.get_oauth_authorize(&query).await;
+		assert_eq!(response.status().as_u16(), 303, "Failed for field: {field}");
+
+		let location = location(&response);
+		assert!(location.starts_with(REDIRECT_URI), "Failed for field: {field}");
+		assert!(location.contains(&format!("error={error}")), "Failed for field: {field}");
+		assert!(location.contains("state=xyz"), "Failed for field: {field}");
+	}
+}
+
+#[tokio::test]
+async fn should_return_401_on_consent_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let mut consent = authorize_query();
+	consent["approve"] = serde_json::json!(true);
+	let response = app.post_oauth_authorize(&consent).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_redirect_with_code_on_consent() {
+	let app = TestApp::new().await;
+	log_in(&app).await;
+
+	let mut consent = authorize_query();
+	consent["approve"] = serde_json::json!(true);
+	let response = app.post_oauth_authorize(&consent).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let redirect_to = response.json::<ConsentResponse>().await.unwrap().redirect_to;
+	let redirect_to = reqwest::Url::parse(&redirect_to).unwrap();
+	let params: Vec<(String, String)> = redirect_to.query_pairs().into_owned().collect();
+	assert!(redirect_to.as_str().starts_with(REDIRECT_URI));
+	assert!(params.iter().any(|(key, value)| key == "code" && value.len() == 32));
+	assert!(params.contains(&("state".to_string(), "xyz".to_string())));
+}
+
+#[tokio::test]
+async fn should_redirect_with_access_denied_on_refusal() {
+	let app = TestApp::new().await;
+	log_in(&app).await;
+
+	let mut consent = authorize_query();
+	consent["approve"] = serde_json::json!(false);
+	let response = app.post_oauth_authorize(&consent).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let redirect_to = response.json::<ConsentResponse>().await.unwrap().redirect_to;
+	assert!(redirect_to.contains("error=access_denied"));
+	assert!(!redirect_to.contains("code="));
+}
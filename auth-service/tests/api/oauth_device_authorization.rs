@@ -0,0 +1,158 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use auth_service::{AppState, DeviceAuthorizationResponse, Email, OAuthErrorResponse, RateLimiter, TokenResponse};
+
+use crate::helpers::{get_random_email, TestApp, TEST_CLIENT_ID};
+
+const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+async fn start_device_flow(app: &TestApp) -> DeviceAuthorizationResponse {
+	let form = [("client_id", TEST_CLIENT_ID), ("scope", "openid email")];
+	let response = app.post_oauth_device_authorization(&form).await;
+	assert_eq!(response.status().as_u16(), 200, "Failed to start device flow");
+	response.json().await.unwrap()
+}
+
+async fn poll(app: &TestApp, device_code: &str) -> reqwest::Response {
+	let form = [("grant_type", GRANT_TYPE), ("client_id", TEST_CLIENT_ID), ("device_code", device_code)];
+	app.post_oauth_token(&form).await
+}
+
+async fn log_in(app: &TestApp, email: &str, requires_2fa: bool) {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": requires_2fa});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+
+	if requires_2fa {
+		assert_eq!(response.status().as_u16(), 206, "Failed to log in");
+		let (login_attempt_id, code) = {
+			let two_fa_code_store = app.two_fa_code_store.read().await;
+			two_fa_code_store.get_code(&Email::from_str(email).unwrap()).await.unwrap()
+		};
+		let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id.as_ref(), "2FACode": code.as_ref()});
+		let response = app.post_verify_2fa(&payload).await;
+		assert_eq!(response.status().as_u16(), 200, "Failed to verify 2FA");
+	} else {
+		assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+	}
+}
+
+#[tokio::test]
+async fn should_return_device_and_user_codes() {
+	let app = TestApp::new().await;
+
+	let device = start_device_flow(&app).await;
+	assert_eq!(device.device_code.len(), 40);
+	assert_eq!(device.user_code.len(), 9);
+	assert!(device.verification_uri.ends_with("/device.html"));
+	assert!(device.verification_uri_complete.ends_with(&format!("?user_code={}", device.user_code)));
+	assert_eq!(device.interval, 5);
+}
+
+#[tokio::test]
+async fn should_return_401_if_unknown_client() {
+	let app = TestApp::new().await;
+
+	let form = [("client_id", "unknown")];
+	let response = app.post_oauth_device_authorization(&form).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_429_if_too_many_device_authorizations() {
+	let limiter = RateLimiter::new(2, Duration::from_secs(60));
+	let app = TestApp::with_state(AppState::default().with_device_authorization_limiter(limiter)).await;
+
+	start_device_flow(&app).await;
+	start_device_flow(&app).await;
+	let form = [("client_id", TEST_CLIENT_ID), ("scope", "openid email")];
+	let response = app.post_oauth_device_authorization(&form).await;
+	assert_eq!(response.status().as_u16(), 429);
+	let error: OAuthErrorResponse = response.json().await.unwrap();
+	assert_eq!(error.error, "temporarily_unavailable");
+}
+
+#[tokio::test]
+async fn should_return_authorization_pending_then_slow_down() {
+	let app = TestApp::new().await;
+	let device = start_device_flow(&app).await;
+
+	let response = poll(&app, &device.device_code).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "authorization_pending");
+
+	let response = poll(&app, &device.device_code).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "slow_down");
+}
+
+#[tokio::test]
+async fn should_issue_tokens_after_approval_with_2fa() {
+	let app = TestApp::new().await;
+	let device = start_device_flow(&app).await;
+	log_in(&app, &get_random_email(), true).await;
+
+	// Users may type the code without the dash and in lowercase
+	let user_code = device.user_code.replace('-', "").to_lowercase();
+	let response = app.post_oauth_device_verify(&serde_json::json!({"userCode": user_code, "approve": true})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let response = poll(&app, &device.device_code).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let tokens = response.json::<TokenResponse>().await.unwrap();
+	assert_eq!(tokens.scope, "openid email");
+	assert!(tokens.id_token.is_some());
+
+	let response = app.post_verify_token(&serde_json::json!({"token": tokens.access_token})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	// The device code is single-use
+	let response = poll(&app, &device.device_code).await;
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn should_return_access_denied_after_denial() {
+	let app = TestApp::new().await;
+	let device = start_device_flow(&app).await;
+	log_in(&app, &get_random_email(), false).await;
+
+	let response = app.post_oauth_device_verify(&serde_json::json!({"userCode": device.user_code, "approve": false})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let response = poll(&app, &device.device_code).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "access_denied");
+}
+
+#[tokio::test]
+async fn should_return_400_if_verifying_without_login() {
+	let app = TestApp::new().await;
+	let device = start_device_flow(&app).await;
+
+	let response = app.post_oauth_device_verify(&serde_json::json!({"userCode": device.user_code, "approve": true})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_user_code() {
+	let app = TestApp::new().await;
+	let device = start_device_flow(&app).await;
+	log_in(&app, &get_random_email(), false).await;
+
+	let test_cases = ["", "BCDF", "AAAA-AAAA", "BCDF-GHJK"];
+
+	for user_code in test_cases {
+		let response = app.post_oauth_device_verify(&serde_json::json!({"userCode": user_code, "approve": true})).await;
+		assert_eq!(response.status().as_u16(), 400, "Failed for user code: {user_code:?}");
+	}
+
+	// A code can only be used once
+	let response = app.post_oauth_device_verify(&serde_json::json!({"userCode": device.user_code, "approve": true})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let response = app.post_oauth_device_verify(&serde_json::json!({"userCode": device.user_code, "approve": true})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
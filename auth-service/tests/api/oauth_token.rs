@@ -0,0 +1,242 @@
+use auth_service::{generate_secret, hash_secret, ConsentResponse, OAuthClient, OAuthErrorResponse, TokenResponse};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+
+use crate::helpers::{get_random_email, TestApp, CODE_CHALLENGE, CODE_VERIFIER, REDIRECT_URI, TEST_CLIENT_ID};
+
+// Log a new user in and walk through the consent screen to get an authorization code
+async fn obtain_code(app: &TestApp) -> String {
+	let random_email = get_random_email();
+	let user_payload = serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": random_email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+
+	let consent = serde_json::json!({
+		"response_type": "code",
+		"client_id": TEST_CLIENT_ID,
+		"redirect_uri": REDIRECT_URI,
+		"scope": "openid email",
+		"code_challenge": CODE_CHALLENGE,
+		"code_challenge_method": "S256",
+		"approve": true,
+	});
+	let response = app.post_oauth_authorize(&consent).await;
+	assert_eq!(response.status().as_u16(), 200, "Failed to consent");
+
+	let redirect_to = response.json::<ConsentResponse>().await.unwrap().redirect_to;
+	reqwest::Url::parse(&redirect_to).unwrap()
+		.query_pairs()
+		.find(|(key, _)| key == "code")
+		.map(|(_, value)| value.into_owned())
+		.expect("Missing code")
+}
+
+fn token_form(code: &str) -> Vec<(&'static str, String)> {
+	vec![
+		("grant_type", "authorization_code".to_string()),
+		("code", code.to_string()),
+		("redirect_uri", REDIRECT_URI.to_string()),
+		("client_id", TEST_CLIENT_ID.to_string()),
+		("code_verifier", CODE_VERIFIER.to_string()),
+	]
+}
+
+#[tokio::test]
+async fn should_return_200_and_tokens_if_valid_code() {
+	let app = TestApp::new().await;
+	let code = obtain_code(&app).await;
+
+	let response = app.post_oauth_token(&token_form(&code)).await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+
+	let tokens = response.json::<TokenResponse>().await.unwrap();
+	assert_eq!(tokens.token_type, "Bearer");
+	assert_eq!(tokens.scope, "openid email");
+	assert_eq!(tokens.id_token.expect("Missing ID token").split('.').count(), 3);
+
+	let response = app.post_verify_token(&serde_json::json!({"token": tokens.access_token})).await;
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_code_reused() {
+	let app = TestApp::new().await;
+	let code = obtain_code(&app).await;
+
+	let response = app.post_oauth_token(&token_form(&code)).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let response = app.post_oauth_token(&token_form(&code)).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn should_return_400_if_pkce_verification_fails() {
+	let app = TestApp::new().await;
+	let code = obtain_code(&app).await;
+
+	let mut form = token_form(&code);
+	form[4].1 = "x".repeat(43);
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn should_return_400_if_redirect_uri_mismatch() {
+	let app = TestApp::new().await;
+	let code = obtain_code(&app).await;
+
+	let mut form = token_form(&code);
+	form[2].1 = "http://localhost:8000/other".to_string();
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn should_return_400_if_missing_parameters() {
+	let app = TestApp::new().await;
+
+	let form = [("grant_type", "authorization_code"), ("client_id", TEST_CLIENT_ID)];
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_request");
+}
+
+#[tokio::test]
+async fn should_return_400_if_unsupported_grant_type() {
+	let app = TestApp::new().await;
+
+	let form = [("grant_type", "password"), ("client_id", TEST_CLIENT_ID)];
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "unsupported_grant_type");
+}
+
+// Register a confidential client and return its secret
+async fn register_service_client(app: &TestApp, client_id: &str) -> String {
+	let secret = generate_secret();
+	app.client_store.write().await
+		.add_client(OAuthClient {
+			client_id: client_id.to_string(),
+			secret_hash: Some(hash_secret(&secret)),
+			redirect_uris: vec![],
+			allowed_scopes: vec!["reports:read".to_string(), "reports:write".to_string()],
+		}).await
+		.expect("Failed to register client");
+	secret
+}
+
+#[tokio::test]
+async fn should_return_200_for_client_credentials_with_basic_auth() {
+	let app = TestApp::new().await;
+	let secret = register_service_client(&app, "service-client").await;
+
+	let response = app.http_client
+		.post(format!("{}/oauth/token", app.address))
+		.basic_auth("service-client", Some(&secret))
+		.form(&[("grant_type", "client_credentials"), ("scope", "reports:read")])
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+
+	let tokens = response.json::<TokenResponse>().await.unwrap();
+	assert_eq!(tokens.scope, "reports:read");
+	assert!(tokens.id_token.is_none());
+
+	// Service tokens carry no user, so they are not valid user tokens
+	let response = app.post_verify_token(&serde_json::json!({"token": tokens.access_token})).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_for_client_credentials_with_form_secret() {
+	let app = TestApp::new().await;
+	let secret = register_service_client(&app, "service-client").await;
+
+	let form = [
+		("grant_type", "client_credentials"),
+		("client_id", "service-client"),
+		("client_secret", &secret),
+	];
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	// Without an explicit scope the client gets everything it is allowed
+	let tokens = response.json::<TokenResponse>().await.unwrap();
+	assert_eq!(tokens.scope, "reports:read reports:write");
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_client_credentials() {
+	let app = TestApp::new().await;
+	let secret = register_service_client(&app, "service-client").await;
+
+	let test_cases = [
+		vec![("grant_type", "client_credentials"), ("client_id", "service-client"), ("client_secret", "wrong")],
+		vec![("grant_type", "client_credentials"), ("client_id", "service-client")],
+		vec![("grant_type", "client_credentials"), ("client_id", "unknown"), ("client_secret", &secret)],
+		vec![("grant_type", "client_credentials")],
+	];
+
+	for test_case in test_cases.iter() {
+		let response = app.post_oauth_token(test_case).await;
+		assert_eq!(response.status().as_u16(), 401, "Failed for input: {:?}", test_case);
+		assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_client");
+	}
+}
+
+#[tokio::test]
+async fn should_return_400_if_public_client_uses_client_credentials() {
+	let app = TestApp::new().await;
+
+	let form = [("grant_type", "client_credentials"), ("client_id", TEST_CLIENT_ID)];
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "unauthorized_client");
+}
+
+#[tokio::test]
+async fn should_return_400_if_scope_not_allowed() {
+	let app = TestApp::new().await;
+	let secret = register_service_client(&app, "service-client").await;
+
+	let form = [
+		("grant_type", "client_credentials"),
+		("client_id", "service-client"),
+		("client_secret", &secret),
+		("scope", "reports:read admin"),
+	];
+	let response = app.post_oauth_token(&form).await;
+	assert_eq!(response.status().as_u16(), 400);
+	assert_eq!(response.json::<OAuthErrorResponse>().await.unwrap().error, "invalid_scope");
+}
+
+#[tokio::test]
+async fn should_sign_id_token_with_published_key() {
+	let app = TestApp::new().await;
+	let code = obtain_code(&app).await;
+
+	let tokens = app.post_oauth_token(&token_form(&code)).await.json::<TokenResponse>().await.unwrap();
+	let id_token = tokens.id_token.expect("Missing ID token");
+
+	// What a relying party does: find the key the header names in the JWKS and check the signature with it
+	let jwks = app.get_jwks().await.json::<JwkSet>().await.unwrap();
+	let header = jsonwebtoken::decode_header(&id_token).unwrap();
+	assert_eq!(header.alg, Algorithm::ES256);
+	let jwk = jwks.find(&header.kid.expect("Missing kid")).expect("Unknown kid");
+
+	let mut validation = Validation::new(Algorithm::ES256);
+	validation.set_audience(&[TEST_CLIENT_ID]);
+	let claims = jsonwebtoken::decode::<serde_json::Value>(&id_token, &DecodingKey::from_jwk(jwk).unwrap(), &validation)
+		.unwrap()
+		.claims;
+	assert_eq!(claims["aud"], TEST_CLIENT_ID);
+}
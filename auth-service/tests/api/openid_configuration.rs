@@ -0,0 +1,43 @@
+use auth_service::{Config, OpenIdConfiguration};
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn should_return_discovery_document() {
+	let app = TestApp::new().await;
+
+	let response = app.get_openid_configuration().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let issuer = Config::default().auth.issuer;
+	let configuration = response.json::<OpenIdConfiguration>().await.unwrap();
+	assert_eq!(configuration.issuer, issuer);
+	assert_eq!(configuration.authorization_endpoint, format!("{issuer}/oauth/authorize"));
+	assert_eq!(configuration.token_endpoint, format!("{issuer}/oauth/token"));
+	assert_eq!(configuration.code_challenge_methods_supported, vec!["S256"]);
+}
+
+#[tokio::test]
+async fn should_publish_id_token_key() {
+	let app = TestApp::new().await;
+
+	let issuer = Config::default().auth.issuer;
+	let configuration = app.get_openid_configuration().await.json::<OpenIdConfiguration>().await.unwrap();
+	assert_eq!(configuration.jwks_uri, format!("{issuer}/.well-known/jwks.json"));
+	assert_eq!(configuration.id_token_signing_alg_values_supported, vec!["ES256"]);
+
+	let response = app.get_jwks().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let jwks = response.json::<JwkSet>().await.unwrap();
+	assert_eq!(jwks.keys.len(), 1);
+	let jwk = &jwks.keys[0];
+	assert!(jwk.common.key_id.is_some());
+	// Only the public half is published
+	let AlgorithmParameters::EllipticCurve(parameters) = &jwk.algorithm else {
+		panic!("Not an EC key: {jwk:?}");
+	};
+	assert!(!parameters.x.is_empty() && !parameters.y.is_empty());
+	assert!(!serde_json::to_string(jwk).unwrap().contains("\"d\""));
+}
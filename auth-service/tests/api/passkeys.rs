@@ -0,0 +1,349 @@
+use std::str::FromStr;
+
+use auth_service::{Config, Email, PasskeyAuthenticationOptions, PasskeyRegistrationOptions, PasskeyResponse};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use ciborium::Value;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use sha2::{Digest, Sha256};
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Stands in for a browser and security key, producing the same JSON the frontend would send
+struct SoftwareAuthenticator {
+	key: SigningKey,
+	credential_id: Vec<u8>,
+	sign_count: u32,
+	attestation_format: &'static str,
+}
+
+impl SoftwareAuthenticator {
+	fn new() -> Self {
+		Self {
+			key: SigningKey::from_slice(&rand::random::<[u8; 32]>()).unwrap(),
+			credential_id: rand::random::<[u8; 16]>().to_vec(),
+			sign_count: 0,
+			attestation_format: "none",
+		}
+	}
+
+	fn id(&self) -> String {
+		URL_SAFE_NO_PAD.encode(&self.credential_id)
+	}
+
+	fn client_data(ceremony: &str, challenge: &str) -> Vec<u8> {
+		serde_json::to_vec(&serde_json::json!({
+			"type": ceremony,
+			"challenge": challenge,
+			// Test apps run with the default issuer
+			"origin": Config::default().auth.issuer,
+		})).unwrap()
+	}
+
+	fn authenticator_data(&self, rp_id: &str, flags: u8) -> Vec<u8> {
+		let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+		data.push(flags);
+		data.extend_from_slice(&self.sign_count.to_be_bytes());
+		data
+	}
+
+	fn register(&mut self, options: &PasskeyRegistrationOptions) -> serde_json::Value {
+		self.sign_count += 1;
+		let point = self.key.verifying_key().to_encoded_point(false);
+		let public_key = Value::Map(vec![
+			(Value::from(1), Value::from(2)),
+			(Value::from(3), Value::from(-7)),
+			(Value::from(-1), Value::from(1)),
+			(Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+			(Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),
+		]);
+
+		// User present, user verified and attested credential data included
+		let mut auth_data = self.authenticator_data(&options.rp.id, 0x45);
+		auth_data.extend_from_slice(&[0; 16]);
+		auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+		auth_data.extend_from_slice(&self.credential_id);
+		ciborium::into_writer(&public_key, &mut auth_data).unwrap();
+
+		let attestation_object = Value::Map(vec![
+			(Value::from("fmt"), Value::from(self.attestation_format)),
+			(Value::from("attStmt"), Value::Map(vec![])),
+			(Value::from("authData"), Value::Bytes(auth_data)),
+		]);
+		let mut attestation_object_bytes = Vec::new();
+		ciborium::into_writer(&attestation_object, &mut attestation_object_bytes).unwrap();
+
+		serde_json::json!({
+			"id": self.id(),
+			"clientDataJSON": URL_SAFE_NO_PAD.encode(Self::client_data("webauthn.create", &options.challenge)),
+			"attestationObject": URL_SAFE_NO_PAD.encode(attestation_object_bytes),
+			"name": "Software key",
+		})
+	}
+
+	fn assert(&mut self, options: &PasskeyAuthenticationOptions, user_verified: bool) -> serde_json::Value {
+		self.sign_count += 1;
+		let flags = if user_verified { 0x05 } else { 0x01 };
+		let auth_data = self.authenticator_data(&options.rp_id, flags);
+		let client_data = Self::client_data("webauthn.get", &options.challenge);
+
+		let mut signed = auth_data.clone();
+		signed.extend_from_slice(&Sha256::digest(&client_data));
+		let signature: Signature = self.key.sign(&signed);
+
+		serde_json::json!({
+			"id": self.id(),
+			"clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+			"authenticatorData": URL_SAFE_NO_PAD.encode(auth_data),
+			"signature": URL_SAFE_NO_PAD.encode(signature.to_der().as_bytes()),
+		})
+	}
+}
+
+async fn signup_and_login(app: &TestApp, email: &str) {
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+}
+
+async fn register_passkey(app: &TestApp, authenticator: &mut SoftwareAuthenticator) -> reqwest::Response {
+	let response = app.post_passkey_register_start().await;
+	assert_eq!(response.status().as_u16(), 200, "Failed to start registration");
+	let options = response.json::<PasskeyRegistrationOptions>().await.unwrap();
+
+	app.post_passkey_register_finish(&authenticator.register(&options)).await
+}
+
+async fn login_options(app: &TestApp, email: Option<&str>) -> PasskeyAuthenticationOptions {
+	second_factor_options(app, email, None).await
+}
+
+async fn second_factor_options(app: &TestApp, email: Option<&str>, login_attempt_id: Option<&str>) -> PasskeyAuthenticationOptions {
+	let response = app.post_passkey_login_start(&serde_json::json!({"email": email, "loginAttemptId": login_attempt_id})).await;
+	assert_eq!(response.status().as_u16(), 200, "Failed to start login");
+	response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_register_and_list_passkey() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+
+	let response = register_passkey(&app, &mut authenticator).await;
+	assert_eq!(response.status().as_u16(), 201);
+	let passkey = response.json::<PasskeyResponse>().await.unwrap();
+	assert_eq!(passkey.id, authenticator.id());
+	assert_eq!(passkey.name.as_deref(), Some("Software key"));
+
+	let response = app.get_passkeys().await;
+	assert_eq!(response.status().as_u16(), 200);
+	let passkeys = response.json::<Vec<PasskeyResponse>>().await.unwrap();
+	assert_eq!(passkeys.len(), 1);
+	assert_eq!(passkeys[0].id, passkey.id);
+
+	// Already registered passkeys are excluded from the next registration
+	let response = app.post_passkey_register_start().await;
+	let options = response.json::<PasskeyRegistrationOptions>().await.unwrap();
+	assert_eq!(options.attestation, "none");
+	assert_eq!(options.exclude_credentials.len(), 1);
+	assert_eq!(options.exclude_credentials[0].id, passkey.id);
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let response = app.post_passkey_register_start().await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_reject_attestation_other_than_none() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	authenticator.attestation_format = "packed";
+
+	let response = register_passkey(&app, &mut authenticator).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_reject_reused_registration_challenge() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+
+	let response = app.post_passkey_register_start().await;
+	let options = response.json::<PasskeyRegistrationOptions>().await.unwrap();
+	let registration = authenticator.register(&options);
+
+	let response = app.post_passkey_register_finish(&registration).await;
+	assert_eq!(response.status().as_u16(), 201);
+	let response = app.post_passkey_register_finish(&registration).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_log_in_with_passkey_without_password() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	let response = register_passkey(&app, &mut authenticator).await;
+	assert_eq!(response.status().as_u16(), 201);
+
+	let response = app.post_logout().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	// Discoverable login, the authenticator picks the account
+	let options = login_options(&app, None).await;
+	assert!(options.allow_credentials.is_empty());
+	let response = app.post_passkey_login_finish(&authenticator.assert(&options, true)).await;
+	assert_eq!(response.status().as_u16(), 204);
+
+	let response = app.get_passkeys().await;
+	assert_eq!(response.status().as_u16(), 200);
+	let passkeys = response.json::<Vec<PasskeyResponse>>().await.unwrap();
+	assert!(passkeys[0].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn should_require_user_verification_for_passwordless_login() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	register_passkey(&app, &mut authenticator).await;
+
+	let options = login_options(&app, None).await;
+	let response = app.post_passkey_login_finish(&authenticator.assert(&options, false)).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_reject_replayed_assertion() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	register_passkey(&app, &mut authenticator).await;
+
+	let options = login_options(&app, None).await;
+	let assertion = authenticator.assert(&options, true);
+	let response = app.post_passkey_login_finish(&assertion).await;
+	assert_eq!(response.status().as_u16(), 204);
+
+	let response = app.post_passkey_login_finish(&assertion).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_reject_sign_count_regression() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	register_passkey(&app, &mut authenticator).await;
+
+	let options = login_options(&app, None).await;
+	let response = app.post_passkey_login_finish(&authenticator.assert(&options, true)).await;
+	assert_eq!(response.status().as_u16(), 204);
+
+	// A clone of the authenticator would still be at the old count
+	authenticator.sign_count = 0;
+	let options = login_options(&app, None).await;
+	let response = app.post_passkey_login_finish(&authenticator.assert(&options, true)).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_accept_passkey_as_second_factor() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	assert_eq!(response.status().as_u16(), 201);
+	let login = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login).await;
+	assert_eq!(response.status().as_u16(), 206);
+	let (login_attempt_id, code) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(&email).unwrap()).await
+		.unwrap();
+	let response = app.post_verify_2fa(&serde_json::json!({
+		"email": email,
+		"loginAttemptId": login_attempt_id.as_ref(),
+		"2FACode": code.as_ref(),
+	})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let mut authenticator = SoftwareAuthenticator::new();
+	let response = register_passkey(&app, &mut authenticator).await;
+	assert_eq!(response.status().as_u16(), 201);
+	app.post_logout().await;
+
+	let response = app.post_login(&login).await;
+	assert_eq!(response.status().as_u16(), 206);
+	let (login_attempt_id, _) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(&email).unwrap()).await
+		.unwrap();
+
+	// The passkeys are only listed for the pending login
+	assert!(login_options(&app, Some(&email)).await.allow_credentials.is_empty());
+	let other_attempt = uuid::Uuid::new_v4().to_string();
+	assert!(second_factor_options(&app, Some(&email), Some(&other_attempt)).await.allow_credentials.is_empty());
+	let options = second_factor_options(&app, Some(&email), Some(login_attempt_id.as_ref())).await;
+	assert_eq!(options.allow_credentials.len(), 1);
+	// Presence is enough for the second factor
+	let response = app.post_verify_2fa(&serde_json::json!({
+		"email": email,
+		"loginAttemptId": login_attempt_id.as_ref(),
+		"passkey": authenticator.assert(&options, false),
+	})).await;
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_reject_other_users_passkey_as_second_factor() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	register_passkey(&app, &mut authenticator).await;
+	app.post_logout().await;
+
+	let email = get_random_email();
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	assert_eq!(response.status().as_u16(), 201);
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 206);
+	let (login_attempt_id, _) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(&email).unwrap()).await
+		.unwrap();
+
+	let options = login_options(&app, None).await;
+	let response = app.post_verify_2fa(&serde_json::json!({
+		"email": email,
+		"loginAttemptId": login_attempt_id.as_ref(),
+		"passkey": authenticator.assert(&options, true),
+	})).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_remove_passkey() {
+	let app = TestApp::new().await;
+	signup_and_login(&app, &get_random_email()).await;
+	let mut authenticator = SoftwareAuthenticator::new();
+	register_passkey(&app, &mut authenticator).await;
+
+	let response = app.delete_passkey(&authenticator.id()).await;
+	assert_eq!(response.status().as_u16(), 204);
+	let response = app.delete_passkey(&authenticator.id()).await;
+	assert_eq!(response.status().as_u16(), 404);
+
+	let passkeys = app.get_passkeys().await.json::<Vec<PasskeyResponse>>().await.unwrap();
+	assert!(passkeys.is_empty());
+
+	let options = login_options(&app, None).await;
+	let response = app.post_passkey_login_finish(&authenticator.assert(&options, true)).await;
+	assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,149 @@
+use std::str::FromStr;
+
+use auth_service::{AccountStatus, Email, ProfileResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn log_in(app: &TestApp, email: &str, requires_2fa: bool) {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": requires_2fa});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert!(response.status().is_success(), "Failed to log in");
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+	let app = TestApp::new().await;
+
+	let response = app.get_profile().await;
+	assert_eq!(response.status().as_u16(), 400);
+
+	let response = app.patch_profile(&serde_json::json!({"displayName": "Jane"})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_empty_profile_after_signup() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in(&app, &email, false).await;
+
+	let response = app.get_profile().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let profile = response.json::<ProfileResponse>().await.unwrap();
+	assert_eq!(profile.email, email);
+	assert!(!profile.email_verified);
+	assert!(profile.display_name.is_none());
+	assert!(profile.locale.is_none());
+	assert!(profile.avatar_url.is_none());
+}
+
+#[tokio::test]
+async fn should_update_and_clear_profile_fields() {
+	let app = TestApp::new().await;
+	log_in(&app, &get_random_email(), false).await;
+
+	let update = serde_json::json!({
+		"displayName": "Jane Doe",
+		"locale": "en-US",
+		"avatarUrl": "https://example.com/jane.png",
+	});
+	let response = app.patch_profile(&update).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let profile = response.json::<ProfileResponse>().await.unwrap();
+	assert_eq!(profile.display_name.as_deref(), Some("Jane Doe"));
+	assert_eq!(profile.avatar_url.as_deref(), Some("https://example.com/jane.png"));
+
+	let response = app.patch_profile(&serde_json::json!({"avatarUrl": ""})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let profile = app.get_profile().await.json::<ProfileResponse>().await.unwrap();
+	assert_eq!(profile.display_name.as_deref(), Some("Jane Doe"));
+	assert_eq!(profile.locale.as_deref(), Some("en-US"));
+	assert!(profile.avatar_url.is_none());
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_profile() {
+	let app = TestApp::new().await;
+	log_in(&app, &get_random_email(), false).await;
+
+	let test_cases = [
+		serde_json::json!({ "displayName": " " }),
+		serde_json::json!({ "displayName": "a".repeat(101) }),
+		serde_json::json!({ "locale": "en_US" }),
+		serde_json::json!({ "avatarUrl": "javascript:alert(1)" }),
+	];
+
+	for test_case in test_cases.iter() {
+		let response = app.patch_profile(test_case).await;
+		assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", test_case);
+	}
+}
+
+#[tokio::test]
+async fn should_mark_email_verified_after_2fa() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in(&app, &email, true).await;
+
+	let (login_attempt_id, code) = {
+		let two_fa_code_store = app.two_fa_code_store.read().await;
+		two_fa_code_store.get_code(&Email::from_str(&email).unwrap()).await.unwrap()
+	};
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id.as_ref(), "2FACode": code.as_ref()});
+	let response = app.post_verify_2fa(&payload).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	let profile = app.get_profile().await.json::<ProfileResponse>().await.unwrap();
+	assert!(profile.email_verified);
+}
+
+#[tokio::test]
+async fn should_accept_bearer_token_before_cookie() {
+	let app = TestApp::new().await;
+	let cookie_email = get_random_email();
+	log_in(&app, &cookie_email, false).await;
+	let bearer_email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": bearer_email, "password": "password123", "requires2FA": false})).await;
+	let token = app.auth_token_for(&serde_json::json!({"email": bearer_email, "password": "password123"})).await;
+
+	// The cookie is only used when there's no bearer token
+	let response = app.http_client
+		.get(format!("{}/profile", app.address))
+		.bearer_auth(&token)
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<ProfileResponse>().await.unwrap().email, bearer_email);
+
+	// Changes authenticated by a bearer token don't need a CSRF token
+	let response = reqwest::Client::new()
+		.patch(format!("{}/profile", app.address))
+		.bearer_auth(&token)
+		.json(&serde_json::json!({"displayName": "Jane"}))
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_403_if_account_no_longer_active() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in(&app, &email, false).await;
+
+	// The session is still valid, but the account it belongs to isn't
+	app.set_account_status(&email, AccountStatus::Disabled).await;
+	let response = app.get_profile().await;
+	assert_eq!(response.status().as_u16(), 403);
+
+	app.set_account_status(&email, AccountStatus::Active).await;
+	let response = app.get_profile().await;
+	assert_eq!(response.status().as_u16(), 200);
+}
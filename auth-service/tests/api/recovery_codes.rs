@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use auth_service::{Email, RecoveryCodesResponse, RecoveryCodesStatusResponse, SignupResponse, TwoFactorAuthResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Sign up a 2FA user and return the recovery codes shown at signup
+async fn sign_up(app: &TestApp, email: &str) -> Vec<String> {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": true});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	response.json::<SignupResponse>().await.unwrap().recovery_codes.expect("Missing recovery codes")
+}
+
+async fn start_login(app: &TestApp, email: &str) -> String {
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 206);
+	response.json::<TwoFactorAuthResponse>().await.unwrap().login_attempt_id
+}
+
+async fn verify_with(app: &TestApp, email: &str, login_attempt_id: &str, code: &str) -> u16 {
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": code});
+	app.post_verify_2fa(&payload).await.status().as_u16()
+}
+
+#[tokio::test]
+async fn should_only_return_codes_for_2fa_users() {
+	let app = TestApp::new().await;
+
+	let codes = sign_up(&app, &get_random_email()).await;
+	assert_eq!(codes.len(), 10);
+
+	let user_payload = serde_json::json!({"email": get_random_email(), "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert!(response.json::<SignupResponse>().await.unwrap().recovery_codes.is_none());
+}
+
+#[tokio::test]
+async fn should_log_in_with_recovery_code_once() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let codes = sign_up(&app, &email).await;
+
+	let login_attempt_id = start_login(&app, &email).await;
+	// Codes are accepted regardless of case
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &codes[0].to_uppercase()).await, 200);
+
+	let login_attempt_id = start_login(&app, &email).await;
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &codes[0]).await, 401);
+
+	let response = app.get_recovery_codes().await;
+	assert_eq!(response.json::<RecoveryCodesStatusResponse>().await.unwrap().remaining, 9);
+}
+
+#[tokio::test]
+async fn should_send_alert_email_when_recovery_code_used() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let codes = sign_up(&app, &email).await;
+
+	let login_attempt_id = start_login(&app, &email).await;
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &codes[3]).await, 200);
+
+	let alert = app.email_client.sent_emails().pop().unwrap();
+	assert_eq!(alert.recipient, email);
+	assert!(alert.subject.contains("Recovery code used"));
+	assert!(alert.content.contains("9 recovery codes left"));
+}
+
+#[tokio::test]
+async fn should_require_login_attempt_for_recovery_code() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let codes = sign_up(&app, &email).await;
+	start_login(&app, &email).await;
+
+	let wrong_attempt_id = uuid::Uuid::new_v4().to_string();
+	assert_eq!(verify_with(&app, &email, &wrong_attempt_id, &codes[0]).await, 401);
+
+	// The code was not consumed by the failed attempt
+	let (login_attempt_id, _) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(&email).unwrap()).await
+		.unwrap();
+	assert_eq!(verify_with(&app, &email, login_attempt_id.as_ref(), &codes[0]).await, 200);
+}
+
+#[tokio::test]
+async fn should_regenerate_recovery_codes() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let old_codes = sign_up(&app, &email).await;
+	let login_attempt_id = start_login(&app, &email).await;
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &old_codes[0]).await, 200);
+
+	let response = app.post_recovery_codes().await;
+	assert_eq!(response.status().as_u16(), 200);
+	let new_codes = response.json::<RecoveryCodesResponse>().await.unwrap().recovery_codes;
+	assert_eq!(new_codes.len(), 10);
+
+	let login_attempt_id = start_login(&app, &email).await;
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &old_codes[1]).await, 401);
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &new_codes[0]).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_2fa_not_enabled() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": false});
+	app.post_signup(&user_payload).await;
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 204);
+
+	assert_eq!(app.post_recovery_codes().await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+	let app = TestApp::new().await;
+
+	assert_eq!(app.get_recovery_codes().await.status().as_u16(), 400);
+	assert_eq!(app.post_recovery_codes().await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_for_oauth_access_token() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let codes = sign_up(&app, &email).await;
+	let login_attempt_id = start_login(&app, &email).await;
+	assert_eq!(verify_with(&app, &email, &login_attempt_id, &codes[0]).await, 200);
+
+	// Clients the user consented to can read their claims, but not manage their account
+	let access_token = app.get_oauth_tokens("openid email").await.access_token;
+	let response = app.http_client
+		.get(format!("{}/recovery-codes", app.address))
+		.bearer_auth(&access_token)
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 401);
+	let response = app.http_client
+		.post(format!("{}/recovery-codes", app.address))
+		.bearer_auth(&access_token)
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 401);
+
+	assert_eq!(app.get_userinfo(&access_token).await.status().as_u16(), 200);
+}
@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use auth_service::{AppState, Email, Resend2FAPolicy, TwoFactorAuthResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn app_without_cooldown() -> TestApp {
+	let policy = Resend2FAPolicy {
+		cooldown: chrono::Duration::zero(),
+		max_resends: 2,
+	};
+	TestApp::with_state(AppState::default().with_resend_2fa_policy(policy)).await
+}
+
+// Sign up a 2FA user, start logging in and return the login attempt ID
+async fn start_login(app: &TestApp, email: &str) -> String {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": true});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 206);
+	response.json::<TwoFactorAuthResponse>().await.unwrap().login_attempt_id
+}
+
+#[tokio::test]
+async fn should_resend_new_code_for_same_login_attempt() {
+	let app = app_without_cooldown().await;
+	let email = get_random_email();
+	let login_attempt_id = start_login(&app, &email).await;
+	let (_, old_code) = app.two_fa_code_store.read().await.get_code(&Email::from_str(&email).unwrap()).await.unwrap();
+
+	let response = app.post_resend_2fa(&serde_json::json!({"email": email, "loginAttemptId": login_attempt_id})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<TwoFactorAuthResponse>().await.unwrap().login_attempt_id, login_attempt_id);
+
+	let (_, new_code) = app.two_fa_code_store.read().await.get_code(&Email::from_str(&email).unwrap()).await.unwrap();
+	let email_content = app.email_client.sent_emails().pop().unwrap().content;
+	assert!(email_content.contains(new_code.as_ref()));
+
+	// Only the latest code is accepted
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": old_code.as_ref()});
+	if old_code != new_code {
+		assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 401);
+	}
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": new_code.as_ref()});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_429_during_cooldown() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	let login_attempt_id = start_login(&app, &email).await;
+
+	let response = app.post_resend_2fa(&serde_json::json!({"email": email, "loginAttemptId": login_attempt_id})).await;
+	assert_eq!(response.status().as_u16(), 429);
+}
+
+#[tokio::test]
+async fn should_return_429_after_max_resends() {
+	let app = app_without_cooldown().await;
+	let email = get_random_email();
+	let login_attempt_id = start_login(&app, &email).await;
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id});
+
+	for _ in 0..2 {
+		assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 200);
+	}
+	assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 429);
+}
+
+#[tokio::test]
+async fn should_start_over_after_logging_in_again() {
+	let app = app_without_cooldown().await;
+	let email = get_random_email();
+	let login_attempt_id = start_login(&app, &email).await;
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login_attempt_id});
+	for _ in 0..2 {
+		assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 200);
+	}
+	assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 429);
+
+	// Logging in again, like the 429 says, replaces the capped attempt
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 206);
+	let new_login_attempt_id = response.json::<TwoFactorAuthResponse>().await.unwrap().login_attempt_id;
+	assert_ne!(new_login_attempt_id, login_attempt_id);
+	let payload = serde_json::json!({"email": email, "loginAttemptId": new_login_attempt_id});
+	assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 200);
+
+	let (_, code) = app.two_fa_code_store.read().await.get_code(&Email::from_str(&email).unwrap()).await.unwrap();
+	let payload = serde_json::json!({"email": email, "loginAttemptId": new_login_attempt_id, "2FACode": code.as_ref()});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_for_unknown_login_attempt() {
+	let app = app_without_cooldown().await;
+	let email = get_random_email();
+	start_login(&app, &email).await;
+
+	let payload = serde_json::json!({"email": email, "loginAttemptId": uuid::Uuid::new_v4().to_string()});
+	assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 401);
+
+	let payload = serde_json::json!({"email": get_random_email(), "loginAttemptId": uuid::Uuid::new_v4().to_string()});
+	assert_eq!(app.post_resend_2fa(&payload).await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_input() {
+	let app = TestApp::new().await;
+
+	let test_cases = [
+		serde_json::json!({"email": "invalid", "loginAttemptId": uuid::Uuid::new_v4().to_string()}),
+		serde_json::json!({"email": get_random_email(), "loginAttemptId": "invalid"}),
+	];
+	for test_case in test_cases.iter() {
+		let response = app.post_resend_2fa(test_case).await;
+		assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", test_case);
+	}
+}
@@ -0,0 +1,179 @@
+use auth_service::{AppState, Config, Role, SignupResponse, TwoFactorAuthResponse, UserRolesResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn signup(app: &TestApp, email: &str) {
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+}
+
+async fn login(app: &TestApp, email: &str) {
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+}
+
+// Sign up an admin and log in as them
+async fn login_as_admin(app: &TestApp) -> String {
+	let email = get_random_email();
+	signup(app, &email).await;
+	app.grant_role(&email, Role::Admin).await;
+	login(app, &email).await;
+	email
+}
+
+// An app whose config makes `email` the bootstrap admin
+async fn app_with_admin_email(email: &str) -> TestApp {
+	let mut config = Config::default();
+	config.auth.admin_emails = vec![email.to_string()];
+	TestApp::with_state(AppState::default().with_config(config)).await
+}
+
+async fn roles(app: &TestApp, email: &str) -> Vec<Role> {
+	app.user_store.read().await.get_user(email.parse().unwrap()).await.unwrap().roles
+}
+
+// Log in a 2FA user with the given second factor, or the emailed code if there's none
+async fn login_with_2fa(app: &TestApp, email: &str, second_factor: Option<&str>) {
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	let login = response.json::<TwoFactorAuthResponse>().await.unwrap();
+	let code = match second_factor {
+		Some(code) => code.to_string(),
+		None => app.two_fa_code_store.read().await.get_code(&email.parse().unwrap()).await.unwrap().1.as_ref().to_string(),
+	};
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login.login_attempt_id, "2FACode": code});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 200, "Failed to verify 2FA");
+}
+
+#[tokio::test]
+async fn should_grant_admin_role_once_admin_email_is_verified() {
+	let email = get_random_email();
+	let app = app_with_admin_email(&email).await;
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	assert_eq!(response.status().as_u16(), 201);
+	assert!(roles(&app, &email).await.is_empty());
+
+	login_with_2fa(&app, &email, None).await;
+	assert_eq!(roles(&app, &email).await, vec![Role::Admin]);
+	// The first login already carries the role's permissions
+	assert_eq!(app.get_user_roles(&email).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_not_grant_admin_role_without_verified_email() {
+	let email = get_random_email();
+	let app = app_with_admin_email(&email).await;
+
+	let response = app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	let recovery_codes = response.json::<SignupResponse>().await.unwrap().recovery_codes.unwrap();
+	// Whoever signed up gets the recovery codes, so they don't prove access to the mailbox
+	login_with_2fa(&app, &email, Some(&recovery_codes[0])).await;
+
+	assert!(roles(&app, &email).await.is_empty());
+	assert_eq!(app.get_user_roles(&email).await.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+	let app = TestApp::new().await;
+
+	let response = app.get_user_roles(&get_random_email()).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_403_without_permission() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	login(&app, &email).await;
+
+	let response = app.get_user_roles(&email).await;
+	assert_eq!(response.status().as_u16(), 403);
+	let response = app.put_user_roles(&email, &serde_json::json!({"roles": ["admin"]})).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_let_admin_assign_roles() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.put_user_roles(&email, &serde_json::json!({"roles": ["support"]})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	let roles = response.json::<UserRolesResponse>().await.unwrap();
+	assert_eq!(roles.roles, vec![Role::Support]);
+	assert_eq!(roles.permissions, vec!["users:read", "users:impersonate", "audit:read"]);
+
+	let response = app.get_user_roles(&email).await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<UserRolesResponse>().await.unwrap().roles, vec![Role::Support]);
+}
+
+#[tokio::test]
+async fn should_carry_new_roles_from_next_login() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+	login(&app, &email).await;
+
+	// Permissions live in the token, so granting a role doesn't change the current one
+	app.grant_role(&email, Role::Support).await;
+	let response = app.get_user_roles(&email).await;
+	assert_eq!(response.status().as_u16(), 403);
+
+	login(&app, &email).await;
+	let response = app.get_user_roles(&email).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	// Support can look but not change roles
+	let response = app.put_user_roles(&email, &serde_json::json!({"roles": ["admin"]})).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_accept_bearer_token() {
+	let app = TestApp::new().await;
+	let admin = login_as_admin(&app).await;
+
+	let token = app.auth_token();
+
+	let response = reqwest::Client::new()
+		.get(format!("{}/admin/users/{}/roles", app.address, admin))
+		.bearer_auth(token)
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_400_for_unknown_role() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+	let email = get_random_email();
+	signup(&app, &email).await;
+
+	let response = app.put_user_roles(&email, &serde_json::json!({"roles": ["superuser"]})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_404_for_unknown_user() {
+	let app = TestApp::new().await;
+	login_as_admin(&app).await;
+
+	let response = app.put_user_roles(&get_random_email(), &serde_json::json!({"roles": []})).await;
+	assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_not_let_admin_remove_own_role_management() {
+	let app = TestApp::new().await;
+	let admin = login_as_admin(&app).await;
+
+	let response = app.put_user_roles(&admin, &serde_json::json!({"roles": ["support"]})).await;
+	assert_eq!(response.status().as_u16(), 400);
+}
@@ -9,3 +9,25 @@ async fn login() {
 	assert_eq!(response.status().as_u16(), 200);
 	assert_eq!(response.headers().get("content-type").unwrap(), "text/html");
 }
+
+#[tokio::test]
+async fn should_return_a_request_id() {
+	let app = TestApp::new().await;
+
+	let response = app.get_root().await;
+	let request_id = response.headers().get("x-request-id").unwrap().to_str().unwrap();
+	assert!(uuid::Uuid::parse_str(request_id).is_ok());
+}
+
+#[tokio::test]
+async fn should_keep_the_callers_request_id() {
+	let app = TestApp::new().await;
+
+	let response = app.http_client
+		.get(&app.address)
+		.header("x-request-id", "trace-me")
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(response.headers().get("x-request-id").unwrap(), "trace-me");
+}
@@ -0,0 +1,134 @@
+use auth_service::SessionResponse;
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn sign_up(app: &TestApp, email: &str) {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+}
+
+// Log in and return the new auth token
+async fn log_in(app: &TestApp, email: &str) -> String {
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+
+	app.auth_token()
+}
+
+async fn token_is_valid(app: &TestApp, token: &str) -> bool {
+	let response = app.post_verify_token(&serde_json::json!({"token": token})).await;
+	response.status().as_u16() == 200
+}
+
+async fn sessions(app: &TestApp) -> Vec<SessionResponse> {
+	let response = app.get_sessions().await;
+	assert_eq!(response.status().as_u16(), 200);
+	response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+	let app = TestApp::new().await;
+
+	assert_eq!(app.get_sessions().await.status().as_u16(), 400);
+	assert_eq!(app.delete_sessions().await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_list_each_login_as_a_session() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in(&app, &email).await;
+	log_in(&app, &email).await;
+
+	let sessions = sessions(&app).await;
+	assert_eq!(sessions.len(), 2);
+	assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+	assert!(sessions.iter().all(|session| session.ip.as_deref() == Some("127.0.0.1")));
+}
+
+#[tokio::test]
+async fn should_not_list_other_users_sessions() {
+	let app = TestApp::new().await;
+	let other = get_random_email();
+	sign_up(&app, &other).await;
+	log_in(&app, &other).await;
+
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in(&app, &email).await;
+
+	assert_eq!(sessions(&app).await.len(), 1);
+}
+
+#[tokio::test]
+async fn should_revoke_a_single_session() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	let old_token = log_in(&app, &email).await;
+	let current_token = log_in(&app, &email).await;
+
+	let old_session = sessions(&app).await.into_iter().find(|session| !session.current).unwrap();
+	let response = app.delete_session(&old_session.id).await;
+	assert_eq!(response.status().as_u16(), 204);
+
+	assert!(!token_is_valid(&app, &old_token).await);
+	assert!(token_is_valid(&app, &current_token).await);
+	assert_eq!(sessions(&app).await.len(), 1);
+}
+
+#[tokio::test]
+async fn should_return_404_for_unknown_session() {
+	let app = TestApp::new().await;
+	let other = get_random_email();
+	sign_up(&app, &other).await;
+	log_in(&app, &other).await;
+	let other_session = sessions(&app).await.remove(0);
+
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in(&app, &email).await;
+
+	let response = app.delete_session(&other_session.id).await;
+	assert_eq!(response.status().as_u16(), 404);
+
+	let response = app.delete_session("not-a-session").await;
+	assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_log_out_everywhere() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	let first_token = log_in(&app, &email).await;
+	let second_token = log_in(&app, &email).await;
+	let oauth_token = app.get_oauth_tokens("openid").await.access_token;
+
+	let response = app.delete_sessions().await;
+	assert_eq!(response.status().as_u16(), 204);
+
+	for token in [first_token, second_token, oauth_token] {
+		assert!(!token_is_valid(&app, &token).await);
+	}
+	assert_eq!(app.get_sessions().await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_end_session_on_logout() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in(&app, &email).await;
+	log_in(&app, &email).await;
+
+	let response = app.post_logout().await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	log_in(&app, &email).await;
+	assert_eq!(sessions(&app).await.len(), 2);
+}
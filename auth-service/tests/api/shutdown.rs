@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use auth_service::{AppState, TwoFactorAuthResponse, WebhookClient};
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::Router;
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Local stand-in for the user's 2FA webhook that takes a while to answer
+async fn spawn_slow_webhook(delay: Duration) -> String {
+	let router = Router::new().route("/2fa", post(move || async move {
+		tokio::time::sleep(delay).await;
+		StatusCode::OK
+	}));
+
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}/2fa", listener.local_addr().unwrap());
+	tokio::spawn(async move { axum::serve(listener, router).await });
+
+	url
+}
+
+#[tokio::test]
+async fn should_stop_accepting_connections_after_shutdown() {
+	let app = TestApp::new().await;
+	assert_eq!(app.get_health_live().await.status().as_u16(), 200);
+	let address = app.address.clone();
+
+	app.shutdown.shutdown();
+	app.stopped().await.expect("Server failed");
+
+	let response = reqwest::Client::new().get(format!("{address}/health/live")).send().await;
+	assert!(response.is_err(), "Server still answering after shutdown");
+}
+
+#[tokio::test]
+async fn should_finish_requests_in_flight() {
+	let app = TestApp::with_state(AppState::default().with_webhook_client(WebhookClient::allowing_local_webhooks())).await;
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": true})).await;
+	let login = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+	let login = login.json::<TwoFactorAuthResponse>().await.unwrap();
+	let code = app.two_fa_code_store.read().await.get_code(&email.parse().unwrap()).await.unwrap().1;
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login.login_attempt_id, "2FACode": code.as_ref()});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 200);
+
+	let url = spawn_slow_webhook(Duration::from_millis(500)).await;
+	let response = app.put_two_fa_channel(&serde_json::json!({"channel": "webhook", "webhookUrl": url})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	// The login waits on the webhook while the app is told to shut down
+	let http_client = app.http_client.clone();
+	let address = app.address.clone();
+	let login = tokio::spawn(async move {
+		http_client
+			.post(format!("{address}/login"))
+			.json(&serde_json::json!({"email": email, "password": "password123"}))
+			.send()
+			.await
+	});
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	app.shutdown.shutdown();
+
+	let response = login.await.unwrap().expect("Request in flight was cut off");
+	assert_eq!(response.status().as_u16(), 206);
+	app.stopped().await.expect("Server failed");
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use auth_service::{AppState, Application, Config, TlsConfig};
+use uuid::Uuid;
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Writes a new self-signed certificate for the test server to the configured files and returns its PEM
+fn write_certificate(tls: &TlsConfig) -> String {
+	let certified_key = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_string()]).unwrap();
+	let cert = certified_key.cert.pem();
+	std::fs::write(&tls.cert_path, &cert).unwrap();
+	std::fs::write(&tls.key_path, certified_key.key_pair.serialize_pem()).unwrap();
+
+	cert
+}
+
+fn tls_config() -> TlsConfig {
+	let dir = std::env::temp_dir().join(format!("auth-service-tls-{}", Uuid::new_v4()));
+	std::fs::create_dir_all(&dir).unwrap();
+
+	TlsConfig {
+		cert_path: dir.join("cert.pem"),
+		key_path: dir.join("key.pem"),
+		reload_interval_seconds: 1,
+	}
+}
+
+fn config_with_tls(tls: TlsConfig) -> Config {
+	let mut config = Config::default();
+	config.server.tls = Some(tls);
+	config
+}
+
+#[tokio::test]
+async fn should_serve_https_with_secure_cookies() {
+	let tls = tls_config();
+	write_certificate(&tls);
+	let app = TestApp::with_state(AppState::default().with_config(config_with_tls(tls))).await;
+	assert!(app.address.starts_with("https://"));
+
+	let email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": email, "password": "password123", "requires2FA": false})).await;
+	let response = app.post_login(&serde_json::json!({"email": email, "password": "password123"})).await;
+
+	assert_eq!(response.status().as_u16(), 204);
+	let cookie = response.headers().get("set-cookie").unwrap().to_str().unwrap();
+	assert!(cookie.contains("Secure"), "Cookie isn't secure: {cookie}");
+}
+
+#[tokio::test]
+async fn should_not_answer_plain_http_when_serving_https() {
+	let tls = tls_config();
+	write_certificate(&tls);
+	let app = TestApp::with_state(AppState::default().with_config(config_with_tls(tls))).await;
+
+	let address = app.address.replace("https://", "http://");
+	let response = reqwest::Client::new().get(format!("{address}/health/live")).send().await;
+
+	assert!(response.is_err());
+}
+
+#[tokio::test]
+async fn should_pick_up_a_renewed_certificate() {
+	let tls = tls_config();
+	write_certificate(&tls);
+	let app = TestApp::with_state(AppState::default().with_config(config_with_tls(tls.clone()))).await;
+	assert_eq!(app.get_health_live().await.status().as_u16(), 200);
+
+	let renewed = write_certificate(&tls);
+	let client = reqwest::Client::builder()
+		.add_root_certificate(reqwest::Certificate::from_pem(renewed.as_bytes()).unwrap())
+		.build()
+		.unwrap();
+
+	// Only trusts the renewed certificate, so requests fail until it's been swapped in
+	let mut reloaded = false;
+	for _ in 0..50 {
+		if client.get(format!("{}/health/live", app.address)).send().await.is_ok() {
+			reloaded = true;
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(100)).await;
+	}
+	assert!(reloaded, "Renewed certificate wasn't picked up");
+}
+
+#[tokio::test]
+async fn should_fail_to_build_without_the_certificate() {
+	let tls = TlsConfig {
+		cert_path: PathBuf::from("missing/cert.pem"),
+		key_path: PathBuf::from("missing/key.pem"),
+		reload_interval_seconds: 60,
+	};
+	let config = config_with_tls(tls);
+
+	let app = Application::build(AppState::default(), &config.server).await;
+
+	assert!(app.is_err());
+}
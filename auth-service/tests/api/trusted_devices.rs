@@ -0,0 +1,128 @@
+use std::str::FromStr;
+
+use auth_service::{Email, TrustedDeviceResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn sign_up(app: &TestApp, email: &str) {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": true});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+}
+
+// Log in and return the status code, 204 if 2FA was skipped and 206 if it's required
+async fn log_in(app: &TestApp, email: &str) -> u16 {
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	app.post_login(&login_payload).await.status().as_u16()
+}
+
+async fn log_in_with_2fa(app: &TestApp, email: &str, remember_device: bool) {
+	assert_eq!(log_in(app, email).await, 206);
+	let (login_attempt_id, code) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(email).unwrap()).await
+		.unwrap();
+
+	let payload = serde_json::json!({
+		"email": email,
+		"loginAttemptId": login_attempt_id.as_ref(),
+		"2FACode": code.as_ref(),
+		"rememberDevice": remember_device,
+	});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 200);
+}
+
+async fn trusted_devices(app: &TestApp) -> Vec<TrustedDeviceResponse> {
+	let response = app.get_trusted_devices().await;
+	assert_eq!(response.status().as_u16(), 200);
+	response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_skip_2fa_on_remembered_device() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in_with_2fa(&app, &email, true).await;
+
+	assert_eq!(app.post_logout().await.status().as_u16(), 200);
+	assert_eq!(log_in(&app, &email).await, 204);
+}
+
+#[tokio::test]
+async fn should_require_2fa_without_remember_device() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in_with_2fa(&app, &email, false).await;
+
+	assert!(trusted_devices(&app).await.is_empty());
+	assert_eq!(app.post_logout().await.status().as_u16(), 200);
+	assert_eq!(log_in(&app, &email).await, 206);
+}
+
+#[tokio::test]
+async fn should_not_skip_2fa_for_other_users() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in_with_2fa(&app, &email, true).await;
+
+	let other = get_random_email();
+	sign_up(&app, &other).await;
+	assert_eq!(log_in(&app, &other).await, 206);
+}
+
+#[tokio::test]
+async fn should_list_and_revoke_trusted_device() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in_with_2fa(&app, &email, true).await;
+
+	let devices = trusted_devices(&app).await;
+	assert_eq!(devices.len(), 1);
+	assert!(devices[0].current);
+	assert!(devices[0].expires_at > devices[0].created_at);
+
+	let response = app.delete_trusted_device(&devices[0].id).await;
+	assert_eq!(response.status().as_u16(), 204);
+	assert!(trusted_devices(&app).await.is_empty());
+
+	assert_eq!(app.post_logout().await.status().as_u16(), 200);
+	assert_eq!(log_in(&app, &email).await, 206);
+}
+
+#[tokio::test]
+async fn should_revoke_all_trusted_devices() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in_with_2fa(&app, &email, true).await;
+	assert_eq!(trusted_devices(&app).await.len(), 1);
+
+	let response = app.delete_trusted_devices().await;
+	assert_eq!(response.status().as_u16(), 204);
+	assert!(trusted_devices(&app).await.is_empty());
+
+	assert_eq!(app.post_logout().await.status().as_u16(), 200);
+	assert_eq!(log_in(&app, &email).await, 206);
+}
+
+#[tokio::test]
+async fn should_return_404_for_unknown_device() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	sign_up(&app, &email).await;
+	log_in_with_2fa(&app, &email, false).await;
+
+	let response = app.delete_trusted_device(&uuid::Uuid::new_v4().to_string()).await;
+	assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+	let app = TestApp::new().await;
+
+	assert_eq!(app.get_trusted_devices().await.status().as_u16(), 400);
+	assert_eq!(app.delete_trusted_devices().await.status().as_u16(), 400);
+}
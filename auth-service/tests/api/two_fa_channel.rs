@@ -0,0 +1,150 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use auth_service::{AppState, Email, TwoFAChannelBody, TwoFactorAuthResponse, WebhookClient};
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Sign up a 2FA user and log them in through the default email channel
+async fn log_in_2fa_user(app: &TestApp, email: &str) {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": true});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+
+	let login = start_login(app, email).await;
+	assert_eq!(login.channel, "email");
+	let code = pending_code(app, email).await;
+	let payload = serde_json::json!({"email": email, "loginAttemptId": login.login_attempt_id, "2FACode": code});
+	assert_eq!(app.post_verify_2fa(&payload).await.status().as_u16(), 200);
+}
+
+async fn start_login(app: &TestApp, email: &str) -> TwoFactorAuthResponse {
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 206);
+	response.json().await.unwrap()
+}
+
+async fn pending_code(app: &TestApp, email: &str) -> String {
+	let (_, code) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(email).unwrap()).await
+		.unwrap();
+	code.as_ref().to_string()
+}
+
+// The webhooks these tests spawn listen on the loopback interface, which the app refuses by default
+async fn app_with_local_webhooks() -> TestApp {
+	TestApp::with_state(AppState::default().with_webhook_client(WebhookClient::allowing_local_webhooks())).await
+}
+
+// Local stand-in for the user's webhook that records the payloads it receives
+async fn spawn_webhook(status: StatusCode) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+	let received = Arc::new(Mutex::new(Vec::new()));
+	let recorder = received.clone();
+	let router = Router::new().route("/2fa", post(move |Json(body): Json<serde_json::Value>| async move {
+		recorder.lock().unwrap().push(body);
+		status
+	}));
+
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}/2fa", listener.local_addr().unwrap());
+	tokio::spawn(async move { axum::serve(listener, router).await });
+
+	(url, received)
+}
+
+#[tokio::test]
+async fn should_default_to_email() {
+	let app = TestApp::new().await;
+	log_in_2fa_user(&app, &get_random_email()).await;
+
+	let response = app.get_two_fa_channel().await;
+	assert_eq!(response.status().as_u16(), 200);
+	let channel = response.json::<TwoFAChannelBody>().await.unwrap();
+	assert_eq!(channel.channel, "email");
+	assert!(channel.phone_number.is_none());
+}
+
+#[tokio::test]
+async fn should_send_code_by_sms() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in_2fa_user(&app, &email).await;
+
+	let response = app.put_two_fa_channel(&serde_json::json!({"channel": "sms", "phoneNumber": "+30 691 234 5678"})).await;
+	assert_eq!(response.status().as_u16(), 200);
+	assert_eq!(response.json::<TwoFAChannelBody>().await.unwrap().phone_number.as_deref(), Some("+306912345678"));
+
+	app.post_logout().await;
+	let login = start_login(&app, &email).await;
+	assert_eq!(login.channel, "sms");
+
+	let sms = app.sms_client.sent_messages().pop().expect("No SMS sent");
+	assert_eq!(sms.recipient, "+306912345678");
+	assert!(sms.content.contains(&pending_code(&app, &email).await));
+}
+
+#[tokio::test]
+async fn should_send_code_to_webhook() {
+	let app = app_with_local_webhooks().await;
+	let email = get_random_email();
+	log_in_2fa_user(&app, &email).await;
+	let (url, received) = spawn_webhook(StatusCode::OK).await;
+
+	let response = app.put_two_fa_channel(&serde_json::json!({"channel": "webhook", "webhookUrl": url})).await;
+	assert_eq!(response.status().as_u16(), 200);
+
+	app.post_logout().await;
+	let login = start_login(&app, &email).await;
+	assert_eq!(login.channel, "webhook");
+
+	let code = pending_code(&app, &email).await;
+	assert_eq!(received.lock().unwrap().clone(), vec![serde_json::json!({"email": email, "code": code})]);
+}
+
+#[tokio::test]
+async fn should_return_502_if_delivery_fails() {
+	let app = app_with_local_webhooks().await;
+	let email = get_random_email();
+	log_in_2fa_user(&app, &email).await;
+	let (url, _) = spawn_webhook(StatusCode::INTERNAL_SERVER_ERROR).await;
+	app.put_two_fa_channel(&serde_json::json!({"channel": "webhook", "webhookUrl": url})).await;
+	app.post_logout().await;
+
+	// The failed attempt is dropped, so logging in again fails the same way instead of getting stuck
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 502);
+	assert_eq!(app.post_login(&login_payload).await.status().as_u16(), 502);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_channel() {
+	let app = TestApp::new().await;
+	log_in_2fa_user(&app, &get_random_email()).await;
+
+	let test_cases = [
+		serde_json::json!({"channel": "pigeon"}),
+		serde_json::json!({"channel": "sms"}),
+		serde_json::json!({"channel": "sms", "phoneNumber": "6912345678"}),
+		serde_json::json!({"channel": "webhook", "webhookUrl": "ftp://example.com"}),
+		serde_json::json!({"channel": "webhook", "webhookUrl": "http://example.com/2fa"}),
+		serde_json::json!({"channel": "webhook", "webhookUrl": "https://127.0.0.1/2fa"}),
+		serde_json::json!({"channel": "webhook", "webhookUrl": "https://169.254.169.254/latest/meta-data"}),
+		serde_json::json!({"channel": "webhook", "webhookUrl": "https://localhost/2fa"}),
+	];
+	for test_case in test_cases.iter() {
+		let response = app.put_two_fa_channel(test_case).await;
+		assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", test_case);
+	}
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+	let app = TestApp::new().await;
+
+	assert_eq!(app.get_two_fa_channel().await.status().as_u16(), 400);
+	assert_eq!(app.put_two_fa_channel(&serde_json::json!({"channel": "email"})).await.status().as_u16(), 400);
+}
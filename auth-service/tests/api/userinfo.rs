@@ -0,0 +1,88 @@
+use auth_service::{AccountStatus, UserInfoResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn log_in(app: &TestApp, email: &str) {
+	let user_payload = serde_json::json!({"email": email, "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+
+	let profile = serde_json::json!({"displayName": "Jane Doe", "locale": "el-GR"});
+	let response = app.patch_profile(&profile).await;
+	assert_eq!(response.status().as_u16(), 200, "Failed to update profile");
+}
+
+#[tokio::test]
+async fn should_return_400_if_missing_token() {
+	let app = TestApp::new().await;
+
+	let response = app.http_client
+		.get(format!("{}/userinfo", app.address))
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+	let app = TestApp::new().await;
+
+	let response = app.get_userinfo("invalid").await;
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_403_for_first_party_token() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in(&app, &email).await;
+
+	// Session tokens have no scope, so they aren't access tokens for any of the claims
+	let token = app.auth_token();
+
+	let response = app.get_userinfo(&token).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_filter_claims_by_scope() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in(&app, &email).await;
+
+	let test_cases = [
+		("openid", false, false),
+		("openid email", true, false),
+		("openid profile", false, true),
+		("openid email profile", true, true),
+	];
+
+	for (scope, has_email, has_profile) in test_cases {
+		let tokens = app.get_oauth_tokens(scope).await;
+		let response = app.get_userinfo(&tokens.access_token).await;
+		assert_eq!(response.status().as_u16(), 200, "Failed for scope: {scope}");
+
+		let userinfo = response.json::<UserInfoResponse>().await.unwrap();
+		assert_eq!(userinfo.sub, email, "Failed for scope: {scope}");
+		assert_eq!(userinfo.email.is_some(), has_email, "Failed for scope: {scope}");
+		assert_eq!(userinfo.email_verified.is_some(), has_email, "Failed for scope: {scope}");
+		assert_eq!(userinfo.name.is_some(), has_profile, "Failed for scope: {scope}");
+		assert_eq!(userinfo.locale.is_some(), has_profile, "Failed for scope: {scope}");
+	}
+}
+
+#[tokio::test]
+async fn should_return_403_if_account_no_longer_active() {
+	let app = TestApp::new().await;
+	let email = get_random_email();
+	log_in(&app, &email).await;
+	let tokens = app.get_oauth_tokens("openid email").await;
+
+	app.set_account_status(&email, AccountStatus::Disabled).await;
+	let response = app.get_userinfo(&tokens.access_token).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
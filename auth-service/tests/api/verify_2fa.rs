@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use auth_service::{Email, TwoFactorAuthResponse};
+use auth_service::{AccountStatus, Email, TwoFactorAuthResponse};
 
 use crate::helpers::{get_random_email, TestApp};
 
@@ -105,3 +105,24 @@ async fn should_return_401_if_old_code() {
 	let response = app.post_verify_2fa(&correct_2fa_code).await;
 	assert_eq!(response.status().as_u16(), 401);
 }
+
+#[tokio::test]
+async fn should_return_403_if_account_disabled_during_2fa() {
+	let app = TestApp::new().await;
+
+	let random_email = get_random_email();
+	let user_payload = serde_json::json!({"email": random_email, "password": "password123", "requires2FA": true});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": random_email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 206);
+
+	app.set_account_status(&random_email, AccountStatus::Disabled).await;
+	let (login_attempt_id, code) = app.two_fa_code_store.read().await
+		.get_code(&Email::from_str(&random_email).unwrap()).await
+		.unwrap();
+	let payload = serde_json::json!({"email": random_email, "loginAttemptId": login_attempt_id.as_ref(), "2FACode": code.as_ref()});
+	let response = app.post_verify_2fa(&payload).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
@@ -1,6 +1,6 @@
-use reqwest::cookie::CookieStore;
+use auth_service::AccountStatus;
 
-use crate::helpers::TestApp;
+use crate::helpers::{get_random_email, TestApp};
 
 #[tokio::test]
 async fn should_return_422_if_malformed_input() {
@@ -28,8 +28,7 @@ async fn should_return_200_valid_token() {
 	let response = app.post_login(&login_payload).await;
 	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
 
-	let header = app.cookie_jar.cookies(&"http://127.0.0.1".parse().unwrap()).unwrap();
-	let token = header.to_str().unwrap().split('=').collect::<Vec<&str>>()[1];
+	let token = app.auth_token();
 	let data = serde_json::json!({"token": token});
 	let response = app.post_verify_token(&data).await;
 	assert_eq!(response.status().as_u16(), 200);
@@ -55,8 +54,7 @@ async fn should_return_401_if_banned_token() {
 	let response = app.post_login(&login_payload).await;
 	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
 
-	let header = app.cookie_jar.cookies(&"http://127.0.0.1".parse().unwrap()).unwrap();
-	let token = header.to_str().unwrap().split('=').collect::<Vec<&str>>()[1];
+	let token = app.auth_token();
 	let data = serde_json::json!({"token": token});
 
 	let response = app.post_logout().await;
@@ -65,3 +63,95 @@ async fn should_return_401_if_banned_token() {
 	let response = app.post_verify_token(&data).await;
 	assert_eq!(response.status().as_u16(), 401);
 }
+
+#[tokio::test]
+async fn should_return_403_if_account_no_longer_active() {
+	let app = TestApp::new().await;
+
+	let random_email = get_random_email();
+	let user_payload = serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false});
+	let response = app.post_signup(&user_payload).await;
+	assert_eq!(response.status().as_u16(), 201, "Failed to create user");
+	let login_payload = serde_json::json!({"email": random_email, "password": "password123"});
+	let response = app.post_login(&login_payload).await;
+	assert_eq!(response.status().as_u16(), 204, "Failed to log in");
+
+	let token = app.auth_token();
+	let data = serde_json::json!({"token": token});
+
+	app.set_account_status(&random_email, AccountStatus::Disabled).await;
+	let response = app.post_verify_token(&data).await;
+	assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn should_verify_bearer_token_without_body() {
+	let app = TestApp::new().await;
+
+	let random_email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false})).await;
+	let token = app.auth_token_for(&serde_json::json!({"email": random_email, "password": "password123"})).await;
+
+	let response = app.http_client
+		.post(format!("{}/verify-token", app.address))
+		.bearer_auth(&token)
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+
+	let body = response.json::<serde_json::Value>().await.unwrap();
+	assert_eq!(body["email"], random_email);
+	assert_eq!(body["status"], "active");
+	assert_eq!(body["requires2FA"], false);
+	// The account's secrets and lockout state never leave the store
+	for field in ["password", "failed_login_attempts", "locked_until"] {
+		assert!(body.get(field).is_none(), "Response contains {field}: {body}");
+	}
+}
+
+#[tokio::test]
+async fn should_verify_cookie_without_body() {
+	let app = TestApp::new().await;
+
+	let random_email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false})).await;
+	app.post_login(&serde_json::json!({"email": random_email, "password": "password123"})).await;
+
+	let response = app.http_client
+		.post(format!("{}/verify-token", app.address))
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_prefer_body_token_over_request_credentials() {
+	let app = TestApp::new().await;
+
+	let random_email = get_random_email();
+	app.post_signup(&serde_json::json!({"email": random_email, "password": "password123", "requires2FA": false})).await;
+	let token = app.auth_token_for(&serde_json::json!({"email": random_email, "password": "password123"})).await;
+
+	let response = app.http_client
+		.post(format!("{}/verify-token", app.address))
+		.bearer_auth(&token)
+		.json(&serde_json::json!({"token": "invalid"}))
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_400_without_body_or_credentials() {
+	let app = TestApp::new().await;
+
+	let response = app.http_client
+		.post(format!("{}/verify-token", app.address))
+		.send()
+		.await
+		.expect("Failed to execute request.");
+	assert_eq!(response.status().as_u16(), 400);
+}